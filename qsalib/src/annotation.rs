@@ -0,0 +1,369 @@
+use std::fs;
+use std::path::Path;
+
+use crate::matrices::Matrices;
+use crate::error::{QSAError, Result};
+
+/// GFF3 feature types describing the whole sequence rather than a region of it.
+const WHOLE_SEQUENCE: [&str; 4] = ["region", "source", "chromosome", "databank_entry"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strand {
+    Forward,
+    Reverse,
+    Unknown,
+}
+
+impl Strand {
+    fn parse(symbol: &str) -> Strand {
+        match symbol {
+            "+" => Strand::Forward,
+            "-" => Strand::Reverse,
+            _ => Strand::Unknown,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Strand::Forward => "+",
+            Strand::Reverse => "-",
+            Strand::Unknown => ".",
+        }
+    }
+}
+
+/// An annotated gene, ORF or region, made of one or more segments of the reference.
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub name: String,
+    pub kind: String,
+    pub strand: Strand,
+    /// 0-based, half-open segments sorted by starting position.
+    pub segments: Vec<(usize, usize)>,
+    /// Bases to skip before the first codon, from the GFF3 phase of the segment translated first.
+    pub phase: usize,
+}
+
+impl Region {
+    pub fn start(&self) -> usize {
+        self.segments.first().map(|x| x.0).unwrap_or(0)
+    }
+
+    pub fn end(&self) -> usize {
+        self.segments.last().map(|x| x.1).unwrap_or(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.iter().map(|(start, end)| end - start).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reference positions of the region in the direction of its strand.
+    pub fn positions(&self) -> Vec<usize> {
+        let mut positions = self.segments.iter()
+            .flat_map(|(start, end)| *start..*end)
+            .collect::<Vec<usize>>();
+
+        if self.strand == Strand::Reverse {
+            positions.reverse();
+        }
+
+        positions
+    }
+
+    /// Columns of the matrices covered by the region, given the analysed range.
+    pub fn columns(&self, range: (i32, i32)) -> Vec<usize> {
+        let (start, end) = (range.0 as usize, range.1 as usize);
+
+        self.positions().into_iter()
+            .filter(|x| *x >= start && *x < end)
+            .map(|x| x - start)
+            .collect()
+    }
+}
+
+/// Regions read from a GFF3 or BED file.
+#[derive(Debug, Clone, Default)]
+pub struct Annotation {
+    regions: Vec<Region>,
+}
+
+impl Annotation {
+    /// Reads a GFF3 or a BED file, telling them apart from the extension.
+    pub fn from_path<P>(path: P) -> Result<Annotation>
+        where P: AsRef<Path>
+    {
+        let extension = path.as_ref()
+            .extension()
+            .map(|x| x.to_str().unwrap().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "bed" => Annotation::from_bed(path),
+            _ => Annotation::from_gff3(path),
+        }
+    }
+
+    /// Reads a GFF3 file. Lines sharing the same ID are joined into a single region.
+    pub fn from_gff3<P>(path: P) -> Result<Annotation>
+        where P: AsRef<Path>
+    {
+        let content = fs::read_to_string(path).map_err(|_| QSAError::AnnotationNotFound)?;
+
+        let mut regions: Vec<(Option<String>, Region)> = Vec::new();
+        for line in content.lines() {
+            if line.starts_with("##FASTA") {
+                break
+            }
+
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue
+            }
+
+            let fields = line.split('\t').collect::<Vec<&str>>();
+            if fields.len() < 9 {
+                return Err(QSAError::AnnotationMalformed);
+            }
+
+            let kind = fields[2].to_string();
+            if WHOLE_SEQUENCE.contains(&kind.as_str()) {
+                continue
+            }
+
+            let start = fields[3].parse::<usize>().map_err(|_| QSAError::AnnotationMalformed)?;
+            let end = fields[4].parse::<usize>().map_err(|_| QSAError::AnnotationMalformed)?;
+            if start == 0 || end < start {
+                return Err(QSAError::AnnotationMalformed);
+            }
+
+            let phase = match fields[7] {
+                "." | "0" => 0,
+                "1" => 1,
+                "2" => 2,
+                _ => return Err(QSAError::AnnotationMalformed),
+            };
+            let strand = Strand::parse(fields[6]);
+
+            let attribute = |key: &str| {
+                fields[8].split(';')
+                    .filter_map(|x| x.trim().split_once('='))
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| v.to_string())
+            };
+
+            let id = attribute("ID");
+            let segment = (start - 1, end);
+
+            if let Some(id) = &id {
+                let joined = regions.iter_mut()
+                    .find(|(other, region)| other.as_ref() == Some(id) && region.kind == kind);
+
+                if let Some((_, region)) = joined {
+                    // translation starts at the rightmost segment on the minus strand
+                    let first = match region.strand {
+                        Strand::Reverse => segment.1 > region.end(),
+                        _ => segment.0 < region.start(),
+                    };
+                    if first {
+                        region.phase = phase;
+                    }
+
+                    region.segments.push(segment);
+                    region.segments.sort_unstable();
+                    continue
+                }
+            }
+
+            let name = attribute("Name")
+                .or_else(|| attribute("gene"))
+                .or_else(|| id.clone())
+                .unwrap_or_else(|| format!("{}:{}-{}", kind, start, end));
+
+            regions.push((id, Region {
+                name,
+                kind,
+                strand,
+                segments: vec![segment],
+                phase,
+            }));
+        }
+
+        Ok(
+            Annotation {
+                regions: regions.into_iter().map(|(_, region)| region).collect(),
+            }
+        )
+    }
+
+    /// Reads a BED file, using the blocks of BED12 lines as segments.
+    ///
+    /// Lines with a thick part (thickStart < thickEnd) are taken as CDS features restricted to it.
+    pub fn from_bed<P>(path: P) -> Result<Annotation>
+        where P: AsRef<Path>
+    {
+        let content = fs::read_to_string(path).map_err(|_| QSAError::AnnotationNotFound)?;
+
+        let mut regions: Vec<Region> = Vec::new();
+        for line in content.lines() {
+            if line.starts_with('#') || line.starts_with("track") || line.starts_with("browser") || line.trim().is_empty() {
+                continue
+            }
+
+            let fields = line.split('\t').collect::<Vec<&str>>();
+            if fields.len() < 3 {
+                return Err(QSAError::AnnotationMalformed);
+            }
+
+            let start = fields[1].parse::<usize>().map_err(|_| QSAError::AnnotationMalformed)?;
+            let end = fields[2].parse::<usize>().map_err(|_| QSAError::AnnotationMalformed)?;
+            if end < start {
+                return Err(QSAError::AnnotationMalformed);
+            }
+
+            let mut segments: Vec<(usize, usize)> =
+                if fields.len() >= 12 {
+                    let parse = |field: &str| field.split(',')
+                        .filter(|x| !x.is_empty())
+                        .map(|x| x.parse::<usize>())
+                        .collect::<std::result::Result<Vec<usize>, _>>()
+                        .map_err(|_| QSAError::AnnotationMalformed);
+
+                    let (sizes, starts) = (parse(fields[10])?, parse(fields[11])?);
+
+                    starts.iter()
+                        .zip(sizes.iter())
+                        .map(|(s, l)| (start + s, start + s + l))
+                        .collect()
+                } else {
+                    vec![(start, end)]
+                };
+
+            let thick = match (fields.get(6).map(|x| x.parse::<usize>()), fields.get(7).map(|x| x.parse::<usize>())) {
+                (Some(Ok(thick_start)), Some(Ok(thick_end))) if thick_start < thick_end => Some((thick_start, thick_end)),
+                _ => None,
+            };
+
+            if let Some((thick_start, thick_end)) = thick {
+                segments = segments.into_iter()
+                    .map(|(s, e)| (s.max(thick_start), e.min(thick_end)))
+                    .filter(|(s, e)| s < e)
+                    .collect();
+            }
+
+            regions.push(Region {
+                name: fields.get(3)
+                    .map(|x| x.to_string())
+                    .unwrap_or_else(|| format!("{}-{}", start, end)),
+                kind: if thick.is_some() { "CDS" } else { "region" }.to_string(),
+                strand: Strand::parse(fields.get(5).unwrap_or(&".")),
+                segments,
+                phase: 0,
+            });
+        }
+
+        Ok(
+            Annotation {
+                regions,
+            }
+        )
+    }
+
+    pub fn get_regions(&self) -> &[Region] {
+        self.regions.as_slice()
+    }
+
+    /// Coding sequences, i.e. the regions of type `CDS`.
+    pub fn coding(&self) -> Vec<&Region> {
+        self.of_kind("CDS")
+    }
+
+    /// Regions of a given feature type, e.g. `gene` or `CDS`.
+    pub fn of_kind(&self, kind: &str) -> Vec<&Region> {
+        self.regions.iter().filter(|x| x.kind == kind).collect()
+    }
+
+    /// Regions overlapping a (0-based) reference position.
+    pub fn at(&self, position: usize) -> Vec<&Region> {
+        self.regions.iter()
+            .filter(|x| x.segments.iter().any(|(start, end)| position >= *start && position < *end))
+            .collect()
+    }
+}
+
+/// Diversity of a sample restricted to an annotated region.
+#[derive(Debug, Clone)]
+pub struct RegionSummary {
+    pub name: String,
+    pub kind: String,
+    pub strand: Strand,
+    /// Positions of the region inside the analysed range.
+    pub length: usize,
+    /// Efficiency summed over the region, normalized by its length.
+    pub alpha: f64,
+    pub median_efficiency: f64,
+    pub max_efficiency: f64,
+    /// Non-consensus bases with a frequency of at least `min_freq`.
+    pub variants: usize,
+}
+
+impl RegionSummary {
+    pub fn new(region: &Region, matrices: &Matrices, range: (i32, i32), min_freq: f64) -> RegionSummary {
+        let columns = region.columns(range);
+        let efficiency = matrices.get_efficiency();
+        let ppm = matrices.get_ppm();
+
+        let mut values = columns.iter()
+            .map(|col| efficiency[*col])
+            .collect::<Vec<f64>>();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let alpha = if values.is_empty() { 0. } else { values.iter().sum::<f64>() / values.len() as f64 };
+        let median_efficiency = match values.len() {
+            0 => 0.,
+            n if n % 2 == 0 => (values[n / 2 - 1] + values[n / 2]) / 2.,
+            n => values[n / 2],
+        };
+
+        let variants = columns.iter()
+            .map(|col| {
+                // the consensus base is always above the cutoff on a covered position
+                ppm.column(*col).iter().filter(|x| **x >= min_freq).count().saturating_sub(1)
+            })
+            .sum();
+
+        RegionSummary {
+            name: region.name.clone(),
+            kind: region.kind.clone(),
+            strand: region.strand,
+            length: columns.len(),
+            alpha,
+            median_efficiency,
+            max_efficiency: values.last().copied().unwrap_or(0.),
+            variants,
+        }
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["sample", "region", "type", "strand", "length", "alpha", "median efficiency", "max efficiency", "variants"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    pub(crate) fn record(&self, sample: &str) -> Vec<String> {
+        vec![
+            sample.to_string(),
+            self.name.clone(),
+            self.kind.clone(),
+            self.strand.symbol().to_string(),
+            self.length.to_string(),
+            self.alpha.to_string(),
+            self.median_efficiency.to_string(),
+            self.max_efficiency.to_string(),
+            self.variants.to_string(),
+        ]
+    }
+}
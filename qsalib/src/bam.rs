@@ -1,18 +1,57 @@
+use std::collections::HashSet;
 use std::path::{PathBuf, Path};
 
 use ndarray::{Array, ArrayView, Ix1, Ix2, ShapeBuilder, Axis};
 use bam::BamReader;
+use csv::Writer;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde_json::json;
 
 use crate::matrices::Matrices;
-use crate::utils::expand_dir;
+use crate::reads::{AlignedRead, Reads, ReadCounts};
+use crate::protein::ProteinMatrices;
+use crate::selection::Selection;
+use crate::distance::{Distance, AlphaDifference};
+use crate::metadata::Metadata;
+use crate::differentiation::Differentiation;
+use crate::partition::Partition;
+use crate::ordination::Ordination;
+use crate::tree::{Tree, to_phylip};
+use crate::comparison::GroupTest;
+use crate::differential::DifferentialDiversity;
+use crate::trajectory::{Trajectories, TimedSample};
+use crate::rate::EvolutionaryRate;
+use crate::haplotype::{Complexity, HaplotypeWindow, local_haplotypes};
+use crate::reconstruction::{GlobalHaplotypes, spanned_region};
+use crate::linkage::Linkage;
+use crate::covariation::Covariation;
+use crate::spectrum::Spectrum;
+use crate::logo::{Logo, LogoKind, top_windows};
+use crate::hypermut::{Hypermutation, ReadHypermutation};
+use crate::annotation::{Annotation, RegionSummary};
+use crate::tidy::{PositionMetrics, SampleSummary, Table, BREADTH_DEPTHS, manifest_to_json, number};
+use crate::stats::quantile;
+use crate::utils::{expand_dir, read_fasta};
 use crate::error::{QSAError, Result};
 
 pub struct BamDataBuilder {
     bams: Vec<PathBuf>,
     dirs: Vec<PathBuf>,
+    reference: Option<PathBuf>,
+    annotation: Option<PathBuf>,
+    metadata: Option<PathBuf>,
     range: (i32, i32),
     threshold: f64,
     checks: bool,
+    hypermutation: Option<(f64, bool)>,
+    distance: Option<Box<dyn Distance>>,
+    haplotype_windows: Option<(usize, usize)>,
+    haplotype_min_freq: f64,
+    haplotype_mismatches: usize,
+    global_haplotypes: Option<(f64, f64)>,
+    global_region: Option<(usize, usize)>,
+    linkage: Option<(f64, u64)>,
+    covariation: Option<(f64, u64, usize, u64)>,
 }
 
 impl Default for BamDataBuilder {
@@ -20,9 +59,21 @@ impl Default for BamDataBuilder {
         BamDataBuilder {
             bams: Vec::default(),
             dirs: Vec::default(),
+            reference: None,
+            annotation: None,
+            metadata: None,
             range: (i32::default(), i32::default()),
             threshold: f64::default(),
             checks: true,
+            hypermutation: None,
+            distance: None,
+            haplotype_windows: None,
+            haplotype_min_freq: 0.01,
+            haplotype_mismatches: 2,
+            global_haplotypes: None,
+            global_region: None,
+            linkage: None,
+            covariation: None,
         }
     }
 }
@@ -72,6 +123,42 @@ impl BamDataBuilder {
         Ok(self)
     }
 
+    pub fn with_reference<P>(&mut self, reference: P) -> Result<&mut Self>
+        where P: AsRef<Path>
+    {
+        if !reference.as_ref().is_file() {
+            return Err(QSAError::ReferenceNotFound);
+        }
+
+        self.reference = Some(reference.as_ref().to_path_buf());
+
+        Ok(self)
+    }
+
+    pub fn with_annotation<P>(&mut self, annotation: P) -> Result<&mut Self>
+        where P: AsRef<Path>
+    {
+        if !annotation.as_ref().is_file() {
+            return Err(QSAError::AnnotationNotFound);
+        }
+
+        self.annotation = Some(annotation.as_ref().to_path_buf());
+
+        Ok(self)
+    }
+
+    pub fn with_metadata<P>(&mut self, metadata: P) -> Result<&mut Self>
+        where P: AsRef<Path>
+    {
+        if !metadata.as_ref().is_file() {
+            return Err(QSAError::MetadataNotFound);
+        }
+
+        self.metadata = Some(metadata.as_ref().to_path_buf());
+
+        Ok(self)
+    }
+
     pub fn in_range(&mut self, range: (i32, i32)) -> &mut Self {
         self.range = range;
 
@@ -90,32 +177,163 @@ impl BamDataBuilder {
         self
     }
 
+    /// Flags the reads of each sample with a hypermutation p-value below `p_value`, and removes
+    /// them from its matrices when `exclude` is set.
+    pub fn with_hypermutation(&mut self, p_value: f64, exclude: bool) -> &mut Self {
+        self.hypermutation = Some((p_value, exclude));
+
+        self
+    }
+
+    /// Sets the distance between samples used for β-diversity, α-diversity difference by default.
+    pub fn with_distance(&mut self, distance: Box<dyn Distance>) -> &mut Self {
+        self.distance = Some(distance);
+
+        self
+    }
+
+    /// Reconstructs local haplotypes in windows of `width` columns, starting every `step` columns.
+    pub fn with_haplotype_windows(&mut self, width: usize, step: usize) -> &mut Self {
+        self.haplotype_windows = Some((width, step));
+
+        self
+    }
+
+    /// Sets the frequency under which local haplotypes are taken as errors, and the number of
+    /// mismatches within which they are merged into a more frequent haplotype.
+    pub fn with_haplotype_correction(&mut self, min_freq: f64, mismatches: usize) -> &mut Self {
+        self.haplotype_min_freq = min_freq;
+        self.haplotype_mismatches = mismatches;
+
+        self
+    }
+
+    /// Estimates full-length haplotypes by EM, starting from a per-base error rate of `error_rate`
+    /// and dropping the haplotypes whose frequency falls below `min_freq`.
+    ///
+    /// The region is given as 0-based, half-open reference coordinates and defaults, for each
+    /// sample, to the window as long as its median read that the most reads span.
+    pub fn with_global_haplotypes(&mut self, region: Option<(usize, usize)>, error_rate: f64, min_freq: f64) -> &mut Self {
+        self.global_haplotypes = Some((error_rate, min_freq));
+        self.global_region = region;
+
+        self
+    }
+
+    /// Measures linkage disequilibrium between the sites whose minor base reaches `min_freq`, for
+    /// the pairs spanned by at least `min_reads` reads.
+    pub fn with_linkage(&mut self, min_freq: f64, min_reads: u64) -> &mut Self {
+        self.linkage = Some((min_freq, min_reads));
+
+        self
+    }
+
+    /// Measures the mutual information between the bases of the sites whose minor base reaches
+    /// `min_freq`, for the pairs spanned by at least `min_reads` fragments, with a null from
+    /// `permutations` shuffles drawn from `seed`.
+    pub fn with_covariation(&mut self, min_freq: f64, min_reads: u64, permutations: usize, seed: u64) -> &mut Self {
+        self.covariation = Some((min_freq, min_reads, permutations, seed));
+
+        self
+    }
+
     pub fn build(&mut self) -> Result<BamData> {
         for dir in &self.dirs {
             self.bams.append(&mut expand_dir(dir.to_str().unwrap(), "bam"));
         }
 
+        let reference = match &self.reference {
+            Some(path) => {
+                let (start, end) = (self.range.0 as usize, self.range.1 as usize);
+                let sequence = read_fasta(path)?;
+
+                if sequence.len() < end {
+                    return Err(QSAError::ReferenceMismatch);
+                }
+
+                Some(Array::from_vec(sequence[start..end].to_vec()))
+            },
+            None => None,
+        };
+
+        let annotation = match &self.annotation {
+            Some(path) => Some(Annotation::from_path(path)?),
+            None => None,
+        };
+
         let mut bams: Vec<Bam> = Vec::new();
         for bamp in &self.bams {
-            let bam = Bam::new(bamp, self.range, self.threshold)?;
+            let mut bam = Bam::open(bamp, self.range, self.threshold, reference.clone(), self.hypermutation)?;
+
+            if let Some(annotation) = &annotation {
+                bam.translate(annotation)?;
+            }
+
+            if let Some((width, step)) = self.haplotype_windows {
+                bam.reconstruct_haplotypes(width, step, self.haplotype_min_freq, self.haplotype_mismatches)?;
+            }
+
+            if let Some((error_rate, min_freq)) = self.global_haplotypes {
+                let columns = self.global_region.map(|(start, end)| (start.saturating_sub(self.range.0 as usize), end.saturating_sub(self.range.0 as usize)));
+
+                bam.estimate_global_haplotypes(columns, error_rate, min_freq)?;
+            }
+
+            if let Some((min_freq, min_reads)) = self.linkage {
+                bam.compute_linkage(min_freq, min_reads)?;
+            }
+
+            if let Some((min_freq, min_reads, permutations, seed)) = self.covariation {
+                bam.compute_covariation(min_freq, min_reads, permutations, seed)?;
+            }
 
             bams.push(bam);
         }
 
-        BamData::from_bams(bams, self.checks)
+        let mut data = BamData::from_bams(bams, self.checks)?;
+
+        if let Some(distance) = self.distance.take() {
+            data.set_distance(distance);
+        }
+
+        if let Some(annotation) = annotation {
+            data.set_annotation(annotation);
+        }
+
+        if let Some(metadata) = &self.metadata {
+            data.set_metadata(Metadata::from_path(metadata)?);
+        }
+
+        Ok(data)
     }
 }
 
-#[derive(Default)]
 pub struct BamData {
     bams: Vec<Bam>,
     checks: bool,
     alpha: Array<f64, Ix1>,
     beta: Array<f64, Ix2>,
+    annotation: Option<Annotation>,
+    metadata: Option<Metadata>,
+    distance: Box<dyn Distance>,
+}
+
+impl Default for BamData {
+    fn default() -> Self {
+        BamData {
+            bams: Vec::default(),
+            checks: bool::default(),
+            alpha: Array::default(0),
+            beta: Array::default((0, 0)),
+            annotation: None,
+            metadata: None,
+            distance: Box::new(AlphaDifference),
+        }
+    }
 }
 
 impl BamData {
-    fn alpha(bams: &Vec<Bam>) -> Array<f64, Ix1> {
+    fn alpha(bams: &[Bam]) -> Array<f64, Ix1> {
         Array::from_vec(bams.iter().map(|x| x.alpha_diversity()).collect::<Vec<f64>>())
     }
 
@@ -125,29 +343,30 @@ impl BamData {
         self
     }
 
-    fn beta(alpha: ArrayView<f64, Ix1>) -> Array<f64, Ix2> {
-        let cols = *alpha.shape().get(0).unwrap();
+    fn beta(bams: &[Bam], distance: &dyn Distance) -> Array<f64, Ix2> {
+        let cols = bams.len();
         let mut beta = Array::<f64, Ix2>::zeros((cols, cols).f());
 
         for i in 0..cols {
-            for j in 0..cols {
-                unsafe { *beta.uget_mut([i, j]) = (alpha[[i]] - alpha[[j]]).abs(); }
+            for j in (i + 1)..cols {
+                // undefined distances are kept as NaN, and the matrix is refused by ordination and clustering
+                let d = distance.distance(&bams[i].matrices, &bams[j].matrices).unwrap_or(f64::NAN);
+
+                beta[[i, j]] = d;
+                beta[[j, i]] = d;
             }
         }
 
         beta
     }
 
-    fn beta_upd(&mut self) -> &mut Self {
-        let (rest, last) = self.alpha.view().split_at(Axis(0), self.alpha.len() - 1);
-        let last = last.get(0).unwrap();
-
-        let values = rest.iter()
-            .map(|x| (x - last).abs())
+    fn beta_upd(&mut self, bam: &Bam) -> &mut Self {
+        let mut values = self.bams.iter()
+            .map(|x| self.distance.distance(&x.matrices, &bam.matrices).unwrap_or(f64::NAN))
             .collect::<Vec<f64>>();
 
-        // CHECK: Does this work?
-        self.beta.push_column(ArrayView::from(&values.as_slice()[..values.len() - 1])).unwrap();
+        self.beta.push_column(ArrayView::from(values.as_slice())).unwrap();
+        values.push(0.);
         self.beta.push_row(ArrayView::from(values.as_slice())).unwrap();
 
         self
@@ -167,14 +386,18 @@ impl BamData {
         }
 
         let alpha = BamData::alpha(&bams);
-        let beta = BamData::beta(alpha.view());
+        let distance: Box<dyn Distance> = Box::new(AlphaDifference);
+        let beta = BamData::beta(&bams, distance.as_ref());
 
         Ok(
             BamData {
                 bams,
                 checks,
                 alpha,
-                beta
+                beta,
+                annotation: None,
+                metadata: None,
+                distance,
             }
         )
     }
@@ -194,19 +417,594 @@ impl BamData {
             }
         }
 
-        self.alpha_add(&bam).beta_upd().bams.push(bam);
+        self.alpha_add(&bam).beta_upd(&bam).bams.push(bam);
 
         Ok(())
     }
 
-    pub fn alpha_diversity(&self) -> ArrayView<f64, Ix1> {
+    pub fn alpha_diversity(&self) -> ArrayView<'_, f64, Ix1> {
         self.alpha.view()
     }
 
-    pub fn beta_diversity(&self) -> ArrayView<f64, Ix2> {
+    pub fn beta_diversity(&self) -> ArrayView<'_, f64, Ix2> {
         self.beta.view()
     }
 
+    /// The β-diversity matrix, or an error if the distance between two of the samples is not defined.
+    pub fn finite_beta_diversity(&self) -> Result<ArrayView<'_, f64, Ix2>> {
+        if self.beta.iter().all(|x| x.is_finite()) {
+            Ok(self.beta.view())
+        } else {
+            Err(QSAError::NonFiniteDistance)
+        }
+    }
+
+    /// Replaces the distance between samples, computing the β-diversity matrix again.
+    pub fn set_distance(&mut self, distance: Box<dyn Distance>) -> &mut Self {
+        self.beta = BamData::beta(&self.bams, distance.as_ref());
+        self.distance = distance;
+
+        self
+    }
+
+    pub fn get_distance(&self) -> &dyn Distance {
+        self.distance.as_ref()
+    }
+
+    pub fn set_annotation(&mut self, annotation: Annotation) -> &mut Self {
+        self.annotation = Some(annotation);
+
+        self
+    }
+
+    pub fn get_annotation(&self) -> Option<&Annotation> {
+        self.annotation.as_ref()
+    }
+
+    pub fn set_metadata(&mut self, metadata: Metadata) -> &mut Self {
+        self.metadata = Some(metadata);
+
+        self
+    }
+
+    pub fn get_metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Group of each sample according to the metadata, if any.
+    pub fn get_groups(&self) -> Vec<Option<String>> {
+        self.bams.iter()
+            .map(|bam| {
+                self.metadata.as_ref()
+                    .and_then(|x| x.group(bam.name.as_str()))
+                    .map(|x| x.to_string())
+            })
+            .collect()
+    }
+
+    /// Indices of the samples of each group, in order of first appearance.
+    pub fn group_members(&self) -> Vec<(String, Vec<usize>)> {
+        let mut members: Vec<(String, Vec<usize>)> = Vec::new();
+
+        for (i, group) in self.get_groups().into_iter().enumerate() {
+            if let Some(group) = group {
+                match members.iter_mut().find(|(name, _)| *name == group) {
+                    Some((_, indices)) => indices.push(i),
+                    None => members.push((group, vec![i])),
+                }
+            }
+        }
+
+        members
+    }
+
+    /// Sum of the PFMs of the given samples.
+    pub fn pooled_pfm(&self, samples: &[usize]) -> Array<u64, Ix2> {
+        let shape = self.bams.first().map(|x| x.matrices.get_pfm().dim()).unwrap_or((4, 0));
+        let mut pfm = Array::<u64, Ix2>::zeros(shape.f());
+
+        for i in samples {
+            pfm += &self.bams[*i].matrices.get_pfm();
+        }
+
+        pfm
+    }
+
+    /// Differentiation between every pair of samples, labelled by their names.
+    pub fn pairwise_differentiation(&self) -> Vec<(String, String, Differentiation)> {
+        let mut rv = Vec::new();
+
+        for (i, a) in self.bams.iter().enumerate() {
+            for b in self.bams.iter().skip(i + 1) {
+                rv.push((a.name.clone(), b.name.clone(), Differentiation::new(a.matrices.get_pfm(), b.matrices.get_pfm())));
+            }
+        }
+
+        rv
+    }
+
+    /// Differentiation between every pair of groups of the metadata, pooling the PFMs of their samples.
+    pub fn group_differentiation(&self) -> Vec<(String, String, Differentiation)> {
+        let groups = self.group_members()
+            .into_iter()
+            .map(|(name, samples)| (name, self.pooled_pfm(&samples)))
+            .collect::<Vec<_>>();
+
+        let mut rv = Vec::new();
+        for (i, (a, pa)) in groups.iter().enumerate() {
+            for (b, pb) in groups.iter().skip(i + 1) {
+                rv.push((a.clone(), b.clone(), Differentiation::new(pa.view(), pb.view())));
+            }
+        }
+
+        rv
+    }
+
+    /// Writes the genome-wide and the per-position differentiation between samples and between groups.
+    pub fn differentiation_to_csv<P>(&self, path: P, filename: &str, positions_filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+        let mut positions = Writer::from_path(path.as_ref().join(Path::new(positions_filename))).expect("could not open file");
+
+        writer.write_record(Differentiation::header()).unwrap();
+        positions.write_record(Differentiation::position_header()).unwrap();
+
+        let first = self.bams.first().map(|x| x.get_range().0 as usize + 1).unwrap_or(1);
+        let levels = [("sample", self.pairwise_differentiation()), ("group", self.group_differentiation())];
+
+        for (level, pairs) in levels.iter() {
+            for (a, b, differentiation) in pairs {
+                writer.write_record(differentiation.record(level, a, b)).unwrap();
+
+                for record in differentiation.position_records(level, a, b, first) {
+                    positions.write_record(record).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Principal coordinates analysis of the β-diversity matrix.
+    pub fn ordination(&self) -> Result<Ordination> {
+        Ok(Ordination::pcoa(self.finite_beta_diversity()?))
+    }
+
+    /// Writes the PCoA coordinates of each sample and the eigenvalues of each axis.
+    pub fn ordination_to_csv<P>(&self, path: P, filename: &str, eigenvalues_filename: &str) -> Result<()>
+        where P: AsRef<Path>
+    {
+        let ordination = self.ordination()?;
+        let coordinates = ordination.get_coordinates();
+
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        let mut header = vec!["sample".to_string(), "group".to_string()];
+        header.extend((1..=coordinates.ncols()).map(|i| format!("PCo{}", i)));
+        writer.write_record(header).unwrap();
+
+        for ((bam, group), row) in self.bams.iter().zip(self.get_groups()).zip(coordinates.rows()) {
+            let mut record = vec![bam.name.clone(), group.unwrap_or_default()];
+            record.extend(row.iter().map(|x| x.to_string()));
+
+            writer.write_record(record).unwrap();
+        }
+
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(eigenvalues_filename))).expect("could not open file");
+
+        writer.write_record(["axis", "eigenvalue", "proportion explained"]).unwrap();
+
+        for (i, (value, proportion)) in ordination.get_eigenvalues().iter().zip(ordination.proportion_explained().iter()).enumerate() {
+            writer.write_record(&[format!("PCo{}", i + 1), value.to_string(), proportion.to_string()]).unwrap();
+        }
+
+        Ok(())
+    }
+
+    /// UPGMA tree of the samples from the β-diversity matrix.
+    pub fn upgma(&self) -> Result<Tree> {
+        Ok(Tree::upgma(self.finite_beta_diversity()?, &self.get_names()))
+    }
+
+    /// Neighbour-joining tree of the samples from the β-diversity matrix.
+    pub fn neighbour_joining(&self) -> Result<Tree> {
+        Ok(Tree::neighbour_joining(self.finite_beta_diversity()?, &self.get_names()))
+    }
+
+    /// Writes the UPGMA and neighbour-joining trees in Newick format.
+    pub fn trees_to_newick<P>(&self, path: P, upgma_filename: &str, nj_filename: &str) -> Result<()>
+        where P: AsRef<Path>
+    {
+        let (upgma, nj) = (self.upgma()?, self.neighbour_joining()?);
+
+        std::fs::write(path.as_ref().join(Path::new(upgma_filename)), upgma.to_newick() + "\n")
+            .expect("could not write file");
+        std::fs::write(path.as_ref().join(Path::new(nj_filename)), nj.to_newick() + "\n")
+            .expect("could not write file");
+
+        Ok(())
+    }
+
+    /// Writes the β-diversity matrix in relaxed PHYLIP format.
+    pub fn beta_to_phylip<P>(&self, path: P, filename: &str) -> Result<()>
+        where P: AsRef<Path>
+    {
+        std::fs::write(path.as_ref().join(Path::new(filename)), to_phylip(self.finite_beta_diversity()?, &self.get_names()))
+            .expect("could not write file");
+
+        Ok(())
+    }
+
+    /// PERMANOVA on the β-diversity matrix and rank test on the α-diversity between the groups of
+    /// the metadata, each with `permutations` permutations seeded with `seed`.
+    ///
+    /// The PERMANOVA is left out when a distance between two samples is not defined.
+    pub fn group_tests(&self, permutations: usize, seed: u64) -> Vec<GroupTest> {
+        let groups = self.group_members();
+
+        vec![
+            self.finite_beta_diversity().ok().and_then(|beta| GroupTest::permanova(beta, &groups, permutations, seed)),
+            GroupTest::rank_test(&self.alpha.to_vec(), "alpha diversity", &groups, permutations, seed),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    pub fn group_tests_to_csv<P>(&self, path: P, filename: &str, permutations: usize, seed: u64)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+        writer.write_record(GroupTest::header()).unwrap();
+
+        for test in self.group_tests(permutations, seed) {
+            writer.write_record(test.record()).unwrap();
+        }
+    }
+
+    /// Per-position comparison of two groups of the metadata.
+    pub fn differential_diversity(&self, a: &str, b: &str) -> Result<DifferentialDiversity> {
+        let members = self.group_members();
+        let samples = |group: &str| members.iter()
+            .find(|(name, _)| name == group)
+            .map(|(_, samples)| samples.clone())
+            .ok_or(QSAError::GroupNotFound);
+
+        let (samples_a, samples_b) = (samples(a)?, samples(b)?);
+        let matrices = |samples: &[usize]| samples.iter().map(|i| &self.bams[*i].matrices).collect::<Vec<&Matrices>>();
+
+        Ok(
+            DifferentialDiversity::new(
+                a, self.pooled_pfm(&samples_a).view(), &matrices(&samples_a),
+                b, self.pooled_pfm(&samples_b).view(), &matrices(&samples_b),
+            )
+        )
+    }
+
+    /// Names of the annotated regions each column falls in, separated by `;`.
+    pub fn column_regions(&self) -> Vec<String> {
+        let range = self.bams.first().map(|x| x.get_range()).unwrap_or((0, 0));
+
+        (range.0 as usize..range.1 as usize)
+            .map(|position| {
+                self.annotation.as_ref()
+                    .map(|x| x.at(position).iter().map(|r| r.name.as_str()).collect::<Vec<&str>>().join(";"))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    pub fn differential_to_csv<P>(&self, path: P, filename: &str, differential: &DifferentialDiversity)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+        writer.write_record(DifferentialDiversity::header()).unwrap();
+
+        let first = self.bams.first().map(|x| x.get_range().0 as usize + 1).unwrap_or(1);
+        for record in differential.records(first, &self.column_regions()) {
+            writer.write_record(record).unwrap();
+        }
+    }
+
+    /// Samples of each patient of the metadata, or all of them if there is no `patient` column,
+    /// with their time according to `time`; samples without a time are left out.
+    fn series<F>(&self, time: F) -> Vec<(String, Vec<TimedSample<'_>>)>
+        where F: Fn(&Metadata, &str) -> Option<f64>
+    {
+        let metadata = match &self.metadata {
+            Some(metadata) => metadata,
+            None => return Vec::new(),
+        };
+
+        let mut series: Vec<(String, Vec<TimedSample>)> = Vec::new();
+        for bam in &self.bams {
+            if let Some(t) = time(metadata, bam.name.as_str()) {
+                let patient = metadata.patient(bam.name.as_str()).unwrap_or("all").to_string();
+                let sample = (bam.name.clone(), t, &bam.matrices);
+
+                match series.iter_mut().find(|(name, _)| *name == patient) {
+                    Some((_, samples)) => samples.push(sample),
+                    None => series.push((patient, vec![sample])),
+                }
+            }
+        }
+
+        series
+    }
+
+    /// Minor-allele trajectories of each patient, using the samples with a numeric `timepoint`.
+    pub fn trajectories(&self, min_freq: f64, overdispersion: f64) -> Vec<Trajectories> {
+        self.series(|metadata, sample| metadata.timepoint(sample))
+            .into_iter()
+            .filter(|(_, samples)| samples.len() > 1)
+            .map(|(name, samples)| Trajectories::new(name.as_str(), &samples, min_freq, overdispersion))
+            .collect()
+    }
+
+    /// Writes a summary of each trajectory and the tidy table of the frequencies at each timepoint.
+    pub fn trajectories_to_csv<P>(&self, path: P, filename: &str, tidy_filename: &str, trajectories: &[Trajectories])
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+        let mut tidy = Writer::from_path(path.as_ref().join(Path::new(tidy_filename))).expect("could not open file");
+
+        writer.write_record(Trajectories::header()).unwrap();
+        tidy.write_record(Trajectories::tidy_header()).unwrap();
+
+        let first = self.bams.first().map(|x| x.get_range().0 as usize + 1).unwrap_or(1);
+        for series in trajectories {
+            for record in series.records(first, 0.05) {
+                writer.write_record(record).unwrap();
+            }
+
+            for record in series.tidy_records(first) {
+                tidy.write_record(record).unwrap();
+            }
+        }
+    }
+
+    /// Evolutionary rates of each patient, using the samples with a `date`, with `bootstrap`
+    /// resamplings of the sites seeded with `seed`.
+    pub fn evolutionary_rates(&self, bootstrap: usize, seed: u64) -> Vec<EvolutionaryRate> {
+        self.series(|metadata, sample| metadata.date(sample))
+            .into_iter()
+            .filter_map(|(name, samples)| EvolutionaryRate::new(name.as_str(), &samples, bootstrap, seed))
+            .collect()
+    }
+
+    /// Writes the rates of each patient and the divergence and diversity of each of their samples.
+    pub fn evolutionary_rates_to_csv<P>(&self, path: P, filename: &str, samples_filename: &str, rates: &[EvolutionaryRate])
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+        let mut samples = Writer::from_path(path.as_ref().join(Path::new(samples_filename))).expect("could not open file");
+
+        writer.write_record(EvolutionaryRate::header()).unwrap();
+        samples.write_record(EvolutionaryRate::sample_header()).unwrap();
+
+        for rate in rates {
+            for record in rate.records() {
+                writer.write_record(record).unwrap();
+            }
+
+            for record in rate.sample_records() {
+                samples.write_record(record).unwrap();
+            }
+        }
+    }
+
+    /// Writes the haplotype-level complexity of each sample beside its α-diversity, and that of
+    /// each of its windows.
+    pub fn complexity_to_csv<P>(&self, path: P, filename: &str, windows_filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+        let mut windows = Writer::from_path(path.as_ref().join(Path::new(windows_filename))).expect("could not open file");
+
+        writer.write_record(Complexity::header()).unwrap();
+        windows.write_record(Complexity::window_header()).unwrap();
+
+        for bam in &self.bams {
+            // the mean of no window is NaN
+            let complexity = bam.haplotype_complexity().unwrap_or_else(|| Complexity::mean(&[]));
+            let first = bam.get_range().0 as usize + 1;
+
+            let mut covered = 0;
+            for window in bam.get_haplotypes() {
+                if let Some(x) = window.complexity() {
+                    windows.write_record(x.window_record(bam.name.as_str(), first + window.start, first + window.end - 1, window.reads)).unwrap();
+                    covered += 1;
+                }
+            }
+
+            writer.write_record(complexity.record(bam.name.as_str(), bam.alpha_diversity(), covered)).unwrap();
+        }
+    }
+
+    /// Writes the frequency of the full-length haplotypes of every sample, with the estimated error rate.
+    pub fn global_haplotypes_to_csv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+        writer.write_record(GlobalHaplotypes::header()).unwrap();
+
+        for bam in &self.bams {
+            if let Some(haplotypes) = bam.get_global_haplotypes() {
+                for record in haplotypes.records(bam.name.as_str()) {
+                    writer.write_record(record).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Partition of the diversity of all the samples, with Hill numbers of order `q`.
+    pub fn partition(&self, q: f64) -> Partition {
+        let ppms = self.bams.iter().map(|x| x.matrices.get_ppm()).collect::<Vec<_>>();
+
+        Partition::new(&ppms, q)
+    }
+
+    /// Partition of the diversity of the samples of each group, with Hill numbers of order `q`.
+    pub fn group_partitions(&self, q: f64) -> Vec<(String, Partition)> {
+        self.group_members()
+            .into_iter()
+            .map(|(name, samples)| {
+                let ppms = samples.iter().map(|i| self.bams[*i].matrices.get_ppm()).collect::<Vec<_>>();
+
+                (name, Partition::new(&ppms, q))
+            })
+            .collect()
+    }
+
+    /// Writes the genome-wide and the per-position partition of all the samples and of each group.
+    pub fn partition_to_csv<P>(&self, path: P, filename: &str, positions_filename: &str, q: f64)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+        let mut positions = Writer::from_path(path.as_ref().join(Path::new(positions_filename))).expect("could not open file");
+
+        writer.write_record(Partition::header()).unwrap();
+        positions.write_record(Partition::position_header()).unwrap();
+
+        let first = self.bams.first().map(|x| x.get_range().0 as usize + 1).unwrap_or(1);
+        let mut partitions = vec![("all".to_string(), self.partition(q))];
+        partitions.extend(self.group_partitions(q));
+
+        for (samples, partition) in partitions {
+            writer.write_record(partition.record(samples.as_str())).unwrap();
+
+            for record in partition.position_records(samples.as_str(), first) {
+                positions.write_record(record).unwrap();
+            }
+        }
+    }
+
+    /// Columns (end excluded) of a region given in 1-based, inclusive reference coordinates.
+    pub fn region_columns(&self, start: usize, end: usize) -> Result<(usize, usize)> {
+        let range = self.bams.first().map_or((0, 0), |bam| bam.get_range());
+        let first = range.0 as usize;
+
+        if start == 0 || start > end || start <= first || end > range.1 as usize {
+            return Err(QSAError::RegionOutOfRange);
+        }
+
+        Ok((start - 1 - first, end - first))
+    }
+
+    /// The `n` non-overlapping windows of `width` columns with the highest efficiency averaged
+    /// over the samples, sorted by position.
+    pub fn top_efficiency_windows(&self, width: usize, n: usize) -> Vec<(usize, usize)> {
+        let size = self.bams.iter().map(|bam| bam.matrices.get_efficiency().len()).min().unwrap_or(0);
+        let mut mean = Array::<f64, Ix1>::zeros(size);
+        for bam in &self.bams {
+            mean += &bam.matrices.get_efficiency().slice(ndarray::s![..size]);
+        }
+        mean /= self.bams.len().max(1) as f64;
+
+        top_windows(mean.view(), width, n)
+    }
+
+    /// Logos of the same columns of every sample.
+    pub fn logos(&self, columns: (usize, usize), kind: LogoKind) -> Vec<(String, Logo)> {
+        self.bams.iter()
+            .map(|bam| (bam.name.clone(), bam.logo(columns, kind)))
+            .collect()
+    }
+
+    /// Summaries of all the samples, in order.
+    pub fn summaries(&self, bootstrap: usize, seed: u64) -> Vec<SampleSummary> {
+        self.bams.iter().map(|bam| bam.summary(bootstrap, seed)).collect()
+    }
+
+    /// Writes the quality control of every sample: the reads processed and those left out by
+    /// reason, depth, breadth of coverage and masked positions.
+    pub fn qc_to_csv<P>(&self, path: P, filename: &str, summaries: &[SampleSummary])
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        writer.write_record(SampleSummary::qc_header()).unwrap();
+
+        for summary in summaries {
+            writer.write_record(summary.qc_record()).unwrap();
+        }
+    }
+
+    /// Metrics of every position of every sample, one row per sample and position.
+    pub fn positions_table(&self) -> Table {
+        let mut table = Table::new("positions", &[
+            "sample", "position", "depth", "count_a", "count_c", "count_g", "count_t",
+            "freq_a", "freq_c", "freq_g", "freq_t", "entropy", "efficiency", "masked",
+        ]);
+
+        for bam in &self.bams {
+            for x in bam.position_metrics() {
+                let mut row = vec![json!(bam.name), json!(x.position), json!(x.depth)];
+                row.extend(x.counts.iter().map(|c| json!(c)));
+                row.extend(x.frequencies.iter().map(|f| number(*f)));
+                row.extend([number(x.entropy), number(x.efficiency), json!(x.masked)]);
+
+                table.push(row);
+            }
+        }
+
+        table
+    }
+
+    /// One row per sample, with its group, reads, depth and α-diversity.
+    pub fn samples_table(&self, summaries: &[SampleSummary]) -> Table {
+        let mut table = Table::new("samples", &[
+            "sample", "group", "reads", "hypermutated_reads", "excluded_reads", "positions", "mean_depth",
+            "median_depth", "uncovered_positions", "masked_positions", "alpha", "alpha_lower", "alpha_upper",
+            "processed_reads", "unmapped_reads", "outside_range_reads", "breadth_1x", "breadth_100x", "breadth_1000x",
+        ]);
+
+        for (x, group) in summaries.iter().zip(self.get_groups()) {
+            let mut row = vec![
+                json!(x.sample), json!(group), json!(x.read_counts.kept()), json!(x.hypermutated), json!(x.excluded),
+                json!(x.positions), number(x.mean_depth), number(x.median_depth), json!(x.uncovered),
+                json!(x.masked), number(x.alpha), number(x.alpha_ci.0), number(x.alpha_ci.1),
+                json!(x.read_counts.processed), json!(x.read_counts.unmapped), json!(x.read_counts.outside_range),
+            ];
+            row.extend(x.breadth.iter().map(|b| number(*b)));
+
+            table.push(row);
+        }
+
+        table
+    }
+
+    /// The β-diversity matrix with a column and a row for each sample.
+    pub fn beta_table(&self) -> Table {
+        let names = self.get_names();
+        let mut columns = vec!["sample"];
+        columns.extend(names.iter().map(|x| x.as_str()));
+
+        let mut table = Table::new("beta-diversity", &columns);
+        table.with_attribute("distance", json!(self.distance.name()));
+
+        for (name, row) in names.iter().zip(self.beta.rows()) {
+            let mut record = vec![json!(name)];
+            record.extend(row.iter().map(|x| number(*x)));
+
+            table.push(record);
+        }
+
+        table
+    }
+
+    /// Writes the tables of positions, samples and β-diversity as TSV and JSON, and `manifest.json` listing them.
+    pub fn tables_to_files<P>(&self, path: P, summaries: &[SampleSummary])
+        where P: AsRef<Path>
+    {
+        let tables = [self.positions_table(), self.samples_table(summaries), self.beta_table()];
+
+        for table in &tables {
+            table.to_files(path.as_ref());
+        }
+
+        manifest_to_json(path, "manifest.json", &tables.iter().collect::<Vec<&Table>>());
+    }
+
     pub fn get_names(&self) -> Vec<String> {
         let mut rv: Vec<String> = Vec::new();
         for bam in &self.bams {
@@ -215,6 +1013,103 @@ impl BamData {
 
         rv
     }
+
+    pub fn spectrum_to_csv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        writer.write_record(Spectrum::header()).unwrap();
+
+        for bam in &self.bams {
+            writer.write_record(bam.spectrum().record(bam.name.as_str())).unwrap();
+        }
+    }
+
+    /// Writes the diversity of every sample inside each annotated region, if an annotation was supplied.
+    pub fn regions_to_csv<P>(&self, path: P, filename: &str, min_freq: f64)
+        where P: AsRef<Path>
+    {
+        let annotation = match &self.annotation {
+            Some(annotation) => annotation,
+            None => return,
+        };
+
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        writer.write_record(RegionSummary::header()).unwrap();
+
+        for bam in &self.bams {
+            for summary in bam.region_summaries(annotation, min_freq) {
+                writer.write_record(summary.record(bam.name.as_str())).unwrap();
+            }
+        }
+    }
+
+    /// Writes the amino-acid diversity and consensus of every coding region of each sample.
+    pub fn proteins_to_csv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        writer.write_record(["sample", "protein", "codons", "alpha", "consensus"]).unwrap();
+
+        for bam in &self.bams {
+            for protein in bam.get_proteins() {
+                writer.write_record(&[
+                    bam.name.clone(),
+                    protein.name.clone(),
+                    protein.get_efficiency().len().to_string(),
+                    protein.alpha_diversity().to_string(),
+                    protein.consensus(),
+                ]).unwrap();
+            }
+        }
+    }
+
+    /// Writes πN, πS and dN/dS of every coding region of each sample.
+    pub fn selection_to_csv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        writer.write_record(["sample", "protein", "N sites", "S sites", "piN", "piS", "dN/dS"]).unwrap();
+
+        for bam in &self.bams {
+            for selection in bam.selection() {
+                let summary = selection.summary();
+
+                writer.write_record(&[
+                    bam.name.clone(),
+                    selection.name.clone(),
+                    summary.nonsynonymous_sites.to_string(),
+                    summary.synonymous_sites.to_string(),
+                    summary.pi_n().to_string(),
+                    summary.pi_s().to_string(),
+                    summary.dn_ds().to_string(),
+                ]).unwrap();
+            }
+        }
+    }
+
+    pub fn hypermutation_to_csv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        writer.write_record(["sample", "reads", "hypermutated", "fraction"]).unwrap();
+
+        for bam in &self.bams {
+            if let Some(hypermutation) = bam.get_hypermutation() {
+                writer.write_record(&[
+                    bam.name.clone(),
+                    hypermutation.total_reads().to_string(),
+                    hypermutation.get_hypermutated().len().to_string(),
+                    hypermutation.fraction().to_string(),
+                ]).unwrap();
+            }
+        }
+    }
 }
 
 pub struct BamDataIntoIterator {
@@ -267,15 +1162,41 @@ pub struct Bam {
     pub name: String,
     pub matrices: Matrices,
     pub(crate) sqsn: String,
+    reference: Option<Array<u8, Ix1>>,
+    hypermutation: Option<Hypermutation>,
+    excluded: HashSet<usize>,
+    proteins: Vec<ProteinMatrices>,
+    selection: Vec<Selection>,
+    haplotypes: Vec<HaplotypeWindow>,
+    global_haplotypes: Option<GlobalHaplotypes>,
+    linkage: Option<Linkage>,
+    covariation: Option<Covariation>,
+    read_counts: ReadCounts,
+    path: PathBuf,
+    range: (i32, i32),
+    threshold: f64,
 }
 
 impl Bam {
     pub fn new<P>(bam: P, range: (i32, i32), threshold: f64) -> Result<Self>
         where P: AsRef<Path>
     {
-        let name = bam.as_ref().iter().nth(1).unwrap().to_str().unwrap();
-        let name = name[..name.len() - 4].to_string();
+        Bam::open(bam, range, threshold, None, None)
+    }
+
+    /// Reads a BAM file, comparing its reads with `reference` when one is supplied, and testing them
+    /// for hypermutation when `hypermutation` gives a p-value, leaving the flagged reads out of the
+    /// matrices when it also says to exclude them.
+    ///
+    /// With a reference, the reads are tested while the matrices are built, in a single pass over
+    /// the file. Without one they are tested against the consensus of all the reads, so in a second
+    /// pass, which also rebuilds the matrices when the flagged reads are excluded.
+    pub fn open<P>(bam: P, range: (i32, i32), threshold: f64, reference: Option<Array<u8, Ix1>>, hypermutation: Option<(f64, bool)>) -> Result<Self>
+        where P: AsRef<Path>
+    {
+        let name = bam.as_ref().file_stem().unwrap().to_str().unwrap().to_string();
 
+        let path = bam.as_ref().to_path_buf();
         let bam = BamReader::from_path(bam, 0).unwrap();
 
         let sqsn =
@@ -285,19 +1206,159 @@ impl Bam {
                 "".to_string()
             };
 
-        let matrices = Matrices::new(bam, range, threshold)?;
+        let (mut hypermutation, exclude) = match hypermutation {
+            Some((p_value, exclude)) => (Some(Hypermutation::with_p_value(p_value)), exclude),
+            None => (None, false),
+        };
+
+        let mut reads = Reads::new(bam, range);
+        let matrices = match (&reference, hypermutation.as_mut()) {
+            (Some(reference), Some(hypermutation)) => Matrices::new(
+                (&mut reads).enumerate()
+                    .filter(|(i, read)| !(hypermutation.test(*i, read, reference.view()) && exclude))
+                    .map(|(_, read)| read),
+                range,
+            )?,
+            _ => Matrices::new(&mut reads, range)?,
+        };
+
+        let mut bam = Bam {
+            name,
+            matrices,
+            sqsn,
+            reference,
+            hypermutation: None,
+            excluded: HashSet::new(),
+            proteins: Vec::new(),
+            selection: Vec::new(),
+            haplotypes: Vec::new(),
+            global_haplotypes: None,
+            linkage: None,
+            covariation: None,
+            read_counts: reads.counts(),
+            path,
+            range,
+            threshold,
+        };
+
+        if let Some(mut hypermutation) = hypermutation {
+            if bam.reference.is_none() {
+                let consensus = bam.matrices.get_consensus();
+                let kept = bam.reads()?
+                    .enumerate()
+                    .filter(|(i, read)| !(hypermutation.test(*i, read, consensus.view()) && exclude))
+                    .map(|(_, read)| read);
+
+                if exclude {
+                    bam.matrices = Matrices::new(kept, range)?;
+                } else {
+                    kept.for_each(drop);
+                }
+            }
 
-        Ok(
-            Bam {
-                name,
-                matrices,
-                sqsn,
+            if exclude {
+                bam.excluded = hypermutation.indices();
             }
-        )
+            bam.hypermutation = Some(hypermutation);
+        }
+
+        Ok(bam)
     }
 
     pub fn alpha_diversity(&self) -> f64 {
-        self.matrices.get_efficiency().sum() / self.matrices.get_coverage().len() as f64
+        self.matrices.alpha_diversity()
+    }
+
+    /// 95% confidence interval of the α-diversity, from `bootstrap` resamplings of the positions.
+    pub fn alpha_confidence(&self, bootstrap: usize, seed: u64) -> (f64, f64) {
+        let efficiency = self.matrices.get_efficiency();
+        let n = efficiency.len();
+        if n == 0 || bootstrap == 0 {
+            return (f64::NAN, f64::NAN);
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let alphas = (0..bootstrap)
+            .map(|_| (0..n).map(|_| efficiency[rng.gen_range(0..n)]).sum::<f64>() / n as f64)
+            .collect::<Vec<f64>>();
+
+        (quantile(&alphas, 0.025), quantile(&alphas, 0.975))
+    }
+
+    /// Range of the reference the columns of the matrices refer to.
+    pub fn get_range(&self) -> (i32, i32) {
+        self.range
+    }
+
+    /// Records of the BAM file, and those left out of the analysed range by reason.
+    pub fn get_read_counts(&self) -> ReadCounts {
+        self.read_counts
+    }
+
+    /// Path of the BAM file the sample was read from.
+    pub fn get_path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Sequence logo of the columns from `start` to `end` (excluded).
+    pub fn logo(&self, (start, end): (usize, usize), kind: LogoKind) -> Logo {
+        Logo::new(self.matrices.get_pfm(), self.matrices.get_ppm(), (start, end), self.range.0 as usize + 1, kind)
+    }
+
+    /// Depth, base counts and frequencies, entropy and efficiency of every position.
+    ///
+    /// Positions are masked when no read covers them or when their coverage, relative to the
+    /// maximum, is below the threshold.
+    pub fn position_metrics(&self) -> Vec<PositionMetrics> {
+        let (pfm, ppm) = (self.matrices.get_pfm(), self.matrices.get_ppm());
+        let (depth, coverage, efficiency) = (self.matrices.get_depth(), self.matrices.get_coverage(), self.matrices.get_efficiency());
+
+        (0..pfm.ncols())
+            .map(|col| {
+                PositionMetrics {
+                    position: self.range.0 as usize + 1 + col,
+                    depth: depth[col],
+                    counts: [pfm[[0, col]], pfm[[1, col]], pfm[[2, col]], pfm[[3, col]]],
+                    frequencies: [ppm[[0, col]], ppm[[1, col]], ppm[[2, col]], ppm[[3, col]]],
+                    entropy: efficiency[col] * 4_f64.log2(),
+                    efficiency: efficiency[col],
+                    masked: depth[col] == 0 || coverage[col] < self.threshold,
+                }
+            })
+            .collect()
+    }
+
+    /// Reads, depth, breadth and α-diversity of the sample, with `bootstrap` resamplings for the
+    /// confidence interval.
+    pub fn summary(&self, bootstrap: usize, seed: u64) -> SampleSummary {
+        let positions = self.position_metrics();
+        let depth = positions.iter().map(|x| x.depth as f64).collect::<Vec<f64>>();
+        // summed as integers, as an empty sum of floats is -0
+        let total_depth = self.matrices.get_depth().sum();
+
+        let hypermutated = self.hypermutation.as_ref().map_or(0, |x| x.get_hypermutated().len());
+
+        SampleSummary {
+            sample: self.name.clone(),
+            read_counts: self.read_counts,
+            hypermutated,
+            excluded: self.excluded.len(),
+            positions: positions.len(),
+            mean_depth: total_depth as f64 / depth.len().max(1) as f64,
+            median_depth: quantile(&depth, 0.5),
+            breadth: BREADTH_DEPTHS.map(|x| self.matrices.breadth(x)),
+            uncovered: positions.iter().filter(|x| x.depth == 0).count(),
+            masked: positions.iter().filter(|x| x.masked).count(),
+            alpha: self.alpha_diversity(),
+            alpha_ci: self.alpha_confidence(bootstrap, seed),
+        }
+    }
+
+    pub fn region_summaries(&self, annotation: &Annotation, min_freq: f64) -> Vec<RegionSummary> {
+        annotation.get_regions()
+            .iter()
+            .map(|region| RegionSummary::new(region, &self.matrices, self.range, min_freq))
+            .collect()
     }
 
     pub fn set_name(&mut self, name: String) -> &mut Self {
@@ -306,9 +1367,230 @@ impl Bam {
         self
     }
 
+    /// Sets the reference sequence (as PFM rows) the reads are compared against.
+    pub fn set_reference(&mut self, reference: Array<u8, Ix1>) -> &mut Self {
+        self.reference = Some(reference);
+
+        self
+    }
+
+    /// The reference sequence, falling back to the sample consensus when none was supplied.
+    pub fn get_reference(&self) -> Array<u8, Ix1> {
+        match &self.reference {
+            Some(reference) => reference.clone(),
+            None => self.matrices.get_consensus(),
+        }
+    }
+
+    pub fn spectrum(&self) -> Spectrum {
+        Spectrum::new(self.matrices.get_pfm(), self.get_reference().view())
+    }
+
+    /// Reads the BAM file again, returning the reads inside the analysed range.
+    pub fn reads(&self) -> Result<Reads> {
+        Reads::from_path(&self.path, self.range)
+    }
+
+    /// Reads the BAM file again, returning the reads the matrices were built from.
+    pub fn analysed_reads(&self) -> Result<impl Iterator<Item = AlignedRead> + '_> {
+        Ok(
+            self.reads()?
+                .enumerate()
+                .filter(move |(i, _)| !self.excluded.contains(i))
+                .map(|(_, read)| read)
+        )
+    }
+
+    /// Builds the amino-acid matrices of every coding region of the annotation, and their πN and πS.
+    pub fn translate(&mut self, annotation: &Annotation) -> Result<&mut Self> {
+        let proteins = ProteinMatrices::from_reads(self.analysed_reads()?, &annotation.coding(), self.range);
+        self.selection = proteins.iter().map(Selection::new).collect();
+        self.proteins = proteins;
+
+        Ok(self)
+    }
+
+    pub fn get_proteins(&self) -> &[ProteinMatrices] {
+        self.proteins.as_slice()
+    }
+
+    /// Reconstructs the local haplotypes of sliding windows from the analysed reads.
+    pub fn reconstruct_haplotypes(&mut self, width: usize, step: usize, min_freq: f64, mismatches: usize) -> Result<&mut Self> {
+        let ncols = self.matrices.get_pfm().ncols();
+        let haplotypes = local_haplotypes(self.analysed_reads()?, ncols, width, step, min_freq, mismatches);
+        self.haplotypes = haplotypes;
+
+        Ok(self)
+    }
+
+    pub fn get_haplotypes(&self) -> &[HaplotypeWindow] {
+        self.haplotypes.as_slice()
+    }
+
+    /// Haplotype-level complexity averaged over the windows with at least one haplotype.
+    pub fn haplotype_complexity(&self) -> Option<Complexity> {
+        let windows = self.haplotypes.iter().filter_map(|x| x.complexity()).collect::<Vec<Complexity>>();
+
+        if windows.is_empty() { None } else { Some(Complexity::mean(&windows)) }
+    }
+
+    /// Writes the haplotypes of each window to its own FASTA file in directory `dirname`.
+    pub fn haplotypes_to_fasta<P>(&self, path: P, dirname: &str)
+        where P: AsRef<Path>
+    {
+        let dir = path.as_ref().join(Path::new(dirname));
+        std::fs::create_dir_all(&dir).expect("could not create directory");
+
+        let first = self.range.0 as usize + 1;
+        for window in &self.haplotypes {
+            let filename = format!("{}-{}.fasta", first + window.start, first + window.end - 1);
+
+            std::fs::write(dir.join(filename), window.to_fasta(self.name.as_str(), first))
+                .expect("could not write file");
+        }
+    }
+
+    /// Estimates the full-length haplotypes of the reads spanning columns `start..end`, or by default
+    /// the window as long as the median read that the most reads span.
+    pub fn estimate_global_haplotypes(&mut self, columns: Option<(usize, usize)>, error_rate: f64, min_freq: f64) -> Result<&mut Self> {
+        let reads = self.analysed_reads()?.collect::<Vec<AlignedRead>>();
+        let (start, end) = columns.or_else(|| spanned_region(&reads)).unwrap_or((0, 0));
+        let end = end.min(self.matrices.get_pfm().ncols());
+        let haplotypes = GlobalHaplotypes::new(reads.into_iter(), start.min(end), end, error_rate, min_freq);
+        self.global_haplotypes = Some(haplotypes);
+
+        Ok(self)
+    }
+
+    pub fn get_global_haplotypes(&self) -> Option<&GlobalHaplotypes> {
+        self.global_haplotypes.as_ref()
+    }
+
+    /// Writes the full-length haplotypes in FASTA format and the posteriors of the reads.
+    pub fn global_haplotypes_to_files<P>(&self, path: P, fasta_filename: &str, posteriors_filename: &str)
+        where P: AsRef<Path>
+    {
+        if let Some(haplotypes) = &self.global_haplotypes {
+            std::fs::write(path.as_ref().join(Path::new(fasta_filename)), haplotypes.to_fasta(self.name.as_str(), self.range.0 as usize + 1))
+                .expect("could not write file");
+
+            let mut writer = Writer::from_path(path.as_ref().join(Path::new(posteriors_filename))).expect("could not open file");
+            writer.write_record(haplotypes.posterior_header()).unwrap();
+
+            for record in haplotypes.posterior_records() {
+                writer.write_record(record).unwrap();
+            }
+        }
+    }
+
+    /// Measures linkage disequilibrium between the variable sites of the analysed reads.
+    pub fn compute_linkage(&mut self, min_freq: f64, min_reads: u64) -> Result<&mut Self> {
+        let linkage = Linkage::new(self.analysed_reads()?, self.matrices.get_pfm(), min_freq, min_reads);
+        self.linkage = Some(linkage);
+
+        Ok(self)
+    }
+
+    pub fn get_linkage(&self) -> Option<&Linkage> {
+        self.linkage.as_ref()
+    }
+
+    /// Writes the linkage disequilibrium of every pair of variable sites.
+    pub fn linkage_to_csv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        if let Some(linkage) = &self.linkage {
+            let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+            writer.write_record(Linkage::header()).unwrap();
+
+            for record in linkage.records(self.range.0 as usize + 1) {
+                writer.write_record(record).unwrap();
+            }
+        }
+    }
+
+    /// Measures the mutual information between the bases of the variable sites of the analysed reads.
+    pub fn compute_covariation(&mut self, min_freq: f64, min_reads: u64, permutations: usize, seed: u64) -> Result<&mut Self> {
+        let covariation = Covariation::new(self.analysed_reads()?, self.matrices.get_pfm(), min_freq, min_reads, permutations, seed);
+        self.covariation = Some(covariation);
+
+        Ok(self)
+    }
+
+    pub fn get_covariation(&self) -> Option<&Covariation> {
+        self.covariation.as_ref()
+    }
+
+    /// Writes the coupled positions, from the strongest to the weakest corrected mutual information.
+    pub fn covariation_to_csv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        if let Some(covariation) = &self.covariation {
+            let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+            writer.write_record(Covariation::header()).unwrap();
+
+            for record in covariation.records(self.range.0 as usize + 1) {
+                writer.write_record(record).unwrap();
+            }
+        }
+    }
+
+    /// Synonymous and nonsynonymous diversity of every coding region, computed by `translate`.
+    pub fn selection(&self) -> &[Selection] {
+        self.selection.as_slice()
+    }
+
+    /// Writes πN, πS and dN/dS of every codon of each coding region.
+    pub fn selection_to_csv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        writer.write_record(Selection::header()).unwrap();
+
+        for selection in self.selection() {
+            for record in selection.records() {
+                writer.write_record(record).unwrap();
+            }
+        }
+    }
+
+    /// Writes the amino-acid PFM of every coding region, one row per codon.
+    pub fn proteins_to_csv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        writer.write_record(ProteinMatrices::header()).unwrap();
+
+        for protein in &self.proteins {
+            for record in protein.records() {
+                writer.write_record(record).unwrap();
+            }
+        }
+    }
+
+    pub fn get_hypermutation(&self) -> Option<&Hypermutation> {
+        self.hypermutation.as_ref()
+    }
+
+    pub fn hypermutated_to_csv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        writer.write_record(ReadHypermutation::header()).unwrap();
+
+        if let Some(hypermutation) = &self.hypermutation {
+            for read in hypermutation.get_hypermutated() {
+                writer.write_record(read.record()).unwrap();
+            }
+        }
+    }
+
     pub fn pfm_to_csv<P>(&self, path: P, filename: &str)
         where P: AsRef<Path>
     {
-        self.matrices.pfm_to_csv(path, filename);
+        self.matrices.pfm_to_csv(path, filename, self.name.as_str(), self.range.0 as usize + 1);
     }
 }
\ No newline at end of file
@@ -0,0 +1,179 @@
+use ndarray::{ArrayView, Ix2};
+
+use crate::stats::{ranks, ties, permutation_p_value};
+
+/// A test of whether samples differ between groups, with a permutation p-value.
+///
+/// Groups are given as `(name, sample indices)`, as returned by `BamData::group_members`.
+#[derive(Debug, Clone)]
+pub struct GroupTest {
+    pub test: &'static str,
+    pub variable: &'static str,
+    pub groups: Vec<String>,
+    pub statistic: f64,
+    pub effect_size: f64,
+    pub effect_measure: &'static str,
+    pub p_value: f64,
+    pub permutations: usize,
+}
+
+/// Group label of each sample that belongs to a group, and the sample itself.
+fn labelled(groups: &[(String, Vec<usize>)]) -> (Vec<usize>, Vec<usize>) {
+    groups.iter()
+        .enumerate()
+        .flat_map(|(g, (_, samples))| samples.iter().map(move |s| (g, *s)))
+        .unzip()
+}
+
+impl GroupTest {
+    /// PERMANOVA (Anderson, 2001) on a distance matrix, with R² as the effect size.
+    ///
+    /// Returns `None` with fewer than two groups or no more samples than groups.
+    pub fn permanova(distances: ArrayView<f64, Ix2>, groups: &[(String, Vec<usize>)], permutations: usize, seed: u64) -> Option<GroupTest> {
+        let (labels, samples) = labelled(groups);
+        let (n, a) = (samples.len(), groups.len());
+        if a < 2 || n <= a {
+            return None;
+        }
+
+        let squared = |i: usize, j: usize| distances[[samples[i], samples[j]]].powi(2);
+
+        let mut total = 0.;
+        for i in 0..n {
+            for j in (i + 1)..n {
+                total += squared(i, j);
+            }
+        }
+        total /= n as f64;
+
+        let within = |labels: &[usize]| {
+            let mut sums = vec![0.; a];
+            let mut sizes = vec![0; a];
+            for i in 0..n {
+                sizes[labels[i]] += 1;
+                for j in (i + 1)..n {
+                    if labels[i] == labels[j] {
+                        sums[labels[i]] += squared(i, j);
+                    }
+                }
+            }
+
+            sums.iter().zip(sizes.iter()).map(|(s, k)| s / *k as f64).sum::<f64>()
+        };
+        let pseudo_f = |labels: &[usize]| {
+            let w = within(labels);
+
+            ((total - w) / (a - 1) as f64) / (w / (n - a) as f64)
+        };
+
+        let w = within(&labels);
+
+        Some(
+            GroupTest {
+                test: "PERMANOVA",
+                variable: "beta diversity",
+                groups: groups.iter().map(|x| x.0.clone()).collect(),
+                statistic: pseudo_f(&labels),
+                effect_size: 1. - w / total,
+                effect_measure: "R2",
+                p_value: permutation_p_value(&labels, pseudo_f, permutations, seed),
+                permutations,
+            }
+        )
+    }
+
+    /// Mann–Whitney U test with two groups, Kruskal–Wallis H test with more, ignoring NaN values.
+    ///
+    /// The effect size is the rank-biserial correlation for Mann–Whitney (positive when the first
+    /// group is larger) and ε² for Kruskal–Wallis. Returns `None` with fewer than two non-empty groups.
+    pub fn rank_test(values: &[f64], variable: &'static str, groups: &[(String, Vec<usize>)], permutations: usize, seed: u64) -> Option<GroupTest> {
+        let groups = groups.iter()
+            .map(|(name, samples)| (name.clone(), samples.iter().copied().filter(|i| !values[*i].is_nan()).collect::<Vec<usize>>()))
+            .filter(|(_, samples)| !samples.is_empty())
+            .collect::<Vec<_>>();
+
+        let (labels, samples) = labelled(&groups);
+        let (n, a) = (samples.len() as f64, groups.len());
+        if a < 2 {
+            return None;
+        }
+
+        let observations = samples.iter().map(|i| values[*i]).collect::<Vec<f64>>();
+        let ranks = ranks(&observations);
+        let rank_sums = |labels: &[usize]| {
+            let mut sums = vec![(0., 0.); a];
+            for (label, rank) in labels.iter().zip(ranks.iter()) {
+                sums[*label].0 += rank;
+                sums[*label].1 += 1.;
+            }
+
+            sums
+        };
+
+        let names = groups.iter().map(|x| x.0.clone()).collect();
+
+        if a == 2 {
+            let u = |labels: &[usize]| {
+                let (r, k) = rank_sums(labels)[0];
+
+                r - k * (k + 1.) / 2.
+            };
+            let (n1, n2) = (groups[0].1.len() as f64, groups[1].1.len() as f64);
+            let statistic = u(&labels);
+
+            Some(
+                GroupTest {
+                    test: "Mann-Whitney",
+                    variable,
+                    groups: names,
+                    statistic,
+                    effect_size: 2. * statistic / (n1 * n2) - 1.,
+                    effect_measure: "rank-biserial r",
+                    p_value: permutation_p_value(&labels, |x| (u(x) - n1 * n2 / 2.).abs(), permutations, seed),
+                    permutations,
+                }
+            )
+        } else {
+            let correction = 1. - ties(&observations).iter().map(|t| (t.pow(3) - t) as f64).sum::<f64>() / (n.powi(3) - n);
+            let h = |labels: &[usize]| {
+                let sum = rank_sums(labels).iter().map(|(r, k)| r * r / k).sum::<f64>();
+
+                (12. / (n * (n + 1.)) * sum - 3. * (n + 1.)) / correction
+            };
+            let statistic = h(&labels);
+
+            Some(
+                GroupTest {
+                    test: "Kruskal-Wallis",
+                    variable,
+                    groups: names,
+                    statistic,
+                    effect_size: statistic / (n - 1.),
+                    effect_measure: "epsilon2",
+                    p_value: permutation_p_value(&labels, h, permutations, seed),
+                    permutations,
+                }
+            )
+        }
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["test", "variable", "groups", "statistic", "effect size", "effect measure", "p-value", "permutations"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    pub(crate) fn record(&self) -> Vec<String> {
+        vec![
+            self.test.to_string(),
+            self.variable.to_string(),
+            self.groups.join(" vs "),
+            self.statistic.to_string(),
+            self.effect_size.to_string(),
+            self.effect_measure.to_string(),
+            self.p_value.to_string(),
+            self.permutations.to_string(),
+        ]
+    }
+}
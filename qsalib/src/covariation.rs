@@ -0,0 +1,219 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use ndarray::{Array, ArrayView, Ix2};
+
+use crate::linkage::variable_sites;
+use crate::reads::AlignedRead;
+use crate::stats::{permutation_p_value, benjamini_hochberg};
+
+/// Mutual information, in bits, between the bases of two positions observed on the same reads.
+fn mutual_information(a: &[usize], b: &[usize]) -> f64 {
+    let n = a.len() as f64;
+    let mut joint = [[0_f64; 4]; 4];
+    for (x, y) in a.iter().zip(b.iter()) {
+        joint[*x][*y] += 1.;
+    }
+
+    let rows = joint.map(|x| x.iter().sum::<f64>());
+    let cols = (0..4).map(|j| joint.iter().map(|x| x[j]).sum::<f64>()).collect::<Vec<f64>>();
+
+    joint.iter()
+        .enumerate()
+        .flat_map(|(i, x)| x.iter().enumerate().map(move |(j, c)| (i, j, *c)))
+        .filter(|(_, _, c)| *c > 0.)
+        .map(|(i, j, c)| c / n * (c * n / (rows[i] * cols[j])).log2())
+        .sum()
+}
+
+/// Coupling between two variable positions, from the bases of the reads covering both.
+#[derive(Debug, Clone)]
+pub struct Coupling {
+    /// Columns of the two positions.
+    pub a: usize,
+    pub b: usize,
+    /// Joint base counts, rows for `a` and columns for `b`.
+    pub counts: [[u64; 4]; 4],
+    /// Mutual information, in bits.
+    pub mi: f64,
+    /// Mutual information after the average product correction.
+    pub apc: f64,
+    pub p_value: f64,
+    pub q_value: f64,
+}
+
+impl Coupling {
+    pub fn reads(&self) -> u64 {
+        self.counts.iter().flatten().sum()
+    }
+}
+
+/// Covariation of the variable positions of a sample, as mutual information between their bases
+/// on the same reads, to point at coevolving positions such as compensatory mutations.
+///
+/// Variable positions are those whose second most frequent base reaches `min_freq`. Mates sharing
+/// a name are joined into one fragment, and positions where they disagree are left out. Pairs
+/// covered by fewer than `min_reads` fragments are not measured. Mutual information is corrected
+/// for the background shared by each position with all the others by the average product
+/// correction of Dunn et al. (2008): MIp(a, b) = MI(a, b) - MI(a, ·) MI(b, ·) / MI(·, ·). Its
+/// significance comes from permuting the bases of one position over the fragments, and p-values
+/// are corrected with the Benjamini–Hochberg procedure.
+pub struct Covariation {
+    columns: Vec<usize>,
+    couplings: Vec<Coupling>,
+    pub permutations: usize,
+}
+
+impl Covariation {
+    pub fn new<I>(reads: I, pfm: ArrayView<u64, Ix2>, min_freq: f64, min_reads: u64, permutations: usize, seed: u64) -> Covariation
+        where I: Iterator<Item = AlignedRead>
+    {
+        let columns = variable_sites(pfm, min_freq).into_iter().map(|x| x.column).collect::<Vec<usize>>();
+
+        // known bases of each fragment at the variable positions, mates merged by name
+        let mut fragments: HashMap<String, HashMap<usize, Option<u8>>> = HashMap::new();
+        for read in reads {
+            let first = columns.partition_point(|x| *x < read.offset);
+            let fragment = fragments.entry(read.name.clone()).or_default();
+
+            for (i, column) in columns.iter().enumerate().skip(first).take_while(|(_, x)| **x < read.end()) {
+                if let Some(base) = read.base_at(*column) {
+                    let call = fragment.entry(i).or_insert(Some(base));
+                    if *call != Some(base) {
+                        *call = None;
+                    }
+                }
+            }
+        }
+
+        let mut observations: HashMap<(usize, usize), (Vec<usize>, Vec<usize>)> = HashMap::new();
+        for fragment in fragments.values() {
+            let mut calls = fragment.iter()
+                .filter_map(|(i, x)| x.map(|base| (*i, base as usize)))
+                .collect::<Vec<(usize, usize)>>();
+            calls.sort_unstable();
+
+            for (k, (i, x)) in calls.iter().enumerate() {
+                for (j, y) in calls.iter().skip(k + 1) {
+                    let pair = observations.entry((*i, *j)).or_default();
+                    pair.0.push(*x);
+                    pair.1.push(*y);
+                }
+            }
+        }
+
+        let mut pairs = observations.into_iter()
+            .filter(|(_, (a, _))| a.len() as u64 >= min_reads.max(1))
+            .collect::<Vec<_>>();
+        pairs.sort_by_key(|x| x.0);
+
+        let mut couplings = pairs.iter()
+            .enumerate()
+            .map(|(k, ((i, j), (a, b)))| {
+                let mut counts = [[0; 4]; 4];
+                for (x, y) in a.iter().zip(b.iter()) {
+                    counts[*x][*y] += 1;
+                }
+
+                let statistic = |labels: &[usize]| mutual_information(a, labels);
+
+                Coupling {
+                    a: columns[*i],
+                    b: columns[*j],
+                    counts,
+                    mi: mutual_information(a, b),
+                    apc: f64::NAN,
+                    p_value: permutation_p_value(b, statistic, permutations, seed.wrapping_add(k as u64)),
+                    q_value: f64::NAN,
+                }
+            })
+            .collect::<Vec<Coupling>>();
+
+        // average product correction, over the pairs that were measured
+        let mut totals: HashMap<usize, (f64, f64)> = HashMap::new();
+        for x in &couplings {
+            for column in [x.a, x.b] {
+                let total = totals.entry(column).or_insert((0., 0.));
+                total.0 += x.mi;
+                total.1 += 1.;
+            }
+        }
+        let mean = couplings.iter().map(|x| x.mi).sum::<f64>() / couplings.len() as f64;
+        let background = |column: usize| totals[&column].0 / totals[&column].1;
+
+        for x in couplings.iter_mut() {
+            x.apc = if mean > 0. { x.mi - background(x.a) * background(x.b) / mean } else { 0. };
+        }
+
+        let q_values = benjamini_hochberg(&couplings.iter().map(|x| x.p_value).collect::<Vec<f64>>());
+        for (x, q) in couplings.iter_mut().zip(q_values) {
+            x.q_value = q;
+        }
+
+        Covariation {
+            columns,
+            couplings,
+            permutations,
+        }
+    }
+
+    /// Columns of the variable positions.
+    pub fn get_columns(&self) -> &[usize] {
+        self.columns.as_slice()
+    }
+
+    /// Couplings ordered by their pair of columns.
+    pub fn get_couplings(&self) -> &[Coupling] {
+        self.couplings.as_slice()
+    }
+
+    /// Couplings from the strongest to the weakest corrected mutual information.
+    pub fn ranked(&self) -> Vec<&Coupling> {
+        let mut ranked = self.couplings.iter().collect::<Vec<&Coupling>>();
+        ranked.sort_by(|a, b| b.apc.partial_cmp(&a.apc).unwrap_or(Ordering::Equal).then((a.a, a.b).cmp(&(b.a, b.b))));
+
+        ranked
+    }
+
+    /// Symmetric matrix of the corrected mutual information between the variable positions, NaN
+    /// for the pairs that were not measured.
+    pub fn contact_map(&self) -> Array<f64, Ix2> {
+        let n = self.columns.len();
+        let index = self.columns.iter().enumerate().map(|(i, x)| (*x, i)).collect::<HashMap<usize, usize>>();
+
+        let mut map = Array::<f64, Ix2>::from_elem((n, n), f64::NAN);
+        for x in &self.couplings {
+            let (i, j) = (index[&x.a], index[&x.b]);
+            map[[i, j]] = x.apc;
+            map[[j, i]] = x.apc;
+        }
+
+        map
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["rank", "position a", "position b", "reads", "MI", "MIp", "p-value", "q-value", "permutations"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// Ranked records, numbered from `first` (1-based reference coordinate of the first column).
+    pub(crate) fn records(&self, first: usize) -> Vec<Vec<String>> {
+        self.ranked()
+            .iter()
+            .enumerate()
+            .map(|(rank, x)| vec![
+                (rank + 1).to_string(),
+                (first + x.a).to_string(),
+                (first + x.b).to_string(),
+                x.reads().to_string(),
+                x.mi.to_string(),
+                x.apc.to_string(),
+                x.p_value.to_string(),
+                x.q_value.to_string(),
+                self.permutations.to_string(),
+            ])
+            .collect()
+    }
+}
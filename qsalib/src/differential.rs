@@ -0,0 +1,132 @@
+use ndarray::{ArrayView, Ix1, Ix2, Axis};
+
+use crate::matrices::Matrices;
+use crate::stats::{g_test, mann_whitney, benjamini_hochberg};
+
+/// Tests of a single position between two groups of samples.
+#[derive(Debug, Clone)]
+pub struct PositionTest {
+    /// Column of the position in the matrices.
+    pub column: usize,
+    /// Efficiency of the pooled PFM of each group.
+    pub efficiency: (f64, f64),
+    /// G-test of the base composition of the pooled PFMs.
+    pub g: f64,
+    pub g_p_value: f64,
+    pub g_q_value: f64,
+    /// Mann–Whitney test of the efficiencies of the samples of the two groups.
+    pub u: f64,
+    pub u_p_value: f64,
+    pub u_q_value: f64,
+}
+
+/// Normalised Shannon entropy of the bases of a PFM column, NaN if the column is empty.
+fn pooled_efficiency(column: ArrayView<u64, Ix1>) -> f64 {
+    let total = column.sum() as f64;
+    if total == 0. {
+        return f64::NAN;
+    }
+
+    - column.iter()
+        .filter(|x| **x > 0)
+        .map(|x| *x as f64 / total)
+        .map(|x| x * x.log2() / 4_f64.log2())
+        .sum::<f64>()
+}
+
+/// Per-position comparison of two groups of samples, with Benjamini–Hochberg correction over the
+/// positions that could be tested.
+///
+/// Base composition is compared with a G-test on the pooled PFMs, while efficiency is compared with
+/// a two-sided Mann–Whitney test on the samples covering the position.
+pub struct DifferentialDiversity {
+    pub a: String,
+    pub b: String,
+    positions: Vec<PositionTest>,
+}
+
+impl DifferentialDiversity {
+    pub fn new(a: &str, pfm_a: ArrayView<u64, Ix2>, samples_a: &[&Matrices], b: &str, pfm_b: ArrayView<u64, Ix2>, samples_b: &[&Matrices]) -> DifferentialDiversity {
+        let efficiencies = |samples: &[&Matrices], col: usize| samples.iter()
+            .filter(|x| x.get_pfm().column(col).sum() > 0)
+            .map(|x| x.get_efficiency()[col])
+            .collect::<Vec<f64>>();
+
+        let mut positions = pfm_a.axis_iter(Axis(1))
+            .zip(pfm_b.axis_iter(Axis(1)))
+            .enumerate()
+            .map(|(column, (x, y))| {
+                let (g, g_p_value) = if x.sum() > 0 && y.sum() > 0 {
+                    g_test(&[x.to_vec(), y.to_vec()])
+                } else {
+                    (f64::NAN, f64::NAN)
+                };
+                let (u, u_p_value) = mann_whitney(&efficiencies(samples_a, column), &efficiencies(samples_b, column));
+
+                PositionTest {
+                    column,
+                    efficiency: (pooled_efficiency(x), pooled_efficiency(y)),
+                    g,
+                    g_p_value,
+                    g_q_value: f64::NAN,
+                    u,
+                    u_p_value,
+                    u_q_value: f64::NAN,
+                }
+            })
+            .collect::<Vec<PositionTest>>();
+
+        let g_q = benjamini_hochberg(&positions.iter().map(|x| x.g_p_value).collect::<Vec<f64>>());
+        let u_q = benjamini_hochberg(&positions.iter().map(|x| x.u_p_value).collect::<Vec<f64>>());
+        for (position, (g, u)) in positions.iter_mut().zip(g_q.into_iter().zip(u_q)) {
+            position.g_q_value = g;
+            position.u_q_value = u;
+        }
+
+        DifferentialDiversity {
+            a: a.to_string(),
+            b: b.to_string(),
+            positions,
+        }
+    }
+
+    pub fn get_positions(&self) -> &[PositionTest] {
+        self.positions.as_slice()
+    }
+
+    /// Positions whose base composition differs at a false discovery rate of `fdr`.
+    pub fn significant(&self, fdr: f64) -> Vec<&PositionTest> {
+        self.positions.iter().filter(|x| x.g_q_value <= fdr).collect()
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        [
+            "a", "b", "position", "efficiency a", "efficiency b",
+            "G", "G p-value", "G q-value", "U", "U p-value", "U q-value", "regions",
+        ]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// One record per position, numbered from `first` (1-based reference coordinate of the first
+    /// column), with the names of the regions each position falls in.
+    pub(crate) fn records(&self, first: usize, regions: &[String]) -> Vec<Vec<String>> {
+        self.positions.iter()
+            .map(|x| vec![
+                self.a.clone(),
+                self.b.clone(),
+                (first + x.column).to_string(),
+                x.efficiency.0.to_string(),
+                x.efficiency.1.to_string(),
+                x.g.to_string(),
+                x.g_p_value.to_string(),
+                x.g_q_value.to_string(),
+                x.u.to_string(),
+                x.u_p_value.to_string(),
+                x.u_q_value.to_string(),
+                regions.get(x.column).cloned().unwrap_or_default(),
+            ])
+            .collect()
+    }
+}
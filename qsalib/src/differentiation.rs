@@ -0,0 +1,187 @@
+use ndarray::{Array, ArrayView, Ix1, Ix2, Axis, Zip};
+
+use crate::error::{QSAError, Result};
+
+/// Heterozygosities of two populations at a single position.
+#[derive(Debug, Clone, Copy, Default)]
+struct Heterozygosity {
+    /// Mean within-population heterozygosity, corrected for sample size (Hudson).
+    within: f64,
+    /// Probability that two bases, one from each population, differ (Hudson).
+    between: f64,
+    /// Mean within-population gene diversity, Hs.
+    hs: f64,
+    /// Gene diversity of the populations pooled with equal weights, Ht.
+    ht: f64,
+}
+
+impl Heterozygosity {
+    fn new(a: ArrayView<u64, Ix1>, b: ArrayView<u64, Ix1>) -> Option<Heterozygosity> {
+        let (na, nb) = (a.sum() as f64, b.sum() as f64);
+        if na < 2. || nb < 2. {
+            return None;
+        }
+
+        let (pa, pb) = (a.map(|x| *x as f64 / na), b.map(|x| *x as f64 / nb));
+        let (ha, hb) = (1. - pa.dot(&pa), 1. - pb.dot(&pb));
+        let pbar = (&pa + &pb) / 2.;
+
+        Some(
+            Heterozygosity {
+                within: (ha * na / (na - 1.) + hb * nb / (nb - 1.)) / 2.,
+                between: 1. - pa.dot(&pb),
+                hs: (ha + hb) / 2.,
+                ht: 1. - pbar.dot(&pbar),
+            }
+        )
+    }
+
+    fn fst(&self) -> f64 {
+        if self.between > 0. { 1. - self.within / self.between } else { f64::NAN }
+    }
+
+    fn gst(&self) -> f64 {
+        if self.ht > 0. { (self.ht - self.hs) / self.ht } else { f64::NAN }
+    }
+
+    /// Jost's D for two populations.
+    fn jost_d(&self) -> f64 {
+        if self.hs < 1. { 2. * (self.ht - self.hs) / (1. - self.hs) } else { f64::NAN }
+    }
+}
+
+/// Differentiation between two populations (samples or pooled groups of samples), from their PFMs.
+///
+/// Per-position values are NaN where a population has fewer than two bases or where the statistic
+/// is undefined (e.g. Fst of a position fixed for the same base in both). Genome-wide values are an
+/// error when no position is covered by two bases or more in both populations.
+pub struct Differentiation {
+    heterozygosity: Vec<Option<Heterozygosity>>,
+    fst: Array<f64, Ix1>,
+    gst: Array<f64, Ix1>,
+    jost_d: Array<f64, Ix1>,
+}
+
+impl Differentiation {
+    pub fn new(a: ArrayView<u64, Ix2>, b: ArrayView<u64, Ix2>) -> Differentiation {
+        let heterozygosity = a.axis_iter(Axis(1))
+            .zip(b.axis_iter(Axis(1)))
+            .map(|(x, y)| Heterozygosity::new(x, y))
+            .collect::<Vec<_>>();
+
+        let stat = |f: fn(&Heterozygosity) -> f64| heterozygosity.iter()
+            .map(|x| x.as_ref().map(f).unwrap_or(f64::NAN))
+            .collect::<Array<f64, Ix1>>();
+
+        Differentiation {
+            fst: stat(Heterozygosity::fst),
+            gst: stat(Heterozygosity::gst),
+            jost_d: stat(Heterozygosity::jost_d),
+            heterozygosity,
+        }
+    }
+
+    /// Positions both populations cover with two bases or more.
+    pub fn shared_positions(&self) -> usize {
+        self.heterozygosity.iter().flatten().count()
+    }
+
+    /// Sum of the heterozygosities over all the positions both populations cover.
+    fn total(&self) -> Result<Heterozygosity> {
+        if self.shared_positions() == 0 {
+            return Err(QSAError::NoSharedPositions);
+        }
+
+        Ok(
+            self.heterozygosity.iter()
+                .flatten()
+                .fold(Heterozygosity::default(), |acc, x| Heterozygosity {
+                    within: acc.within + x.within,
+                    between: acc.between + x.between,
+                    hs: acc.hs + x.hs,
+                    ht: acc.ht + x.ht,
+                })
+        )
+    }
+
+    pub fn get_fst(&self) -> ArrayView<'_, f64, Ix1> {
+        self.fst.view()
+    }
+
+    pub fn get_gst(&self) -> ArrayView<'_, f64, Ix1> {
+        self.gst.view()
+    }
+
+    pub fn get_jost_d(&self) -> ArrayView<'_, f64, Ix1> {
+        self.jost_d.view()
+    }
+
+    /// Genome-wide Hudson's Fst, as a ratio of averages (Bhatia et al., 2013).
+    pub fn fst(&self) -> Result<f64> {
+        Ok(self.total()?.fst())
+    }
+
+    /// Genome-wide Nei's Gst, from Hs and Ht averaged over the positions.
+    pub fn gst(&self) -> Result<f64> {
+        Ok(self.total()?.gst())
+    }
+
+    /// Genome-wide Jost's D, from Hs and Ht averaged over the positions.
+    pub fn jost_d(&self) -> Result<f64> {
+        let total = self.total()?;
+        let n = self.shared_positions() as f64;
+
+        Ok(Heterozygosity { hs: total.hs / n, ht: total.ht / n, ..total }.jost_d())
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["level", "a", "b", "positions", "fst", "gst", "jost D"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// Genome-wide values, `NA` when the populations share no covered position.
+    pub(crate) fn record(&self, level: &str, a: &str, b: &str) -> Vec<String> {
+        let value = |x: Result<f64>| x.map_or("NA".to_string(), |x| x.to_string());
+
+        vec![
+            level.to_string(),
+            a.to_string(),
+            b.to_string(),
+            self.shared_positions().to_string(),
+            value(self.fst()),
+            value(self.gst()),
+            value(self.jost_d()),
+        ]
+    }
+
+    pub(crate) fn position_header() -> Vec<String> {
+        ["level", "a", "b", "position", "fst", "gst", "jost D"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// One record per position, numbered from `first` (1-based reference coordinate of the first column).
+    pub(crate) fn position_records(&self, level: &str, a: &str, b: &str, first: usize) -> Vec<Vec<String>> {
+        let mut records: Vec<Vec<String>> = Vec::new();
+
+        Zip::indexed(&self.fst)
+            .and(&self.gst)
+            .and(&self.jost_d)
+            .for_each(|i, fst, gst, jost_d| {
+                records.push(vec![
+                    level.to_string(),
+                    a.to_string(),
+                    b.to_string(),
+                    (first + i).to_string(),
+                    fst.to_string(),
+                    gst.to_string(),
+                    jost_d.to_string(),
+                ]);
+            });
+
+        records
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+use std::str::FromStr;
+
+use ndarray::{ArrayView, Axis, Ix1, Zip};
+
+use crate::matrices::Matrices;
+use crate::error::{QSAError, Result};
+
+/// A distance between two samples, used to build the β-diversity matrix.
+pub trait Distance {
+    fn name(&self) -> &'static str;
+
+    /// Distance between two samples, or an error when it is not defined for them.
+    fn distance(&self, a: &Matrices, b: &Matrices) -> Result<f64>;
+}
+
+/// Columns of the PPMs of two samples at the positions covered in both.
+fn shared_columns<'a>(a: &'a Matrices, b: &'a Matrices) -> Vec<(ArrayView<'a, f64, Ix1>, ArrayView<'a, f64, Ix1>)> {
+    let (pa, pb) = (a.get_ppm(), b.get_ppm());
+
+    (0..pa.ncols().min(pb.ncols()))
+        .map(move |i| (pa.index_axis_move(Axis(1), i), pb.index_axis_move(Axis(1), i)))
+        .filter(|(x, y)| x.sum() > 0. && y.sum() > 0.)
+        .collect()
+}
+
+/// Mean of the values, or an error when there is none, as the samples share no covered position.
+fn mean<I>(values: I) -> Result<f64>
+    where I: Iterator<Item = f64>
+{
+    let (sum, n) = values.fold((0., 0), |acc, x| (acc.0 + x, acc.1 + 1));
+
+    if n == 0 { Err(QSAError::NoSharedPositions) } else { Ok(sum / n as f64) }
+}
+
+/// Absolute difference in α-diversity, the original β-diversity of the README.
+pub struct AlphaDifference;
+
+impl Distance for AlphaDifference {
+    fn name(&self) -> &'static str {
+        "alpha"
+    }
+
+    fn distance(&self, a: &Matrices, b: &Matrices) -> Result<f64> {
+        Ok((a.alpha_diversity() - b.alpha_diversity()).abs())
+    }
+}
+
+/// Jensen–Shannon divergence (base 2, in [0, 1]) averaged over the shared positions.
+///
+/// It is not defined for samples sharing no covered position.
+pub struct JensenShannon;
+
+impl Distance for JensenShannon {
+    fn name(&self) -> &'static str {
+        "jsd"
+    }
+
+    fn distance(&self, a: &Matrices, b: &Matrices) -> Result<f64> {
+        let kl = |p: f64, m: f64| if p > 0. { p * (p / m).log2() } else { 0. };
+
+        mean(
+            shared_columns(a, b).iter()
+                .map(|(p, q)| {
+                    Zip::from(p).and(q).fold(0., |acc, p, q| {
+                        let m = (p + q) / 2.;
+
+                        acc + (kl(*p, m) + kl(*q, m)) / 2.
+                    })
+                })
+        )
+    }
+}
+
+/// Hellinger distance between base frequencies averaged over the shared positions.
+///
+/// It is not defined for samples sharing no covered position.
+pub struct Hellinger;
+
+impl Distance for Hellinger {
+    fn name(&self) -> &'static str {
+        "hellinger"
+    }
+
+    fn distance(&self, a: &Matrices, b: &Matrices) -> Result<f64> {
+        mean(
+            shared_columns(a, b).iter()
+                .map(|(p, q)| {
+                    let bc = Zip::from(p).and(q).fold(0., |acc, p, q| acc + (p * q).sqrt());
+
+                    (1. - bc).max(0.).sqrt()
+                })
+        )
+    }
+}
+
+/// Bray–Curtis dissimilarity between the whole PFMs, counts included.
+pub struct BrayCurtis;
+
+impl Distance for BrayCurtis {
+    fn name(&self) -> &'static str {
+        "bray-curtis"
+    }
+
+    fn distance(&self, a: &Matrices, b: &Matrices) -> Result<f64> {
+        let (diff, total) = Zip::from(a.get_pfm())
+            .and(b.get_pfm())
+            .fold((0., 0.), |acc, x, y| {
+                let (x, y) = (*x as f64, *y as f64);
+
+                (acc.0 + (x - y).abs(), acc.1 + x + y)
+            });
+
+        Ok(if total > 0. { diff / total } else { 0. })
+    }
+}
+
+/// Nei's standard genetic distance, treating each shared position as a locus.
+///
+/// It is not defined for samples sharing no covered position, and it is infinite, so an error too,
+/// for samples sharing no base at any of their shared positions.
+pub struct Nei;
+
+impl Distance for Nei {
+    fn name(&self) -> &'static str {
+        "nei"
+    }
+
+    fn distance(&self, a: &Matrices, b: &Matrices) -> Result<f64> {
+        let columns = shared_columns(a, b);
+
+        let jx = mean(columns.iter().map(|(p, _)| p.dot(p)))?;
+        let jy = mean(columns.iter().map(|(_, q)| q.dot(q)))?;
+        let jxy = mean(columns.iter().map(|(p, q)| p.dot(q)))?;
+
+        if jxy > 0. { Ok((-(jxy / (jx * jy).sqrt()).ln()).max(0.)) } else { Err(QSAError::NoSharedBases) }
+    }
+}
+
+/// Distances available out of the box, selectable by name.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Metric {
+    #[default]
+    Alpha,
+    JensenShannon,
+    Hellinger,
+    BrayCurtis,
+    Nei,
+}
+
+impl Metric {
+    pub const NAMES: [&'static str; 5] = ["alpha", "jsd", "hellinger", "bray-curtis", "nei"];
+
+    pub fn distance(&self) -> Box<dyn Distance> {
+        match self {
+            Metric::Alpha => Box::new(AlphaDifference),
+            Metric::JensenShannon => Box::new(JensenShannon),
+            Metric::Hellinger => Box::new(Hellinger),
+            Metric::BrayCurtis => Box::new(BrayCurtis),
+            Metric::Nei => Box::new(Nei),
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = QSAError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "alpha" => Ok(Metric::Alpha),
+            "jsd" => Ok(Metric::JensenShannon),
+            "hellinger" => Ok(Metric::Hellinger),
+            "bray-curtis" => Ok(Metric::BrayCurtis),
+            "nei" => Ok(Metric::Nei),
+            _ => Err(QSAError::UnknownDistance),
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.distance().name())
+    }
+}
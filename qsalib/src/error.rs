@@ -8,6 +8,20 @@ pub enum QSAError {
     BAMNotFound,
     DirNotFound,
     CoverageHole,
+    ReferenceNotFound,
+    ReferenceMismatch,
+    AnnotationNotFound,
+    AnnotationMalformed,
+    UnknownDistance,
+    MetadataNotFound,
+    MetadataMalformed,
+    InvalidDate,
+    GroupNotFound,
+    UnknownLogoKind,
+    RegionOutOfRange,
+    NoSharedPositions,
+    NoSharedBases,
+    NonFiniteDistance,
 }
 
 impl fmt::Display for QSAError {
@@ -21,6 +35,34 @@ impl fmt::Display for QSAError {
                 write!(f, "One of the supplied directories were not found"),
             QSAError::CoverageHole =>
                 write!(f, "One of the supplied BAM files has a coverage hole inside"),
+            QSAError::ReferenceNotFound =>
+                write!(f, "The supplied reference FASTA file was not found"),
+            QSAError::ReferenceMismatch =>
+                write!(f, "The reference sequence does not cover the selected range"),
+            QSAError::AnnotationNotFound =>
+                write!(f, "The supplied annotation file was not found"),
+            QSAError::AnnotationMalformed =>
+                write!(f, "The supplied annotation file is not a valid GFF3 or BED file"),
+            QSAError::UnknownDistance =>
+                write!(f, "Unknown distance, expected one of: alpha, jsd, hellinger, bray-curtis, nei"),
+            QSAError::MetadataNotFound =>
+                write!(f, "The supplied metadata file was not found"),
+            QSAError::MetadataMalformed =>
+                write!(f, "The supplied metadata file needs a header with a sample column and a name on each row"),
+            QSAError::InvalidDate =>
+                write!(f, "One of the dates of the metadata file is neither a valid YYYY-MM-DD date nor a decimal year"),
+            QSAError::GroupNotFound =>
+                write!(f, "One of the groups to compare has no sample in the metadata"),
+            QSAError::UnknownLogoKind =>
+                write!(f, "Unknown logo, expected one of: information, frequency"),
+            QSAError::RegionOutOfRange =>
+                write!(f, "The selected region is not inside the analysed range"),
+            QSAError::NoSharedPositions =>
+                write!(f, "Two samples or groups share no covered position, so the distance or differentiation between them is not defined"),
+            QSAError::NoSharedBases =>
+                write!(f, "Two samples share no base at their covered positions, so Nei's distance between them is infinite"),
+            QSAError::NonFiniteDistance =>
+                write!(f, "The β-diversity matrix has undefined distances, choose another distance or range to ordinate and cluster the samples"),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use crate::reads::AlignedRead;
+use crate::spectrum::NUCLEOTIDES;
+
+/// A distinct local haplotype of a window, as PFM rows.
+#[derive(Debug, Clone)]
+pub struct Haplotype {
+    pub bases: Vec<u8>,
+    pub count: u64,
+    pub frequency: f64,
+}
+
+impl Haplotype {
+    /// Sequence of the haplotype, with `N` for unknown bases.
+    pub fn sequence(&self) -> String {
+        self.bases.iter()
+            .map(|x| NUCLEOTIDES.get(*x as usize).copied().unwrap_or('N'))
+            .collect()
+    }
+
+    /// Number of positions where two haplotypes have different known bases.
+    pub fn mismatches(&self, other: &Haplotype) -> usize {
+        self.bases.iter()
+            .zip(other.bases.iter())
+            .filter(|(a, b)| **a < 4 && **b < 4 && a != b)
+            .count()
+    }
+}
+
+/// Haplotype-level complexity of a quasispecies (Gregori et al., 2016).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Complexity {
+    /// Number of haplotypes.
+    pub haplotypes: f64,
+    /// Shannon entropy of the haplotype frequencies, normalised by the log of the number of reads.
+    pub entropy: f64,
+    /// Mutation frequency: mean fraction of sites where a read differs from the dominant haplotype.
+    pub mutation_frequency: f64,
+    /// Mutation frequency by entity: the same, with every haplotype counted once.
+    pub mfe: f64,
+}
+
+impl Complexity {
+    /// Mean complexity over windows, NaN without any.
+    pub fn mean(values: &[Complexity]) -> Complexity {
+        let n = values.len() as f64;
+        let sum = |f: fn(&Complexity) -> f64| values.iter().map(f).sum::<f64>() / n;
+
+        Complexity {
+            haplotypes: sum(|x| x.haplotypes),
+            entropy: sum(|x| x.entropy),
+            mutation_frequency: sum(|x| x.mutation_frequency),
+            mfe: sum(|x| x.mfe),
+        }
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["sample", "alpha diversity", "windows", "haplotypes", "haplotype entropy", "mutation frequency", "Mfe"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// Record of a sample, next to its position-based α-diversity.
+    pub(crate) fn record(&self, sample: &str, alpha: f64, windows: usize) -> Vec<String> {
+        vec![
+            sample.to_string(),
+            alpha.to_string(),
+            windows.to_string(),
+            self.haplotypes.to_string(),
+            self.entropy.to_string(),
+            self.mutation_frequency.to_string(),
+            self.mfe.to_string(),
+        ]
+    }
+
+    pub(crate) fn window_header() -> Vec<String> {
+        ["sample", "start", "end", "reads", "haplotypes", "haplotype entropy", "mutation frequency", "Mfe"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// Record of a window, with 1-based inclusive reference coordinates.
+    pub(crate) fn window_record(&self, sample: &str, start: usize, end: usize, reads: u64) -> Vec<String> {
+        vec![
+            sample.to_string(),
+            start.to_string(),
+            end.to_string(),
+            reads.to_string(),
+            self.haplotypes.to_string(),
+            self.entropy.to_string(),
+            self.mutation_frequency.to_string(),
+            self.mfe.to_string(),
+        ]
+    }
+}
+
+/// Local haplotypes of the reads fully covering a window of the analysed range.
+#[derive(Debug, Clone)]
+pub struct HaplotypeWindow {
+    /// First column of the window and the column right after its end.
+    pub start: usize,
+    pub end: usize,
+    /// Reads covering the window before error correction.
+    pub reads: u64,
+    /// Haplotypes after error correction, from the most to the least frequent.
+    haplotypes: Vec<Haplotype>,
+}
+
+impl HaplotypeWindow {
+    /// Collapses the reads of a window into distinct haplotypes and corrects them.
+    ///
+    /// Haplotypes with a frequency below `min_freq` are taken as sequencing errors: they are
+    /// merged into the closest haplotype above it if they differ from it by at most `mismatches`
+    /// bases (ties going to the most frequent one), and are dropped otherwise. Reads with unknown
+    /// bases match any base there.
+    pub fn new(start: usize, end: usize, counts: HashMap<Vec<u8>, u64>, min_freq: f64, mismatches: usize) -> HaplotypeWindow {
+        let reads = counts.values().sum::<u64>();
+
+        let mut raw = counts.into_iter()
+            .map(|(bases, count)| Haplotype { bases, count, frequency: 0. })
+            .collect::<Vec<Haplotype>>();
+        raw.sort_by(|a, b| b.count.cmp(&a.count).then(a.bases.cmp(&b.bases)));
+
+        let threshold = min_freq * reads as f64;
+        let (mut haplotypes, minor): (Vec<Haplotype>, Vec<Haplotype>) = raw.into_iter()
+            .partition(|x| x.count as f64 >= threshold && !x.bases.contains(&4));
+
+        for haplotype in minor {
+            let closest = haplotypes.iter_mut()
+                .map(|x| (x.mismatches(&haplotype), x))
+                .filter(|(d, _)| *d <= mismatches)
+                .min_by_key(|(d, _)| *d);
+
+            if let Some((_, x)) = closest {
+                x.count += haplotype.count;
+            }
+        }
+
+        let total = haplotypes.iter().map(|x| x.count).sum::<u64>();
+        for haplotype in haplotypes.iter_mut() {
+            haplotype.frequency = haplotype.count as f64 / total as f64;
+        }
+        haplotypes.sort_by(|a, b| b.count.cmp(&a.count).then(a.bases.cmp(&b.bases)));
+
+        HaplotypeWindow {
+            start,
+            end,
+            reads,
+            haplotypes,
+        }
+    }
+
+    pub fn get_haplotypes(&self) -> &[Haplotype] {
+        self.haplotypes.as_slice()
+    }
+
+    /// Complexity of the haplotypes after error correction, `None` if there are none.
+    pub fn complexity(&self) -> Option<Complexity> {
+        let dominant = self.haplotypes.first()?;
+        let reads = self.haplotypes.iter().map(|x| x.count).sum::<u64>() as f64;
+        let width = (self.end - self.start) as f64;
+
+        let entropy = - self.haplotypes.iter().map(|x| x.frequency * x.frequency.ln()).sum::<f64>();
+        let distances = self.haplotypes.iter().map(|x| x.mismatches(dominant) as f64 / width).collect::<Vec<f64>>();
+
+        Some(
+            Complexity {
+                haplotypes: self.haplotypes.len() as f64,
+                entropy: if reads > 1. { entropy / reads.ln() } else { 0. },
+                mutation_frequency: self.haplotypes.iter().zip(distances.iter()).map(|(x, d)| x.frequency * d).sum(),
+                mfe: distances.iter().sum::<f64>() / self.haplotypes.len() as f64,
+            }
+        )
+    }
+
+    /// Haplotypes in FASTA format, with positions numbered from `first` (1-based reference
+    /// coordinate of the first column) and frequencies in the headers.
+    pub fn to_fasta(&self, sample: &str, first: usize) -> String {
+        let (start, end) = (first + self.start, first + self.end - 1);
+
+        self.haplotypes.iter()
+            .enumerate()
+            .map(|(i, x)| format!(
+                ">{}_{}-{}_{} freq={:.6} count={}\n{}\n",
+                sample, start, end, i + 1, x.frequency, x.count, x.sequence()
+            ))
+            .collect()
+    }
+}
+
+/// Local haplotypes in windows of `width` columns, starting every `step` columns, over `ncols`
+/// columns; each window only uses the reads covering it entirely.
+pub fn local_haplotypes<I>(reads: I, ncols: usize, width: usize, step: usize, min_freq: f64, mismatches: usize) -> Vec<HaplotypeWindow>
+    where I: Iterator<Item = AlignedRead>
+{
+    if width == 0 || step == 0 || width > ncols {
+        return Vec::new();
+    }
+
+    let starts = (0..=(ncols - width)).step_by(step).collect::<Vec<usize>>();
+    let mut counts: Vec<HashMap<Vec<u8>, u64>> = vec![HashMap::new(); starts.len()];
+
+    for read in reads {
+        // windows starting at or after the read and ending before its end
+        let first = read.offset.div_ceil(step);
+
+        for (k, start) in starts.iter().enumerate().skip(first) {
+            if start + width > read.end() {
+                break
+            }
+
+            let bases = read.bases[(start - read.offset)..(start - read.offset + width)].to_vec();
+            *counts[k].entry(bases).or_insert(0) += 1;
+        }
+    }
+
+    starts.into_iter()
+        .zip(counts)
+        .map(|(start, counts)| HaplotypeWindow::new(start, start + width, counts, min_freq, mismatches))
+        .collect()
+}
@@ -0,0 +1,157 @@
+use std::collections::HashSet;
+
+use ndarray::{ArrayView, Ix1};
+
+use crate::reads::AlignedRead;
+use crate::stats::fisher_exact_greater;
+
+/// Mismatches of a single read against the reference, following Hypermut 2.0.
+///
+/// A reference G is in the hypermutation context when the read shows a purine right after it
+/// and anything but a C two positions after it (GRD), in the control context otherwise.
+#[derive(Debug, Clone)]
+pub struct ReadHypermutation {
+    /// Position of the read inside the BAM file.
+    pub index: usize,
+    pub name: String,
+    /// Counts of reference (first index) against read (second index) bases.
+    pub mismatches: [[u32; 4]; 4],
+    /// G→A mutations and G→A|G sites in the GRD context.
+    pub hyper: (u32, u32),
+    /// G→A mutations and G→A|G sites in the control context.
+    pub control: (u32, u32),
+    pub p_value: f64,
+}
+
+impl ReadHypermutation {
+    pub fn new(index: usize, read: &AlignedRead, reference: ArrayView<u8, Ix1>) -> ReadHypermutation {
+        let mut mismatches = [[0; 4]; 4];
+        let mut hyper = (0, 0);
+        let mut control = (0, 0);
+
+        for (i, nt) in read.bases.iter().enumerate() {
+            let col = read.offset + i;
+
+            let refnt = match reference.get(col) {
+                Some(refnt) if *refnt < 4 && *nt < 4 => *refnt,
+                _ => continue,
+            };
+
+            mismatches[refnt as usize][*nt as usize] += 1;
+
+            // only G→A and G→G count towards the test
+            if refnt != 2 || (*nt != 0 && *nt != 2) {
+                continue
+            }
+
+            let context = match (read.base_at(col + 1), read.base_at(col + 2)) {
+                (Some(r), Some(d)) => (r == 0 || r == 2) && d != 1,
+                _ => continue,
+            };
+
+            let counts = if context { &mut hyper } else { &mut control };
+            counts.1 += 1;
+            if *nt == 0 {
+                counts.0 += 1;
+            }
+        }
+
+        let p_value = fisher_exact_greater(
+            hyper.0 as u64, (hyper.1 - hyper.0) as u64,
+            control.0 as u64, (control.1 - control.0) as u64,
+        );
+
+        ReadHypermutation {
+            index,
+            name: read.name.clone(),
+            mismatches,
+            hyper,
+            control,
+            p_value,
+        }
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["read", "G>A GRD", "G GRD", "G>A control", "G control", "mismatches", "p-value"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    pub(crate) fn record(&self) -> Vec<String> {
+        let mismatches = (0..4)
+            .map(|i| (0..4).filter(|j| *j != i).map(|j| self.mismatches[i][j]).sum::<u32>())
+            .sum::<u32>();
+
+        vec![
+            self.name.clone(),
+            self.hyper.0.to_string(),
+            self.hyper.1.to_string(),
+            self.control.0.to_string(),
+            self.control.1.to_string(),
+            mismatches.to_string(),
+            self.p_value.to_string(),
+        ]
+    }
+}
+
+/// Reads of a sample flagged as hypermutated.
+pub struct Hypermutation {
+    p_value: f64,
+    total: usize,
+    hypermutated: Vec<ReadHypermutation>,
+}
+
+impl Hypermutation {
+    /// Tests every read against the reference, flagging those with a p-value below `p_value`.
+    pub fn new<I>(reads: I, reference: ArrayView<u8, Ix1>, p_value: f64) -> Hypermutation
+        where I: Iterator<Item = AlignedRead>
+    {
+        let mut hypermutation = Hypermutation::with_p_value(p_value);
+
+        for (index, read) in reads.enumerate() {
+            hypermutation.test(index, &read, reference);
+        }
+
+        hypermutation
+    }
+
+    /// No read tested yet, so that reads can be tested one at a time while they are read.
+    pub fn with_p_value(p_value: f64) -> Hypermutation {
+        Hypermutation {
+            p_value,
+            total: 0,
+            hypermutated: Vec::new(),
+        }
+    }
+
+    /// Tests a read against the reference, returning whether it is flagged as hypermutated.
+    pub fn test(&mut self, index: usize, read: &AlignedRead, reference: ArrayView<u8, Ix1>) -> bool {
+        self.total += 1;
+
+        let read = ReadHypermutation::new(index, read, reference);
+        let flagged = read.p_value < self.p_value;
+        if flagged {
+            self.hypermutated.push(read);
+        }
+
+        flagged
+    }
+
+    pub fn total_reads(&self) -> usize {
+        self.total
+    }
+
+    pub fn get_hypermutated(&self) -> &[ReadHypermutation] {
+        self.hypermutated.as_slice()
+    }
+
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 { 0. } else { self.hypermutated.len() as f64 / self.total as f64 }
+    }
+
+    /// Positions inside the BAM file of the hypermutated reads.
+    pub fn indices(&self) -> HashSet<usize> {
+        self.hypermutated.iter().map(|x| x.index).collect()
+    }
+}
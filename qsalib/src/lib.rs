@@ -6,6 +6,75 @@ pub mod matrices;
 /// Entry point for quasispecies analysis starting with BAM files.
 pub mod bam;
 
+/// Reads of a BAM file laid over the analysed range.
+pub mod reads;
+
+/// Read-level detection of APOBEC-induced G→A hypermutation.
+pub mod hypermut;
+
+/// Statistical tests shared by the analyses.
+pub mod stats;
+
+/// Gene, ORF and region annotations read from GFF3 or BED files.
+pub mod annotation;
+
+/// Codon-aware amino-acid frequency matrices of coding regions.
+pub mod protein;
+
+/// Synonymous and nonsynonymous diversity (πN, πS, dN/dS) of coding regions.
+pub mod selection;
+
+/// Distances between samples used for β-diversity.
+pub mod distance;
+
+/// Per-sample metadata, such as the group each sample belongs to.
+pub mod metadata;
+
+/// Population differentiation (Fst, Gst, Jost's D) between samples and groups.
+pub mod differentiation;
+
+/// Partition of γ-diversity into α and β components with Hill numbers.
+pub mod partition;
+
+/// Ordination (PCoA) of the distances between samples.
+pub mod ordination;
+
+/// Permutation tests of differences between groups of samples.
+pub mod comparison;
+
+/// Per-position comparison of two groups of samples.
+pub mod differential;
+
+/// Minor-allele frequency trajectories across timepoints.
+pub mod trajectory;
+
+/// Intra-host rates of divergence and diversity accumulation from dated samples.
+pub mod rate;
+
+/// Local haplotypes of the reads in sliding windows.
+pub mod haplotype;
+
+/// Full-length haplotypes of long reads, with frequencies estimated by EM.
+pub mod reconstruction;
+
+/// Linkage disequilibrium between variable sites spanned by the same reads.
+pub mod linkage;
+
+/// Covariation between positions, as mutual information between the bases of the same reads.
+pub mod covariation;
+
+/// Sequence logos of the base frequencies or of their information content.
+pub mod logo;
+
+/// Tidy, versioned tables of the metrics written as TSV and JSON.
+pub mod tidy;
+
+/// Hierarchical clustering (UPGMA, neighbour joining) of samples and Newick export.
+pub mod tree;
+
+/// Substitution spectrum of a sample against its reference, e.g. for APOBEC hypermutation.
+pub mod spectrum;
+
 /// Functions which do not fall in a specific category and can be used wherever in the crate.
 mod utils;
 
@@ -16,5 +85,27 @@ pub mod error;
 pub mod prelude {
     pub use crate::bam::{BamDataBuilder, BamData, Bam};
     pub use crate::matrices::Matrices;
+    pub use crate::reads::{AlignedRead, Reads, ReadCounts};
+    pub use crate::hypermut::{Hypermutation, ReadHypermutation};
+    pub use crate::annotation::{Annotation, Region, RegionSummary, Strand};
+    pub use crate::protein::ProteinMatrices;
+    pub use crate::selection::{Selection, CodonDiversity};
+    pub use crate::distance::{Distance, Metric};
+    pub use crate::metadata::Metadata;
+    pub use crate::differentiation::Differentiation;
+    pub use crate::partition::{Partition, hill_number};
+    pub use crate::ordination::Ordination;
+    pub use crate::comparison::GroupTest;
+    pub use crate::differential::{DifferentialDiversity, PositionTest};
+    pub use crate::trajectory::{Trajectories, Trajectory, TimedSample};
+    pub use crate::rate::{EvolutionaryRate, Regression};
+    pub use crate::haplotype::{Haplotype, HaplotypeWindow};
+    pub use crate::reconstruction::{GlobalHaplotypes, spanned_region};
+    pub use crate::linkage::{Linkage, LinkagePair, VariableSite};
+    pub use crate::covariation::{Covariation, Coupling};
+    pub use crate::logo::{Logo, LogoColumn, LogoKind};
+    pub use crate::tidy::{Table, PositionMetrics, SampleSummary, FORMAT_VERSION, BREADTH_DEPTHS};
+    pub use crate::tree::{Tree, TreeLayout};
+    pub use crate::spectrum::{Spectrum, GContext};
     pub use crate::error::{Result, QSAError};
 }
\ No newline at end of file
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use ndarray::{ArrayView, Ix2, Axis};
+
+use crate::reads::AlignedRead;
+use crate::spectrum::NUCLEOTIDES;
+use crate::stats::{fisher_exact, benjamini_hochberg};
+
+/// A variable position, with its major and minor base.
+#[derive(Debug, Clone, Copy)]
+pub struct VariableSite {
+    pub column: usize,
+    pub major: u8,
+    pub minor: u8,
+}
+
+/// Columns whose second most frequent base reaches `min_freq`, with their major and minor base.
+pub(crate) fn variable_sites(pfm: ArrayView<u64, Ix2>, min_freq: f64) -> Vec<VariableSite> {
+    pfm.axis_iter(Axis(1))
+        .enumerate()
+        .filter_map(|(column, counts)| {
+            let total = counts.sum() as f64;
+            let mut order = (0..counts.len()).collect::<Vec<usize>>();
+            order.sort_by(|a, b| counts[*b].cmp(&counts[*a]).then(a.cmp(b)));
+
+            if total > 0. && counts[order[1]] as f64 / total >= min_freq {
+                Some(VariableSite { column, major: order[0] as u8, minor: order[1] as u8 })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Linkage between the alleles of two variable sites, from the reads covering both.
+#[derive(Debug, Clone)]
+pub struct LinkagePair {
+    /// Indices of the two sites in `Linkage::get_sites`.
+    pub a: usize,
+    pub b: usize,
+    /// Reads carrying major-major, major-minor, minor-major and minor-minor alleles.
+    pub counts: [u64; 4],
+    pub d: f64,
+    pub d_prime: f64,
+    pub r_squared: f64,
+    pub p_value: f64,
+    pub q_value: f64,
+}
+
+impl LinkagePair {
+    fn new(a: usize, b: usize, counts: [u64; 4]) -> LinkagePair {
+        let n = counts.iter().sum::<u64>() as f64;
+        let p = counts.map(|x| x as f64 / n);
+
+        // frequencies of the major alleles of the two sites
+        let (pa, pb) = (p[0] + p[1], p[0] + p[2]);
+        let d = p[0] * p[3] - p[1] * p[2];
+
+        let max = if d > 0. { (pa * (1. - pb)).min((1. - pa) * pb) } else { (pa * pb).min((1. - pa) * (1. - pb)) };
+        let variance = pa * (1. - pa) * pb * (1. - pb);
+
+        LinkagePair {
+            a,
+            b,
+            counts,
+            d,
+            d_prime: if max > 0. { d / max } else { f64::NAN },
+            r_squared: if variance > 0. { d * d / variance } else { f64::NAN },
+            p_value: fisher_exact(counts[0], counts[1], counts[2], counts[3]),
+            q_value: f64::NAN,
+        }
+    }
+}
+
+/// Linkage disequilibrium between the pairs of variable sites spanned by the same reads.
+///
+/// A site is variable if its second most frequent base reaches `min_freq`; reads are only counted
+/// at the sites where they carry its major or minor base. Pairs covered by fewer than `min_reads`
+/// such reads are left out, and the p-values of Fisher's exact test are corrected with the
+/// Benjamini–Hochberg procedure.
+pub struct Linkage {
+    sites: Vec<VariableSite>,
+    pairs: Vec<LinkagePair>,
+}
+
+impl Linkage {
+    pub fn new<I>(reads: I, pfm: ArrayView<u64, Ix2>, min_freq: f64, min_reads: u64) -> Linkage
+        where I: Iterator<Item = AlignedRead>
+    {
+        let sites = variable_sites(pfm, min_freq);
+
+        let mut counts: HashMap<(usize, usize), [u64; 4]> = HashMap::new();
+        for read in reads {
+            let first = sites.partition_point(|x| x.column < read.offset);
+
+            let alleles = sites[first..].iter()
+                .enumerate()
+                .take_while(|(_, site)| site.column < read.end())
+                .filter_map(|(i, site)| match read.base_at(site.column) {
+                    Some(base) if base == site.major => Some((first + i, 0)),
+                    Some(base) if base == site.minor => Some((first + i, 1)),
+                    _ => None,
+                })
+                .collect::<Vec<(usize, usize)>>();
+
+            for (k, (a, x)) in alleles.iter().enumerate() {
+                for (b, y) in alleles.iter().skip(k + 1) {
+                    counts.entry((*a, *b)).or_insert([0; 4])[2 * x + y] += 1;
+                }
+            }
+        }
+
+        let mut pairs = counts.into_iter()
+            .filter(|(_, x)| x.iter().sum::<u64>() >= min_reads.max(1))
+            .map(|((a, b), x)| LinkagePair::new(a, b, x))
+            .collect::<Vec<LinkagePair>>();
+        pairs.sort_by_key(|x| (x.a, x.b));
+
+        let q_values = benjamini_hochberg(&pairs.iter().map(|x| x.p_value).collect::<Vec<f64>>());
+        for (pair, q) in pairs.iter_mut().zip(q_values) {
+            pair.q_value = q;
+        }
+
+        Linkage {
+            sites,
+            pairs,
+        }
+    }
+
+    pub fn get_sites(&self) -> &[VariableSite] {
+        self.sites.as_slice()
+    }
+
+    pub fn get_pairs(&self) -> &[LinkagePair] {
+        self.pairs.as_slice()
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        [
+            "position a", "major a", "minor a", "position b", "major b", "minor b", "reads",
+            "major-major", "major-minor", "minor-major", "minor-minor", "D", "D'", "r2", "p-value", "q-value",
+        ]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// One record per pair, numbered from `first` (1-based reference coordinate of the first column).
+    pub(crate) fn records(&self, first: usize) -> Vec<Vec<String>> {
+        self.pairs.iter()
+            .map(|x| {
+                let (a, b) = (self.sites[x.a], self.sites[x.b]);
+
+                let mut record = vec![
+                    (first + a.column).to_string(),
+                    NUCLEOTIDES[a.major as usize].to_string(),
+                    NUCLEOTIDES[a.minor as usize].to_string(),
+                    (first + b.column).to_string(),
+                    NUCLEOTIDES[b.major as usize].to_string(),
+                    NUCLEOTIDES[b.minor as usize].to_string(),
+                    x.counts.iter().sum::<u64>().to_string(),
+                ];
+                record.extend(x.counts.iter().map(|c| c.to_string()));
+                record.extend([x.d, x.d_prime, x.r_squared, x.p_value, x.q_value].iter().map(|v| v.to_string()));
+
+                record
+            })
+            .collect()
+    }
+}
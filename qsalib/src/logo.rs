@@ -0,0 +1,150 @@
+use std::fmt;
+use std::str::FromStr;
+
+use ndarray::{ArrayView, Ix1, Ix2};
+
+use crate::error::QSAError;
+
+/// What the heights of the letters of a logo stand for.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LogoKind {
+    /// Frequency of each base times the information content of the position, in bits.
+    #[default]
+    Information,
+    /// Frequency of each base.
+    Frequency,
+}
+
+impl LogoKind {
+    pub const NAMES: [&'static str; 2] = ["information", "frequency"];
+
+    /// Highest stack a position can reach.
+    pub fn max_height(&self) -> f64 {
+        match self {
+            LogoKind::Information => 4_f64.log2(),
+            LogoKind::Frequency => 1.,
+        }
+    }
+}
+
+impl FromStr for LogoKind {
+    type Err = QSAError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "information" => Ok(LogoKind::Information),
+            "frequency" => Ok(LogoKind::Frequency),
+            _ => Err(QSAError::UnknownLogoKind),
+        }
+    }
+}
+
+impl fmt::Display for LogoKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", LogoKind::NAMES[*self as usize])
+    }
+}
+
+/// Heights of the four bases at a position of a logo.
+#[derive(Debug, Clone)]
+pub struct LogoColumn {
+    /// 1-based position on the reference.
+    pub position: usize,
+    pub heights: [f64; 4],
+}
+
+impl LogoColumn {
+    /// Bases with their heights, from the lowest to the highest, as they are stacked.
+    pub fn stacked(&self) -> Vec<(u8, f64)> {
+        let mut stack = self.heights.iter()
+            .enumerate()
+            .filter(|(_, x)| **x > 0.)
+            .map(|(i, x)| (i as u8, *x))
+            .collect::<Vec<(u8, f64)>>();
+        stack.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(b.0.cmp(&a.0)));
+
+        stack
+    }
+}
+
+/// A sequence logo of consecutive columns of a sample.
+///
+/// In information logos, the information content of a position is its maximum entropy (2 bits)
+/// minus its Shannon entropy and the small-sample correction of Schneider et al. (1986),
+/// 3 / (2 ln 2 n) for n bases read; each base gets its share of it by frequency.
+#[derive(Debug, Clone)]
+pub struct Logo {
+    pub kind: LogoKind,
+    pub columns: Vec<LogoColumn>,
+}
+
+impl Logo {
+    /// Logo of the columns from `start` to `end` (excluded), numbered from `first` (1-based reference
+    /// coordinate of the first column of the matrices).
+    pub fn new(pfm: ArrayView<u64, Ix2>, ppm: ArrayView<f64, Ix2>, (start, end): (usize, usize), first: usize, kind: LogoKind) -> Logo {
+        let columns = (start..end.min(ppm.ncols()))
+            .map(|col| {
+                let depth = pfm.column(col).sum();
+                let p = [ppm[[0, col]], ppm[[1, col]], ppm[[2, col]], ppm[[3, col]]];
+
+                let heights = if depth == 0 {
+                    [0.; 4]
+                } else {
+                    match kind {
+                        LogoKind::Frequency => p,
+                        LogoKind::Information => {
+                            let entropy = -p.iter().filter(|x| **x > 0.).map(|x| x * x.log2()).sum::<f64>();
+                            let correction = 3. / (2. * 2_f64.ln() * depth as f64);
+                            let information = (kind.max_height() - entropy - correction).max(0.);
+
+                            p.map(|x| x * information)
+                        },
+                    }
+                };
+
+                LogoColumn {
+                    position: first + col,
+                    heights,
+                }
+            })
+            .collect();
+
+        Logo {
+            kind,
+            columns,
+        }
+    }
+}
+
+/// The `n` non-overlapping windows of `width` columns with the highest total efficiency, as
+/// `(start, end)` columns (end excluded) sorted by position. Efficiency that is not a number counts
+/// as 0.
+pub fn top_windows(efficiency: ArrayView<f64, Ix1>, width: usize, n: usize) -> Vec<(usize, usize)> {
+    let size = efficiency.len();
+    if width == 0 || width > size {
+        return Vec::new();
+    }
+
+    let values = efficiency.iter().map(|x| if x.is_nan() { 0. } else { *x }).collect::<Vec<f64>>();
+    let mut totals = vec![values[..width].iter().sum::<f64>()];
+    for start in 1..=(size - width) {
+        totals.push(totals[start - 1] - values[start - 1] + values[start + width - 1]);
+    }
+
+    let mut order = (0..totals.len()).collect::<Vec<usize>>();
+    order.sort_by(|a, b| totals[*b].partial_cmp(&totals[*a]).unwrap().then(a.cmp(b)));
+
+    let mut windows: Vec<(usize, usize)> = Vec::new();
+    for start in order {
+        if windows.len() == n {
+            break;
+        }
+
+        if windows.iter().all(|(s, e)| start + width <= *s || start >= *e) {
+            windows.push((start, start + width));
+        }
+    }
+    windows.sort_unstable();
+
+    windows
+}
@@ -1,88 +1,70 @@
-use std::fs::File;
 use std::path::Path;
 
-use ndarray::{Array, ArrayView, Ix1, Ix2, ShapeBuilder, s, Axis};
-use bam::{BamReader, Record, RecordReader};
-use csv::{Writer, WriterBuilder};
+use ndarray::{Array, ArrayView, Ix1, Ix2, ShapeBuilder, Axis};
+use csv::Writer;
 
-use crate::error::{Result, QSAError};
+use crate::error::Result;
+use crate::reads::AlignedRead;
 
 pub struct Matrices {
     pfm: Array<u64, Ix2>,
+    depth: Array<u64, Ix1>,
     coverage: Array<f64, Ix1>,
     ppm: Array<f64, Ix2>,
     efficiency: Array<f64, Ix1>,
 }
 
 impl Matrices {
-    fn pfm_coverage(mut bam: BamReader<File>, range: (i32, i32)) -> Result<(Array<u64, Ix2>, Array<f64, Ix1>)> {
+    fn pfm_depth<I>(reads: I, range: (i32, i32)) -> Result<(Array<u64, Ix2>, Array<u64, Ix1>)>
+        where I: Iterator<Item = AlignedRead>
+    {
         let (start, end) = range;
 
         let mut pfm = Array::<u64, Ix2>::zeros((4, (end - start) as usize).f());
-        let mut coverage = Array::<f64, Ix1>::zeros(((end - start) as usize).f());
-
-        let mut record = Record::new();
+        let mut depth = Array::<u64, Ix1>::zeros(((end - start) as usize).f());
 
         // calculate PFM
-        loop {
-            match bam.read_into(&mut record) {
-                Ok(true) => {
-                    let sequence = record.sequence()
-                        .to_vec_acgtn_only()
-                        .iter()
-                        .map(|v| {
-                            match v % 32 {
-                                1 => 0,     3 => 1,     // a|A  c|C
-                                7 => 2,     20 => 3,    // g|G  t|T
-                                21 => 3,    _ => 4,     // u|U  n|N
-                            }
-                        })
-                        .collect::<Vec<u8>>();
-
-                    let (seq_start, seq_end) = (record.start(), record.start() + sequence.len() as i32);
-
-                    if seq_start < start || seq_end > end {
-                        continue
-                    }
-
-                    let fcol = seq_start - start;
-                    for (i, row) in sequence.iter().enumerate() {
-                        if *row == 4 {
-                            continue
-                        }
-
-                        let col = fcol as usize + i;
-
-                        let cell = pfm.get_mut((*row as usize, col))
-                            .expect(format!("could not access ({}, {}) record", row, col).as_str());
-                        *cell += 1;
-                    }
-                },
-                Ok(false) => break,
-                Err(why) => panic!("{}", why),
+        for read in reads {
+            for (i, row) in read.bases.iter().enumerate() {
+                if *row == 4 {
+                    continue
+                }
+
+                let col = read.offset + i;
+
+                let cell = pfm.get_mut((*row as usize, col))
+                    .unwrap_or_else(|| panic!("could not access ({}, {}) record", row, col));
+                *cell += 1;
             }
         }
 
-        // calculate coverage
+        // calculate depth
         for col in 0..pfm.ncols() {
-            let nt = pfm.column(col).sum() as f64;
+            let nt = pfm.column(col).sum();
 
             /*
-            if nt == 0. {
+            if nt == 0 {
                 return Err(QSAError::CoverageHole);
             }
 
              */
 
-            *coverage.get_mut(col).unwrap() = nt;
+            *depth.get_mut(col).unwrap() = nt;
         }
 
+        // return arrays
+        Ok((pfm, depth))
+    }
+
+    /// Depth relative to the maximum depth.
+    fn coverage(depth: ArrayView<u64, Ix1>) -> Array<f64, Ix1> {
+        let mut coverage = depth.map(|x| *x as f64);
+
         let max_val = coverage.iter().copied().fold(f64::NEG_INFINITY, f64::max);
         // coverage.map_inplace(|x| *x /= max_val);
         coverage /= max_val;  // broadcast
 
-        // return arrays
-        Ok((pfm, coverage))
+        coverage
     }
 
     fn ppm(pfm: ArrayView<u64, Ix2>) -> Array<f64, Ix2> {
@@ -104,7 +86,8 @@ impl Matrices {
         for i in 0..size {
             let col = ppm.column(i);
 
-            let norm_shann = - (col.map(|x| (x * x.log2()) / (4_f64.log2())).sum());
+            // 0 * log(0) is taken as 0, as absent bases do not contribute to the entropy
+            let norm_shann = - (col.map(|x| if *x > 0. { (x * x.log2()) / (4_f64.log2()) } else { 0. }).sum());
 
             *efficiency.get_mut(i).unwrap() = norm_shann;
         }
@@ -112,21 +95,12 @@ impl Matrices {
         efficiency
     }
 
-    pub(crate) fn new(bam: BamReader<File>, range: (i32, i32), threshold: f64) -> Result<Matrices> {
-        let (pfm, coverage) = Matrices::pfm_coverage(bam, range)?;
-
-        /*
-        let left_t = coverage.iter().position(|&x| x > threshold).unwrap();
-        let right_t = coverage.len() - coverage.iter().rev().position(|&x| x > threshold).unwrap();
-
-
-        let (pfm, coverage) =
-            (
-                pfm.slice(s![.., left_t..=right_t]).to_owned(),
-                coverage.slice(s![left_t..=right_t]).to_owned(),
-            );
-
-         */
+    /// Builds the matrices of the reads laid over `range`.
+    pub fn new<I>(reads: I, range: (i32, i32)) -> Result<Matrices>
+        where I: Iterator<Item = AlignedRead>
+    {
+        let (pfm, depth) = Matrices::pfm_depth(reads, range)?;
+        let coverage = Matrices::coverage(depth.view());
 
         let ppm = Matrices::ppm(pfm.view());
         let efficiency = Matrices::efficiency(ppm.view());
@@ -134,6 +108,7 @@ impl Matrices {
         Ok (
             Matrices {
                 pfm,
+                depth,
                 coverage,
                 ppm,
                 efficiency,
@@ -141,32 +116,70 @@ impl Matrices {
         )
     }
 
-    pub fn get_pfm(&self) -> ArrayView<u64, Ix2> {
+    pub fn get_pfm(&self) -> ArrayView<'_, u64, Ix2> {
         self.pfm.view()
     }
 
-    pub fn get_coverage(&self) -> ArrayView<f64, Ix1> {
+    /// Number of bases read at each position.
+    pub fn get_depth(&self) -> ArrayView<'_, u64, Ix1> {
+        self.depth.view()
+    }
+
+    /// Depth at each position relative to the maximum depth.
+    pub fn get_coverage(&self) -> ArrayView<'_, f64, Ix1> {
         self.coverage.view()
     }
 
-    pub fn get_ppm(&self) -> ArrayView<f64, Ix2> {
+    /// Fraction of the positions read at least `min_depth` times.
+    pub fn breadth(&self, min_depth: u64) -> f64 {
+        if self.depth.is_empty() {
+            return 0.;
+        }
+
+        self.depth.iter().filter(|x| **x >= min_depth).count() as f64 / self.depth.len() as f64
+    }
+
+    pub fn get_ppm(&self) -> ArrayView<'_, f64, Ix2> {
         self.ppm.view()
     }
 
-    pub fn get_efficiency(&self) -> ArrayView<f64, Ix1> {
+    pub fn get_efficiency(&self) -> ArrayView<'_, f64, Ix1> {
         self.efficiency.view()
     }
 
-    pub fn pfm_to_csv<P>(&self, path: P, filename: &str)
+    /// Efficiency summed over the positions and normalized by the length of the range.
+    pub fn alpha_diversity(&self) -> f64 {
+        self.efficiency.sum() / self.coverage.len() as f64
+    }
+
+    /// Majority base at each position, using the PFM rows (4 where there is no coverage).
+    pub fn get_consensus(&self) -> Array<u8, Ix1> {
+        self.pfm.columns()
+            .into_iter()
+            .map(|col| {
+                let (row, max) = col.iter()
+                    .enumerate()
+                    .fold((4, 0), |acc, (i, x)| if *x > acc.1 { (i as u8, *x) } else { acc });
+
+                if max == 0 { 4 } else { row }
+            })
+            .collect()
+    }
+
+    /// Writes the PFM, one row per position, numbered from `first` (1-based reference coordinate
+    /// of the first column).
+    pub fn pfm_to_csv<P>(&self, path: P, filename: &str, sample: &str, first: usize)
         where P: AsRef<Path>
     {
-        let file = File::create(path.as_ref().join(Path::new(filename))).expect("could not open file");
-        let mut writer = WriterBuilder::new().has_headers(false).from_writer(file);
+        let mut writer = Writer::from_path(path.as_ref().join(Path::new(filename))).expect("could not open file");
+
+        writer.write_record(["sample", "position", "A", "C", "G", "T"]).unwrap();
 
-        writer.write_record(&["A", "C", "G", "T"]).unwrap();
+        for (i, col) in self.pfm.columns().into_iter().enumerate() {
+            let mut record = vec![sample.to_string(), (first + i).to_string()];
+            record.extend(col.iter().map(|x| x.to_string()));
 
-        for col in self.pfm.columns() {
-            writer.write_record(col.to_slice().unwrap().iter().map(|x| x.to_string()).collect::<Vec<_>>()).unwrap();
+            writer.write_record(record).unwrap();
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use csv::ReaderBuilder;
+
+use crate::error::{QSAError, Result};
+use crate::utils::decimal_year;
+
+/// Per-sample metadata read from a CSV or TSV file with a header row.
+///
+/// The `sample` column must match the names of the BAM files; `group` is used to compare
+/// groups of samples, while `timepoint` or `date` and `patient` order the samples of each
+/// individual in time. Any other column is kept and can be read with `get`.
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    columns: Vec<String>,
+    samples: HashMap<String, Vec<String>>,
+}
+
+impl Metadata {
+    /// Reads a metadata file, tab-separated if its extension is `tsv` or `txt`, comma-separated otherwise.
+    pub fn from_path<P>(path: P) -> Result<Metadata>
+        where P: AsRef<Path>
+    {
+        let extension = path.as_ref()
+            .extension()
+            .map(|x| x.to_str().unwrap().to_lowercase())
+            .unwrap_or_default();
+        let delimiter = if extension == "tsv" || extension == "txt" { b'\t' } else { b',' };
+
+        let mut reader = ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|_| QSAError::MetadataNotFound)?;
+
+        let columns = reader.headers()
+            .map_err(|_| QSAError::MetadataMalformed)?
+            .iter()
+            .map(|x| x.trim().to_lowercase())
+            .collect::<Vec<String>>();
+
+        let sample = columns.iter()
+            .position(|x| x == "sample")
+            .ok_or(QSAError::MetadataMalformed)?;
+
+        let mut samples: HashMap<String, Vec<String>> = HashMap::new();
+        for record in reader.records() {
+            let record = record.map_err(|_| QSAError::MetadataMalformed)?;
+            let values = record.iter().map(|x| x.trim().to_string()).collect::<Vec<String>>();
+
+            match values.get(sample) {
+                Some(name) if !name.is_empty() => { samples.insert(name.clone(), values); },
+                _ => return Err(QSAError::MetadataMalformed),
+            }
+        }
+
+        let metadata = Metadata {
+            columns,
+            samples,
+        };
+
+        for sample in metadata.samples.keys() {
+            if let Some(date) = metadata.get(sample, "date") {
+                decimal_year(date)?;
+            }
+        }
+
+        Ok(metadata)
+    }
+
+    /// Value of a column for a sample, if both exist and the value is not empty.
+    pub fn get(&self, sample: &str, column: &str) -> Option<&str> {
+        let column = self.columns.iter().position(|x| x == &column.to_lowercase())?;
+
+        self.samples.get(sample)
+            .and_then(|values| values.get(column))
+            .map(|x| x.as_str())
+            .filter(|x| !x.is_empty())
+    }
+
+    pub fn group(&self, sample: &str) -> Option<&str> {
+        self.get(sample, "group")
+    }
+
+    /// Sampling time of a sample, from the numeric `timepoint` column.
+    pub fn timepoint(&self, sample: &str) -> Option<f64> {
+        self.get(sample, "timepoint").and_then(|x| x.parse().ok())
+    }
+
+    /// Sampling date of a sample as a decimal year, from the `date` column (`YYYY-MM-DD` or a year),
+    /// checked when the file was read.
+    pub fn date(&self, sample: &str) -> Option<f64> {
+        self.get(sample, "date").and_then(|x| decimal_year(x).ok())
+    }
+
+    /// Individual a sample comes from, from the `patient` column.
+    pub fn patient(&self, sample: &str) -> Option<&str> {
+        self.get(sample, "patient")
+    }
+
+    pub fn has_column(&self, column: &str) -> bool {
+        self.columns.iter().any(|x| x == &column.to_lowercase())
+    }
+}
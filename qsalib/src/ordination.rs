@@ -0,0 +1,66 @@
+use ndarray::{Array, ArrayView, Ix1, Ix2, Axis};
+
+use crate::utils::symmetric_eigen;
+
+/// Principal coordinates analysis (classical multidimensional scaling) of a distance matrix.
+pub struct Ordination {
+    eigenvalues: Array<f64, Ix1>,
+    coordinates: Array<f64, Ix2>,
+}
+
+impl Ordination {
+    /// Runs PCoA on a symmetric distance matrix.
+    ///
+    /// Squared distances are double-centred (Gower) and decomposed; only the axes with a positive
+    /// eigenvalue get coordinates, while all eigenvalues are kept, negative ones included, to show
+    /// how far the distance is from being Euclidean.
+    pub fn pcoa(distances: ArrayView<f64, Ix2>) -> Ordination {
+        let n = distances.nrows();
+        if n == 0 {
+            return Ordination {
+                eigenvalues: Array::zeros(0),
+                coordinates: Array::zeros((0, 0)),
+            };
+        }
+
+        let a = distances.map(|x| -0.5 * x * x);
+        let row_means = a.mean_axis(Axis(1)).unwrap();
+        let col_means = a.mean_axis(Axis(0)).unwrap();
+        let mean = a.mean().unwrap();
+
+        let mut b = a;
+        for ((i, j), x) in b.indexed_iter_mut() {
+            *x += mean - row_means[i] - col_means[j];
+        }
+
+        let (eigenvalues, eigenvectors) = symmetric_eigen(b.view());
+
+        let axes = eigenvalues.iter().take_while(|x| **x > 1e-10).count();
+        let mut coordinates = eigenvectors.slice(ndarray::s![.., ..axes]).to_owned();
+        for (mut col, value) in coordinates.columns_mut().into_iter().zip(eigenvalues.iter()) {
+            col *= value.sqrt();
+        }
+
+        Ordination {
+            eigenvalues,
+            coordinates,
+        }
+    }
+
+    /// All the eigenvalues, in decreasing order.
+    pub fn get_eigenvalues(&self) -> ArrayView<'_, f64, Ix1> {
+        self.eigenvalues.view()
+    }
+
+    /// Fraction of the variance explained by each axis, relative to the positive eigenvalues.
+    pub fn proportion_explained(&self) -> Array<f64, Ix1> {
+        let total = self.eigenvalues.iter().filter(|x| **x > 0.).sum::<f64>();
+
+        self.eigenvalues.map(|x| if total > 0. { x / total } else { 0. })
+    }
+
+    /// Coordinates of the samples (rows) on the axes with a positive eigenvalue (columns).
+    pub fn get_coordinates(&self) -> ArrayView<'_, f64, Ix2> {
+        self.coordinates.view()
+    }
+}
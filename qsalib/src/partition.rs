@@ -0,0 +1,170 @@
+use ndarray::{Array, ArrayView, Ix1, Ix2, Axis, Zip};
+
+use crate::error::{QSAError, Result};
+
+/// Sum of `p^q` over the non-zero frequencies, the building block of Hill numbers.
+fn power_sum(p: ArrayView<f64, Ix1>, q: f64) -> f64 {
+    p.iter().filter(|x| **x > 0.).map(|x| x.powf(q)).sum()
+}
+
+/// Shannon entropy (natural logarithm) of a frequency vector.
+fn shannon(p: ArrayView<f64, Ix1>) -> f64 {
+    - p.iter().filter(|x| **x > 0.).map(|x| x * x.ln()).sum::<f64>()
+}
+
+/// Hill number (effective number of bases) of order `q` of a frequency vector.
+pub fn hill_number(p: ArrayView<f64, Ix1>, q: f64) -> f64 {
+    if (q - 1.).abs() < f64::EPSILON {
+        shannon(p).exp()
+    } else {
+        power_sum(p, q).powf(1. / (1. - q))
+    }
+}
+
+/// Jost's (2007) partition of γ-diversity into α and β components, with equally weighted samples.
+///
+/// Diversities are Hill numbers of order `q`, computed at each position covered by all the samples;
+/// elsewhere they are NaN. The multiplicative β ranges from 1 (identical samples) to the number of
+/// samples (no base in common), the additive one is γ - α. Genome-wide values are an error when no
+/// position is covered by all the samples.
+pub struct Partition {
+    pub q: f64,
+    alpha: Array<f64, Ix1>,
+    gamma: Array<f64, Ix1>,
+}
+
+impl Partition {
+    pub fn new(ppms: &[ArrayView<f64, Ix2>], q: f64) -> Partition {
+        let ncols = ppms.iter().map(|x| x.ncols()).min().unwrap_or(0);
+        let n = ppms.len() as f64;
+
+        let mut alpha = Array::<f64, Ix1>::from_elem(ncols, f64::NAN);
+        let mut gamma = Array::<f64, Ix1>::from_elem(ncols, f64::NAN);
+
+        for col in 0..ncols {
+            let columns = ppms.iter().map(|x| x.index_axis(Axis(1), col)).collect::<Vec<_>>();
+            if columns.is_empty() || !columns.iter().all(|x| x.sum() > 0.) {
+                continue
+            }
+
+            let mut pooled = Array::<f64, Ix1>::zeros(columns[0].len());
+            for column in &columns {
+                pooled += column;
+            }
+            pooled /= n;
+
+            alpha[col] = if (q - 1.).abs() < f64::EPSILON {
+                (columns.iter().map(|x| shannon(x.view())).sum::<f64>() / n).exp()
+            } else {
+                (columns.iter().map(|x| power_sum(x.view(), q)).sum::<f64>() / n).powf(1. / (1. - q))
+            };
+            gamma[col] = hill_number(pooled.view(), q);
+        }
+
+        Partition {
+            q,
+            alpha,
+            gamma,
+        }
+    }
+
+    pub fn get_alpha(&self) -> ArrayView<'_, f64, Ix1> {
+        self.alpha.view()
+    }
+
+    pub fn get_gamma(&self) -> ArrayView<'_, f64, Ix1> {
+        self.gamma.view()
+    }
+
+    /// Multiplicative β at each position.
+    pub fn get_beta(&self) -> Array<f64, Ix1> {
+        &self.gamma / &self.alpha
+    }
+
+    /// Additive β at each position.
+    pub fn get_additive_beta(&self) -> Array<f64, Ix1> {
+        &self.gamma - &self.alpha
+    }
+
+    /// Positions covered by all the samples.
+    pub fn shared_positions(&self) -> usize {
+        self.alpha.iter().filter(|x| !x.is_nan()).count()
+    }
+
+    fn mean(values: ArrayView<f64, Ix1>) -> Result<f64> {
+        let (sum, n) = values.iter()
+            .filter(|x| !x.is_nan())
+            .fold((0., 0), |acc, x| (acc.0 + x, acc.1 + 1));
+
+        if n == 0 { Err(QSAError::NoSharedPositions) } else { Ok(sum / n as f64) }
+    }
+
+    /// Genome-wide α, the mean of the per-position values.
+    pub fn alpha(&self) -> Result<f64> {
+        Partition::mean(self.alpha.view())
+    }
+
+    /// Genome-wide γ, the mean of the per-position values.
+    pub fn gamma(&self) -> Result<f64> {
+        Partition::mean(self.gamma.view())
+    }
+
+    /// Genome-wide multiplicative β, so that γ = α * β.
+    pub fn beta(&self) -> Result<f64> {
+        Ok(self.gamma()? / self.alpha()?)
+    }
+
+    /// Genome-wide additive β, so that γ = α + β.
+    pub fn additive_beta(&self) -> Result<f64> {
+        Ok(self.gamma()? - self.alpha()?)
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["samples", "q", "positions", "alpha", "beta", "gamma", "additive beta"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// Genome-wide values, `NA` when no position is covered by all the samples.
+    pub(crate) fn record(&self, samples: &str) -> Vec<String> {
+        let value = |x: Result<f64>| x.map_or("NA".to_string(), |x| x.to_string());
+
+        vec![
+            samples.to_string(),
+            self.q.to_string(),
+            self.shared_positions().to_string(),
+            value(self.alpha()),
+            value(self.beta()),
+            value(self.gamma()),
+            value(self.additive_beta()),
+        ]
+    }
+
+    pub(crate) fn position_header() -> Vec<String> {
+        ["samples", "position", "alpha", "beta", "gamma", "additive beta"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// One record per position, numbered from `first` (1-based reference coordinate of the first column).
+    pub(crate) fn position_records(&self, samples: &str, first: usize) -> Vec<Vec<String>> {
+        let mut records: Vec<Vec<String>> = Vec::new();
+
+        Zip::indexed(&self.alpha)
+            .and(&self.gamma)
+            .for_each(|i, alpha, gamma| {
+                records.push(vec![
+                    samples.to_string(),
+                    (first + i).to_string(),
+                    alpha.to_string(),
+                    (gamma / alpha).to_string(),
+                    gamma.to_string(),
+                    (gamma - alpha).to_string(),
+                ]);
+            });
+
+        records
+    }
+}
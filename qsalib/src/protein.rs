@@ -0,0 +1,220 @@
+use ndarray::{Array, ArrayView, Ix1, Ix2, ShapeBuilder, Axis};
+
+use crate::annotation::{Region, Strand};
+use crate::reads::AlignedRead;
+
+/// Amino acids in the order of the rows of the protein PFM, stop codons last.
+pub const AMINO_ACIDS: [char; 21] = [
+    'A', 'R', 'N', 'D', 'C', 'Q', 'E', 'G', 'H', 'I', 'L',
+    'K', 'M', 'F', 'P', 'S', 'T', 'W', 'Y', 'V', '*',
+];
+
+/// Standard genetic code, indexed by `16 * first + 4 * second + third` with A = 0, C = 1, G = 2, T = 3.
+const GENETIC_CODE: &[u8; 64] = b"KNKNTTTTRSRSIIMIQHQHPPPPRRRRLLLLEDEDAAAAGGGGVVVV*Y*YSSSS*CWCLFLF";
+
+/// Index of a codon inside the 64-codon rows, from its bases as PFM rows.
+pub fn codon_index(codon: [u8; 3]) -> usize {
+    16 * codon[0] as usize + 4 * codon[1] as usize + codon[2] as usize
+}
+
+/// Bases of a codon, given its index inside the 64-codon rows.
+pub fn codon_bases(index: usize) -> [u8; 3] {
+    [(index / 16) as u8, (index / 4 % 4) as u8, (index % 4) as u8]
+}
+
+/// Row of the amino acid (see `AMINO_ACIDS`) a codon translates to.
+pub fn translate(index: usize) -> usize {
+    let aa = GENETIC_CODE[index] as char;
+
+    AMINO_ACIDS.iter().position(|x| *x == aa).unwrap()
+}
+
+/// Amino-acid frequency matrices of a coding region, built from codons called on single reads.
+pub struct ProteinMatrices {
+    pub name: String,
+    codons: Array<u64, Ix2>,
+    pfm: Array<u64, Ix2>,
+    ppm: Array<f64, Ix2>,
+    efficiency: Array<f64, Ix1>,
+}
+
+impl ProteinMatrices {
+    /// Columns of each codon of a region, in the direction of its strand and after its phase.
+    fn codon_columns(region: &Region, range: (i32, i32)) -> Vec<Option<[usize; 3]>> {
+        let (start, end) = (range.0 as usize, range.1 as usize);
+        let positions = region.positions();
+
+        positions.get(region.phase..)
+            .unwrap_or_default()
+            .chunks_exact(3)
+            .map(|codon| {
+                if codon.iter().all(|x| *x >= start && *x < end) {
+                    Some([codon[0] - start, codon[1] - start, codon[2] - start])
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Counts the codons of every region read by each read covering all three of their bases.
+    ///
+    /// Bases of regions on the minus strand are complemented, so that codons are always read
+    /// in the direction of translation.
+    pub fn from_reads<I>(reads: I, regions: &[&Region], range: (i32, i32)) -> Vec<ProteinMatrices>
+        where I: Iterator<Item = AlignedRead>
+    {
+        let columns = regions.iter()
+            .map(|region| ProteinMatrices::codon_columns(region, range))
+            .collect::<Vec<_>>();
+
+        // codons sorted by their leftmost column, so that each read only looks at those it can cover
+        let mut spans = columns.iter()
+            .enumerate()
+            .flat_map(|(r, codons)| {
+                codons.iter()
+                    .enumerate()
+                    .filter_map(move |(c, cols)| cols.map(|cols| (*cols.iter().min().unwrap(), *cols.iter().max().unwrap(), r, c)))
+            })
+            .collect::<Vec<_>>();
+        spans.sort_unstable();
+
+        let mut counts = columns.iter()
+            .map(|codons| Array::<u64, Ix2>::zeros((64, codons.len()).f()))
+            .collect::<Vec<_>>();
+
+        for read in reads {
+            let first = spans.partition_point(|x| x.0 < read.offset);
+
+            for (_, max, r, c) in spans[first..].iter().take_while(|x| x.0 < read.end()) {
+                if *max >= read.end() {
+                    continue
+                }
+
+                let cols = columns[*r][*c].unwrap();
+                let mut codon = [0; 3];
+                let mut called = true;
+                for (nt, col) in codon.iter_mut().zip(cols.iter()) {
+                    match read.base_at(*col) {
+                        Some(base) => *nt = base,
+                        None => called = false,
+                    }
+                }
+
+                if !called {
+                    continue
+                }
+
+                if regions[*r].strand == Strand::Reverse {
+                    codon.iter_mut().for_each(|x| *x = 3 - *x);
+                }
+
+                counts[*r][[codon_index(codon), *c]] += 1;
+            }
+        }
+
+        regions.iter()
+            .zip(counts)
+            .map(|(region, codons)| ProteinMatrices::new(region.name.clone(), codons))
+            .collect()
+    }
+
+    /// Builds the matrices from the counts of the 64 codons (rows) at each codon position (columns).
+    pub fn new(name: String, codons: Array<u64, Ix2>) -> ProteinMatrices {
+        let mut pfm = Array::<u64, Ix2>::zeros((AMINO_ACIDS.len(), codons.ncols()).f());
+        for (index, row) in codons.axis_iter(Axis(0)).enumerate() {
+            let mut aa = pfm.row_mut(translate(index));
+            aa += &row;
+        }
+
+        let mut ppm = pfm.map(|x| *x as f64);
+        for mut col in ppm.columns_mut() {
+            let total = col.sum();
+
+            if total > 0. {
+                col /= total;
+            }
+        }
+
+        let efficiency = ppm.columns()
+            .into_iter()
+            .map(|col| {
+                - col.iter()
+                    .map(|x| if *x > 0. { x * x.log2() } else { 0. })
+                    .sum::<f64>() / (AMINO_ACIDS.len() as f64).log2()
+            })
+            .collect::<Array<f64, Ix1>>();
+
+        ProteinMatrices {
+            name,
+            codons,
+            pfm,
+            ppm,
+            efficiency,
+        }
+    }
+
+    /// Counts of the 64 codons (rows) at each codon position (columns).
+    pub fn get_codons(&self) -> ArrayView<'_, u64, Ix2> {
+        self.codons.view()
+    }
+
+    pub fn get_pfm(&self) -> ArrayView<'_, u64, Ix2> {
+        self.pfm.view()
+    }
+
+    pub fn get_ppm(&self) -> ArrayView<'_, f64, Ix2> {
+        self.ppm.view()
+    }
+
+    /// Shannon entropy of each codon position, normalized by its maximum over 21 symbols.
+    pub fn get_efficiency(&self) -> ArrayView<'_, f64, Ix1> {
+        self.efficiency.view()
+    }
+
+    pub fn alpha_diversity(&self) -> f64 {
+        if self.efficiency.is_empty() { 0. } else { self.efficiency.sum() / self.efficiency.len() as f64 }
+    }
+
+    /// Majority amino acid at each codon position, `-` where no codon was called.
+    pub fn consensus(&self) -> String {
+        self.pfm.columns()
+            .into_iter()
+            .map(|col| {
+                let (row, max) = col.iter()
+                    .enumerate()
+                    .fold((0, 0), |acc, (i, x)| if *x > acc.1 { (i, *x) } else { acc });
+
+                if max == 0 { '-' } else { AMINO_ACIDS[row] }
+            })
+            .collect()
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        let mut header = vec!["protein".to_string(), "codon".to_string(), "depth".to_string(), "consensus".to_string(), "efficiency".to_string()];
+        header.extend(AMINO_ACIDS.iter().map(|x| x.to_string()));
+
+        header
+    }
+
+    pub(crate) fn records(&self) -> Vec<Vec<String>> {
+        let consensus = self.consensus().chars().collect::<Vec<char>>();
+
+        self.pfm.columns()
+            .into_iter()
+            .enumerate()
+            .map(|(i, col)| {
+                let mut record = vec![
+                    self.name.clone(),
+                    (i + 1).to_string(),
+                    col.sum().to_string(),
+                    consensus[i].to_string(),
+                    self.efficiency[i].to_string(),
+                ];
+                record.extend(col.iter().map(|x| x.to_string()));
+
+                record
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,178 @@
+use std::cmp::Ordering;
+
+use ndarray::{Array, Ix2, Axis};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::stats::{linear_regression, quantile};
+use crate::trajectory::TimedSample;
+
+/// Linear regression of a per-site measure on time, with a bootstrap confidence interval of the slope.
+#[derive(Debug, Clone, Copy)]
+pub struct Regression {
+    /// Slope, per site per year.
+    pub rate: f64,
+    pub intercept: f64,
+    pub r_squared: f64,
+    /// 95% bootstrap confidence interval of the rate.
+    pub ci: (f64, f64),
+}
+
+/// Intra-host rates of divergence from the baseline consensus and of diversity accumulation, from
+/// dated samples of one individual.
+///
+/// At each site covered by all the samples, divergence is the frequency of the bases differing from
+/// the consensus of the earliest sample, and diversity is the probability that two bases differ
+/// (1 - Σp²); both are averaged over the sites and regressed on the years since the earliest sample,
+/// so that rates are in substitutions per site per year. Confidence intervals come from resampling
+/// the sites with replacement.
+pub struct EvolutionaryRate {
+    pub series: String,
+    /// Names of the samples and years since the earliest one.
+    pub samples: Vec<(String, f64)>,
+    /// Divergence and diversity of each site (columns) in each sample (rows).
+    divergence: Array<f64, Ix2>,
+    diversity: Array<f64, Ix2>,
+    pub divergence_rate: Regression,
+    pub diversity_rate: Regression,
+    pub bootstrap: usize,
+}
+
+impl EvolutionaryRate {
+    /// Returns `None` unless there are at least two distinct dates and a site covered by all the samples.
+    pub fn new(series: &str, samples: &[TimedSample], bootstrap: usize, seed: u64) -> Option<EvolutionaryRate> {
+        let mut samples = samples.to_vec();
+        samples.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        let (first, last) = (samples.first()?.1, samples.last()?.1);
+        if last <= first {
+            return None;
+        }
+
+        let ncols = samples.iter().map(|x| x.2.get_pfm().ncols()).min().unwrap_or(0);
+        let sites = (0..ncols)
+            .filter(|col| samples.iter().all(|x| x.2.get_pfm().column(*col).sum() > 0))
+            .collect::<Vec<usize>>();
+        if sites.is_empty() {
+            return None;
+        }
+
+        let baseline = samples[0].2.get_ppm();
+        let consensus = sites.iter()
+            .map(|col| {
+                let column = baseline.column(*col);
+
+                (0..column.len()).max_by(|a, b| column[*a].partial_cmp(&column[*b]).unwrap_or(Ordering::Equal)).unwrap()
+            })
+            .collect::<Vec<usize>>();
+
+        let mut divergence = Array::<f64, Ix2>::zeros((samples.len(), sites.len()));
+        let mut diversity = Array::<f64, Ix2>::zeros((samples.len(), sites.len()));
+        for (i, (_, _, matrices)) in samples.iter().enumerate() {
+            let ppm = matrices.get_ppm();
+
+            for (j, (col, base)) in sites.iter().zip(consensus.iter()).enumerate() {
+                let column = ppm.column(*col);
+
+                divergence[[i, j]] = 1. - column[*base];
+                diversity[[i, j]] = 1. - column.dot(&column);
+            }
+        }
+
+        let years = samples.iter().map(|x| x.1 - first).collect::<Vec<f64>>();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let resampled = (0..bootstrap)
+            .map(|_| (0..sites.len()).map(|_| rng.gen_range(0..sites.len())).collect::<Vec<usize>>())
+            .collect::<Vec<Vec<usize>>>();
+
+        let regression = |values: &Array<f64, Ix2>| {
+            let (rate, intercept, r_squared) = linear_regression(&years, &values.mean_axis(Axis(1)).unwrap().to_vec());
+
+            let rates = resampled.iter()
+                .map(|sites| {
+                    let means = values.select(Axis(1), sites).mean_axis(Axis(1)).unwrap().to_vec();
+
+                    linear_regression(&years, &means).0
+                })
+                .collect::<Vec<f64>>();
+
+            Regression {
+                rate,
+                intercept,
+                r_squared,
+                ci: (quantile(&rates, 0.025), quantile(&rates, 0.975)),
+            }
+        };
+
+        Some(
+            EvolutionaryRate {
+                series: series.to_string(),
+                divergence_rate: regression(&divergence),
+                diversity_rate: regression(&diversity),
+                samples: samples.iter().zip(years).map(|(x, t)| (x.0.clone(), t)).collect(),
+                divergence,
+                diversity,
+                bootstrap,
+            }
+        )
+    }
+
+    /// Mean divergence from the baseline consensus of each sample.
+    pub fn divergence(&self) -> Vec<f64> {
+        self.divergence.mean_axis(Axis(1)).unwrap().to_vec()
+    }
+
+    /// Mean diversity of each sample.
+    pub fn diversity(&self) -> Vec<f64> {
+        self.diversity.mean_axis(Axis(1)).unwrap().to_vec()
+    }
+
+    /// Number of sites covered by all the samples.
+    pub fn sites(&self) -> usize {
+        self.divergence.ncols()
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["series", "measure", "rate", "CI low", "CI high", "intercept", "R2", "samples", "sites", "bootstrap"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    pub(crate) fn records(&self) -> Vec<Vec<String>> {
+        [("divergence", &self.divergence_rate), ("diversity", &self.diversity_rate)]
+            .iter()
+            .map(|(measure, x)| vec![
+                self.series.clone(),
+                measure.to_string(),
+                x.rate.to_string(),
+                x.ci.0.to_string(),
+                x.ci.1.to_string(),
+                x.intercept.to_string(),
+                x.r_squared.to_string(),
+                self.samples.len().to_string(),
+                self.sites().to_string(),
+                self.bootstrap.to_string(),
+            ])
+            .collect()
+    }
+
+    pub(crate) fn sample_header() -> Vec<String> {
+        ["series", "sample", "years", "divergence", "diversity"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    pub(crate) fn sample_records(&self) -> Vec<Vec<String>> {
+        self.samples.iter()
+            .zip(self.divergence().into_iter().zip(self.diversity()))
+            .map(|((name, years), (divergence, diversity))| vec![
+                self.series.clone(),
+                name.clone(),
+                years.to_string(),
+                divergence.to_string(),
+                diversity.to_string(),
+            ])
+            .collect()
+    }
+}
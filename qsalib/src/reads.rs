@@ -0,0 +1,127 @@
+use std::fs::File;
+use std::path::Path;
+
+use bam::{BamReader, Record, RecordReader};
+
+use crate::utils::nt_index;
+use crate::error::{QSAError, Result};
+
+/// A read laid over the analysed range, with its bases stored as PFM rows (4 = unknown).
+#[derive(Debug, Clone)]
+pub struct AlignedRead {
+    pub name: String,
+    /// Column of the first base of the read inside the analysed range.
+    pub offset: usize,
+    pub bases: Vec<u8>,
+}
+
+impl AlignedRead {
+    /// Column right after the last base of the read.
+    pub fn end(&self) -> usize {
+        self.offset + self.bases.len()
+    }
+
+    /// Base read at a column of the analysed range, if the read covers it with a known base.
+    pub fn base_at(&self, col: usize) -> Option<u8> {
+        if col < self.offset {
+            return None;
+        }
+
+        match self.bases.get(col - self.offset) {
+            Some(nt) if *nt < 4 => Some(*nt),
+            _ => None,
+        }
+    }
+}
+
+/// Records read from a BAM file, and those left out by reason.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadCounts {
+    pub processed: usize,
+    pub unmapped: usize,
+    /// Mapped reads not lying completely inside the analysed range.
+    pub outside_range: usize,
+}
+
+impl ReadCounts {
+    /// Reads inside the analysed range.
+    pub fn kept(&self) -> usize {
+        self.processed.saturating_sub(self.unmapped + self.outside_range)
+    }
+}
+
+/// Iterator over the reads of a BAM file lying completely inside a range.
+pub struct Reads {
+    bam: BamReader<File>,
+    range: (i32, i32),
+    record: Record,
+    counts: ReadCounts,
+}
+
+impl Reads {
+    pub fn new(bam: BamReader<File>, range: (i32, i32)) -> Reads {
+        Reads {
+            bam,
+            range,
+            record: Record::new(),
+            counts: ReadCounts::default(),
+        }
+    }
+
+    /// Records read so far, and those left out.
+    pub fn counts(&self) -> ReadCounts {
+        self.counts
+    }
+
+    pub fn from_path<P>(path: P, range: (i32, i32)) -> Result<Reads>
+        where P: AsRef<Path>
+    {
+        let bam = BamReader::from_path(path, 0).map_err(|_| QSAError::BAMNotFound)?;
+
+        Ok(Reads::new(bam, range))
+    }
+}
+
+impl Iterator for Reads {
+    type Item = AlignedRead;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, end) = self.range;
+
+        loop {
+            match self.bam.read_into(&mut self.record) {
+                Ok(true) => {
+                    self.counts.processed += 1;
+
+                    if !self.record.flag().is_mapped() {
+                        self.counts.unmapped += 1;
+                        continue
+                    }
+
+                    let bases = self.record.sequence()
+                        .to_vec_acgtn_only()
+                        .iter()
+                        .map(|v| nt_index(*v))
+                        .collect::<Vec<u8>>();
+
+                    let (seq_start, seq_end) = (self.record.start(), self.record.start() + bases.len() as i32);
+
+                    if seq_start < start || seq_end > end {
+                        self.counts.outside_range += 1;
+                        continue
+                    }
+
+                    return Some(
+                        AlignedRead {
+                            name: String::from_utf8_lossy(self.record.name()).to_string(),
+                            offset: (seq_start - start) as usize,
+                            bases,
+                        }
+                    );
+                },
+                Ok(false) => return None,
+                Err(why) => panic!("{}", why),
+            }
+        }
+    }
+}
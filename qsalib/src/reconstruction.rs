@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use ndarray::{Array, ArrayView, Ix2, Axis};
+
+use crate::haplotype::Haplotype;
+use crate::reads::AlignedRead;
+
+const MAX_ITERATIONS: usize = 1000;
+
+/// Mismatches and compared positions of each read against each haplotype.
+type Comparisons = Vec<Vec<(f64, f64)>>;
+
+/// Mismatches and compared positions between a read and a haplotype, ignoring unknown bases.
+fn compare(read: &[u8], haplotype: &[u8]) -> (f64, f64) {
+    read.iter()
+        .zip(haplotype.iter())
+        .filter(|(a, b)| **a < 4 && **b < 4)
+        .fold((0., 0.), |acc, (a, b)| (acc.0 + if a != b { 1. } else { 0. }, acc.1 + 1.))
+}
+
+/// Most frequent known base at each position of the reads, weighted by `weights`; 4 where unknown.
+fn consensus(reads: &[Vec<u8>], weights: &[f64], len: usize) -> Vec<u8> {
+    let mut counts = vec![[0_f64; 4]; len];
+    for (read, weight) in reads.iter().zip(weights.iter()).filter(|(_, w)| **w > 0.) {
+        for (count, base) in counts.iter_mut().zip(read.iter()).filter(|(_, b)| **b < 4) {
+            count[*base as usize] += weight;
+        }
+    }
+
+    counts.iter()
+        .map(|x| {
+            let (base, max) = x.iter().enumerate().fold((4, 0.), |acc, (i, c)| if *c > acc.1 { (i, *c) } else { acc });
+
+            if max > 0. { base as u8 } else { 4 }
+        })
+        .collect()
+}
+
+/// Default region of the full-length haplotypes: the window as long as the median read that the
+/// most reads span, the first one if several tie.
+///
+/// Reads shorter than the median cannot span it, so at least half the reads are considered. `None`
+/// when there is no read.
+pub fn spanned_region(reads: &[AlignedRead]) -> Option<(usize, usize)> {
+    let mut lengths = reads.iter().map(|x| x.bases.len()).collect::<Vec<usize>>();
+    lengths.sort_unstable();
+
+    let len = *lengths.get(lengths.len().saturating_sub(1) / 2)?;
+    let mut spanning = vec![0_i64; reads.iter().map(|x| x.end()).max()? + 1];
+    for read in reads.iter().filter(|x| x.bases.len() >= len) {
+        spanning[read.offset] += 1;
+        spanning[read.end() - len + 1] -= 1;
+    }
+
+    let (start, _) = spanning.iter()
+        .scan(0, |acc, x| { *acc += x; Some(*acc) })
+        .enumerate()
+        .fold((0, 0), |acc, (i, x)| if x > acc.1 { (i, x) } else { acc });
+
+    Some((start, start + len))
+}
+
+/// Full-length haplotypes of the reads spanning a region, with frequencies estimated by
+/// expectation-maximisation.
+///
+/// Candidate haplotypes are seeded by clustering the reads greedily, from the most abundant
+/// sequence down: a read farther than three times the error rate from every seed starts a new
+/// cluster, and each cluster is summarised by its consensus. The EM then alternates between the
+/// posterior probability of each read coming from each haplotype, under a model where every base
+/// is misread with the same probability, and updates of the frequencies, of the sequences (weighted
+/// consensus) and of the error rate. Haplotypes that become identical are merged and those below
+/// `min_freq` are dropped, until nothing changes.
+pub struct GlobalHaplotypes {
+    /// First column of the region and the column right after its end.
+    pub start: usize,
+    pub end: usize,
+    /// Estimated per-base error rate.
+    pub error_rate: f64,
+    pub iterations: usize,
+    haplotypes: Vec<Haplotype>,
+    reads: Vec<String>,
+    /// Posterior probability of each read (rows) coming from each haplotype (columns).
+    posteriors: Array<f64, Ix2>,
+}
+
+impl GlobalHaplotypes {
+    pub fn new<I>(reads: I, start: usize, end: usize, error_rate: f64, min_freq: f64) -> GlobalHaplotypes
+        where I: Iterator<Item = AlignedRead>
+    {
+        let (names, sequences): (Vec<String>, Vec<Vec<u8>>) = reads
+            .filter(|x| x.offset <= start && x.end() >= end)
+            .map(|x| {
+                let bases = x.bases[(start - x.offset)..(end - x.offset)].to_vec();
+
+                (x.name, bases)
+            })
+            .unzip();
+
+        let len = end - start;
+        let mut error_rate = error_rate.clamp(1e-6, 0.5);
+        let mut haplotypes = GlobalHaplotypes::seed(&sequences, len, error_rate);
+        let mut frequencies = vec![1. / haplotypes.len() as f64; haplotypes.len()];
+        let mut iterations = 0;
+
+        while iterations < MAX_ITERATIONS && !haplotypes.is_empty() {
+            iterations += 1;
+
+            let (posteriors, comparisons) = GlobalHaplotypes::e_step(&sequences, &haplotypes, &frequencies, error_rate);
+
+            // M-step
+            let updated_frequencies = posteriors.mean_axis(Axis(0)).unwrap().to_vec();
+            let updated_haplotypes = posteriors.axis_iter(Axis(1))
+                .map(|z| consensus(&sequences, &z.to_vec(), len))
+                .collect::<Vec<Vec<u8>>>();
+
+            let (mismatches, compared) = comparisons.iter()
+                .zip(posteriors.axis_iter(Axis(0)))
+                .flat_map(|(comparison, z)| comparison.iter().zip(z.to_vec()).map(|((m, c), z)| (m * z, c * z)).collect::<Vec<_>>())
+                .fold((0., 0.), |acc, (m, c)| (acc.0 + m, acc.1 + c));
+            if compared > 0. {
+                error_rate = (mismatches / compared).clamp(1e-6, 0.5);
+            }
+
+            let converged = updated_haplotypes == haplotypes
+                && updated_frequencies.iter().zip(frequencies.iter()).all(|(a, b)| (a - b).abs() < 1e-8);
+
+            // identical haplotypes are merged and rare ones dropped, before iterating again
+            let mut merged: Vec<(Vec<u8>, f64)> = Vec::new();
+            for (sequence, frequency) in updated_haplotypes.into_iter().zip(updated_frequencies) {
+                match merged.iter_mut().find(|(s, _)| *s == sequence) {
+                    Some((_, f)) => *f += frequency,
+                    None => merged.push((sequence, frequency)),
+                }
+            }
+            let pruned = merged.len() != haplotypes.len() || merged.iter().any(|(_, f)| *f < min_freq);
+            if merged.iter().any(|(_, f)| *f >= min_freq) {
+                merged.retain(|(_, f)| *f >= min_freq);
+            }
+            merged.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+
+            let total = merged.iter().map(|(_, f)| f).sum::<f64>();
+            haplotypes = merged.iter().map(|(s, _)| s.clone()).collect();
+            frequencies = merged.iter().map(|(_, f)| f / total).collect();
+
+            if converged && !pruned {
+                break
+            }
+        }
+
+        let (posteriors, _) = GlobalHaplotypes::e_step(&sequences, &haplotypes, &frequencies, error_rate);
+        let counts = posteriors.sum_axis(Axis(0));
+
+        GlobalHaplotypes {
+            start,
+            end,
+            error_rate,
+            iterations,
+            haplotypes: haplotypes.into_iter()
+                .zip(frequencies)
+                .zip(counts.iter())
+                .map(|((bases, frequency), count)| Haplotype { bases, count: count.round() as u64, frequency })
+                .collect(),
+            reads: names,
+            posteriors,
+        }
+    }
+
+    /// Posterior probability of each read coming from each haplotype, and the mismatches and
+    /// compared positions of each pair.
+    fn e_step(reads: &[Vec<u8>], haplotypes: &[Vec<u8>], frequencies: &[f64], error_rate: f64) -> (Array<f64, Ix2>, Comparisons) {
+        let (ln_match, ln_mismatch) = ((1. - error_rate).ln(), (error_rate / 3.).ln());
+        let comparisons = reads.iter()
+            .map(|read| haplotypes.iter().map(|h| compare(read, h)).collect::<Vec<(f64, f64)>>())
+            .collect::<Comparisons>();
+
+        let mut posteriors = Array::<f64, Ix2>::zeros((reads.len(), haplotypes.len()));
+        for (mut row, comparison) in posteriors.axis_iter_mut(Axis(0)).zip(comparisons.iter()) {
+            let ln_p = comparison.iter()
+                .zip(frequencies.iter())
+                .map(|((m, c), f)| f.ln() + (c - m) * ln_match + m * ln_mismatch)
+                .collect::<Vec<f64>>();
+            let max = ln_p.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let sum = ln_p.iter().map(|x| (x - max).exp()).sum::<f64>();
+
+            for (z, x) in row.iter_mut().zip(ln_p.iter()) {
+                *z = (x - max).exp() / sum;
+            }
+        }
+
+        (posteriors, comparisons)
+    }
+
+    /// Seeds of the EM: greedy clusters of the reads, summarised by their consensus.
+    fn seed(sequences: &[Vec<u8>], len: usize, error_rate: f64) -> Vec<Vec<u8>> {
+        let mut distinct: HashMap<&Vec<u8>, usize> = HashMap::new();
+        for sequence in sequences {
+            *distinct.entry(sequence).or_insert(0) += 1;
+        }
+
+        let mut distinct = distinct.into_iter().collect::<Vec<(&Vec<u8>, usize)>>();
+        distinct.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let distance = |a: &[u8], b: &[u8]| {
+            let (m, c) = compare(a, b);
+
+            if c > 0. { m / c } else { 0. }
+        };
+
+        let mut seeds: Vec<&Vec<u8>> = Vec::new();
+        for (sequence, _) in distinct {
+            if seeds.iter().all(|s| distance(s, sequence) > 3. * error_rate) {
+                seeds.push(sequence);
+            }
+        }
+
+        let assignments = sequences.iter()
+            .map(|read| {
+                (0..seeds.len())
+                    .min_by(|a, b| distance(read, seeds[*a]).partial_cmp(&distance(read, seeds[*b])).unwrap())
+                    .unwrap()
+            })
+            .collect::<Vec<usize>>();
+
+        (0..seeds.len())
+            .map(|k| {
+                let weights = assignments.iter().map(|x| if *x == k { 1. } else { 0. }).collect::<Vec<f64>>();
+
+                consensus(sequences, &weights, len)
+            })
+            .collect()
+    }
+
+    /// Haplotypes from the most to the least frequent.
+    pub fn get_haplotypes(&self) -> &[Haplotype] {
+        self.haplotypes.as_slice()
+    }
+
+    pub fn get_reads(&self) -> &[String] {
+        self.reads.as_slice()
+    }
+
+    pub fn get_posteriors(&self) -> ArrayView<'_, f64, Ix2> {
+        self.posteriors.view()
+    }
+
+    /// Haplotypes in FASTA format, with positions numbered from `first` (1-based reference
+    /// coordinate of the first column) and frequencies in the headers.
+    pub fn to_fasta(&self, sample: &str, first: usize) -> String {
+        let (start, end) = (first + self.start, first + self.end - 1);
+
+        self.haplotypes.iter()
+            .enumerate()
+            .map(|(i, x)| format!(
+                ">{}_hap{} region={}-{} freq={:.6} reads={}\n{}\n",
+                sample, i + 1, start, end, x.frequency, x.count, x.sequence()
+            ))
+            .collect()
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["sample", "haplotype", "frequency", "reads", "error rate", "iterations"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    pub(crate) fn records(&self, sample: &str) -> Vec<Vec<String>> {
+        self.haplotypes.iter()
+            .enumerate()
+            .map(|(i, x)| vec![
+                sample.to_string(),
+                format!("hap{}", i + 1),
+                x.frequency.to_string(),
+                x.count.to_string(),
+                self.error_rate.to_string(),
+                self.iterations.to_string(),
+            ])
+            .collect()
+    }
+
+    pub(crate) fn posterior_header(&self) -> Vec<String> {
+        let mut header = vec!["read".to_string()];
+        header.extend((1..=self.haplotypes.len()).map(|i| format!("hap{}", i)));
+        header.push("assigned".to_string());
+
+        header
+    }
+
+    /// One record per read, with its posteriors and the haplotype it most likely comes from.
+    pub(crate) fn posterior_records(&self) -> Vec<Vec<String>> {
+        self.reads.iter()
+            .zip(self.posteriors.axis_iter(Axis(0)))
+            .map(|(name, z)| {
+                let assigned = (0..z.len()).max_by(|a, b| z[*a].partial_cmp(&z[*b]).unwrap()).map(|i| format!("hap{}", i + 1));
+
+                let mut record = vec![name.clone()];
+                record.extend(z.iter().map(|x| x.to_string()));
+                record.push(assigned.unwrap_or_default());
+
+                record
+            })
+            .collect()
+    }
+}
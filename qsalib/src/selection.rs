@@ -0,0 +1,236 @@
+use std::sync::OnceLock;
+
+use ndarray::Axis;
+
+use crate::protein::{ProteinMatrices, codon_bases, codon_index, translate};
+
+/// Synonymous sites of each codon and synonymous/nonsynonymous differences between codon pairs.
+///
+/// Sites and differences follow Nei and Gojobori (1986): multiple differences are averaged
+/// over all mutational pathways not going through a stop codon.
+struct CodeTables {
+    synonymous_sites: [f64; 64],
+    differences: Vec<[(f64, f64); 64]>,
+}
+
+/// Tables of the standard genetic code, built once for all the coding regions of all the samples.
+static CODE_TABLES: OnceLock<CodeTables> = OnceLock::new();
+
+impl CodeTables {
+    fn get() -> &'static CodeTables {
+        CODE_TABLES.get_or_init(CodeTables::new)
+    }
+
+    fn new() -> CodeTables {
+        let mut synonymous_sites = [0.; 64];
+        for (codon, sites) in synonymous_sites.iter_mut().enumerate() {
+            let bases = codon_bases(codon);
+
+            for pos in 0..3 {
+                for nt in (0..4).filter(|x| *x != bases[pos]) {
+                    let mut mutant = bases;
+                    mutant[pos] = nt;
+
+                    if translate(codon_index(mutant)) == translate(codon) {
+                        *sites += 1. / 3.;
+                    }
+                }
+            }
+        }
+
+        let differences = (0..64)
+            .map(|a| {
+                let mut row = [(0., 0.); 64];
+                for (b, diff) in row.iter_mut().enumerate() {
+                    *diff = CodeTables::pathways(a, b);
+                }
+
+                row
+            })
+            .collect();
+
+        CodeTables {
+            synonymous_sites,
+            differences,
+        }
+    }
+
+    /// Synonymous and nonsynonymous differences between two codons, averaged over pathways.
+    fn pathways(a: usize, b: usize) -> (f64, f64) {
+        let (from, to) = (codon_bases(a), codon_bases(b));
+        let positions = (0..3).filter(|x| from[*x] != to[*x]).collect::<Vec<usize>>();
+
+        let orders: Vec<Vec<usize>> = match positions.len() {
+            0 => return (0., 0.),
+            1 => vec![positions],
+            2 => vec![positions.clone(), vec![positions[1], positions[0]]],
+            _ => vec![
+                vec![0, 1, 2], vec![0, 2, 1], vec![1, 0, 2],
+                vec![1, 2, 0], vec![2, 0, 1], vec![2, 1, 0],
+            ],
+        };
+
+        let stop = translate(codon_index([3, 0, 0]));
+        let mut valid = (0., 0., 0);
+        let mut all = (0., 0., 0);
+        for order in orders {
+            let (mut syn, mut nonsyn, mut through_stop) = (0., 0., false);
+            let mut current = from;
+
+            for (step, pos) in order.iter().enumerate() {
+                let mut next = current;
+                next[*pos] = to[*pos];
+
+                if translate(codon_index(next)) == translate(codon_index(current)) {
+                    syn += 1.;
+                } else {
+                    nonsyn += 1.;
+                }
+
+                if step < order.len() - 1 && translate(codon_index(next)) == stop {
+                    through_stop = true;
+                }
+
+                current = next;
+            }
+
+            all = (all.0 + syn, all.1 + nonsyn, all.2 + 1);
+            if !through_stop {
+                valid = (valid.0 + syn, valid.1 + nonsyn, valid.2 + 1);
+            }
+        }
+
+        let (syn, nonsyn, n) = if valid.2 > 0 { valid } else { all };
+
+        (syn / n as f64, nonsyn / n as f64)
+    }
+}
+
+/// Nucleotide diversity of a single codon position, split by the effect of the differences.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CodonDiversity {
+    pub synonymous_sites: f64,
+    pub nonsynonymous_sites: f64,
+    /// Mean synonymous differences between two codons picked at random.
+    pub synonymous_diffs: f64,
+    /// Mean nonsynonymous differences between two codons picked at random.
+    pub nonsynonymous_diffs: f64,
+}
+
+impl CodonDiversity {
+    pub fn pi_n(&self) -> f64 {
+        if self.nonsynonymous_sites > 0. { self.nonsynonymous_diffs / self.nonsynonymous_sites } else { 0. }
+    }
+
+    pub fn pi_s(&self) -> f64 {
+        if self.synonymous_sites > 0. { self.synonymous_diffs / self.synonymous_sites } else { 0. }
+    }
+
+    /// πN/πS, NaN when neither kind of diversity is present.
+    pub fn dn_ds(&self) -> f64 {
+        self.pi_n() / self.pi_s()
+    }
+}
+
+/// Synonymous and nonsynonymous diversity of a coding region, from codons called on reads.
+pub struct Selection {
+    pub name: String,
+    codons: Vec<CodonDiversity>,
+}
+
+impl Selection {
+    pub fn new(protein: &ProteinMatrices) -> Selection {
+        let tables = CodeTables::get();
+
+        let codons = protein.get_codons()
+            .axis_iter(Axis(1))
+            .map(|counts| {
+                let total = counts.sum() as f64;
+                if total < 2. {
+                    return CodonDiversity::default();
+                }
+
+                let mut diversity = CodonDiversity::default();
+                for (a, na) in counts.iter().enumerate().filter(|(_, x)| **x > 0) {
+                    let freq = *na as f64 / total;
+                    diversity.synonymous_sites += freq * tables.synonymous_sites[a];
+                    diversity.nonsynonymous_sites += freq * (3. - tables.synonymous_sites[a]);
+
+                    for (b, nb) in counts.iter().enumerate().skip(a + 1).filter(|(_, x)| **x > 0) {
+                        let (syn, nonsyn) = tables.differences[a][b];
+                        let pairs = *na as f64 * *nb as f64;
+
+                        diversity.synonymous_diffs += pairs * syn;
+                        diversity.nonsynonymous_diffs += pairs * nonsyn;
+                    }
+                }
+
+                let comparisons = total * (total - 1.) / 2.;
+                diversity.synonymous_diffs /= comparisons;
+                diversity.nonsynonymous_diffs /= comparisons;
+
+                diversity
+            })
+            .collect();
+
+        Selection {
+            name: protein.name.clone(),
+            codons,
+        }
+    }
+
+    pub fn get_codons(&self) -> &[CodonDiversity] {
+        self.codons.as_slice()
+    }
+
+    /// Totals of the codons in a slice, so that πN and πS are ratios of sums.
+    fn pooled(codons: &[CodonDiversity]) -> CodonDiversity {
+        codons.iter()
+            .fold(CodonDiversity::default(), |acc, x| CodonDiversity {
+                synonymous_sites: acc.synonymous_sites + x.synonymous_sites,
+                nonsynonymous_sites: acc.nonsynonymous_sites + x.nonsynonymous_sites,
+                synonymous_diffs: acc.synonymous_diffs + x.synonymous_diffs,
+                nonsynonymous_diffs: acc.nonsynonymous_diffs + x.nonsynonymous_diffs,
+            })
+    }
+
+    /// Diversity of the whole region.
+    pub fn summary(&self) -> CodonDiversity {
+        Selection::pooled(&self.codons)
+    }
+
+    /// Diversity of windows of `width` codons moved by `step` codons; the first value is the
+    /// 1-based codon at the centre of each window.
+    pub fn sliding_window(&self, width: usize, step: usize) -> Vec<(usize, CodonDiversity)> {
+        if width == 0 || step == 0 || self.codons.len() < width {
+            return Vec::new();
+        }
+
+        (0..=self.codons.len() - width)
+            .step_by(step)
+            .map(|start| (start + width / 2 + 1, Selection::pooled(&self.codons[start..start + width])))
+            .collect()
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["protein", "codon", "N sites", "S sites", "piN", "piS", "dN/dS"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    pub(crate) fn records(&self) -> Vec<Vec<String>> {
+        self.codons.iter()
+            .enumerate()
+            .map(|(i, codon)| vec![
+                self.name.clone(),
+                (i + 1).to_string(),
+                codon.nonsynonymous_sites.to_string(),
+                codon.synonymous_sites.to_string(),
+                codon.pi_n().to_string(),
+                codon.pi_s().to_string(),
+                codon.dn_ds().to_string(),
+            ])
+            .collect()
+    }
+}
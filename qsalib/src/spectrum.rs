@@ -0,0 +1,141 @@
+use ndarray::{Array, ArrayView, Ix1, Ix2};
+
+/// Nucleotides in the same order as the PFM rows.
+pub const NUCLEOTIDES: [char; 4] = ['A', 'C', 'G', 'T'];
+
+/// Dinucleotide context of a reference G, the one APOBEC3G (GG) and APOBEC3F (GA) edit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GContext {
+    GG,
+    GA,
+    Other,
+}
+
+impl GContext {
+    fn index(&self) -> usize {
+        match self {
+            GContext::GG => 0,
+            GContext::GA => 1,
+            GContext::Other => 2,
+        }
+    }
+}
+
+pub struct Spectrum {
+    substitutions: Array<u64, Ix2>,
+    g_to_a: [(u64, u64); 3],
+}
+
+impl Spectrum {
+    /// Builds the spectrum from a PFM and the reference base (PFM row) of each of its columns.
+    ///
+    /// Positions whose reference base is unknown (row 4) are ignored.
+    pub fn new(pfm: ArrayView<u64, Ix2>, reference: ArrayView<u8, Ix1>) -> Spectrum {
+        let mut substitutions = Array::<u64, Ix2>::zeros((4, 4));
+        let mut g_to_a = [(0, 0); 3];
+
+        for (col, nt) in reference.iter().enumerate().take(pfm.ncols()) {
+            let from = *nt as usize;
+            if from > 3 {
+                continue
+            }
+
+            for to in 0..4 {
+                substitutions[[from, to]] += pfm[[to, col]];
+            }
+
+            if from == 2 {
+                let context = match reference.get(col + 1) {
+                    Some(2) => GContext::GG,
+                    Some(0) => GContext::GA,
+                    _ => GContext::Other,
+                };
+
+                let counts = &mut g_to_a[context.index()];
+                counts.0 += pfm[[0, col]];
+                counts.1 += pfm.column(col).sum();
+            }
+        }
+
+        Spectrum {
+            substitutions,
+            g_to_a,
+        }
+    }
+
+    /// Counts of reference (rows) against observed (columns) bases; the diagonal holds the matches.
+    pub fn get_substitutions(&self) -> ArrayView<'_, u64, Ix2> {
+        self.substitutions.view()
+    }
+
+    /// The 12 substitution classes, labelled as `X>Y`, in reference-major order.
+    pub fn classes(&self) -> Vec<(String, u64)> {
+        let mut rv: Vec<(String, u64)> = Vec::new();
+        for (from, f) in NUCLEOTIDES.iter().enumerate() {
+            for (to, t) in NUCLEOTIDES.iter().enumerate() {
+                if from != to {
+                    rv.push((format!("{}>{}", f, t), self.substitutions[[from, to]]));
+                }
+            }
+        }
+
+        rv
+    }
+
+    pub fn transitions(&self) -> u64 {
+        let s = &self.substitutions;
+
+        s[[0, 2]] + s[[2, 0]] + s[[1, 3]] + s[[3, 1]]
+    }
+
+    pub fn transversions(&self) -> u64 {
+        self.classes().iter().map(|(_, x)| x).sum::<u64>() - self.transitions()
+    }
+
+    /// Transition/transversion ratio, infinite when no transversion was observed and NaN when no
+    /// substitution was.
+    pub fn ts_tv(&self) -> f64 {
+        let (transitions, transversions) = (self.transitions(), self.transversions());
+
+        if transitions == 0 && transversions == 0 { f64::NAN } else { transitions as f64 / transversions as f64 }
+    }
+
+    /// Fraction of bases read as A over a reference G in the given context.
+    pub fn g_to_a_rate(&self, context: GContext) -> f64 {
+        let (g_to_a, total) = self.g_to_a[context.index()];
+
+        if total == 0 { 0. } else { g_to_a as f64 / total as f64 }
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        let mut header = vec!["sample".to_string()];
+        for from in NUCLEOTIDES.iter() {
+            for to in NUCLEOTIDES.iter() {
+                if from != to {
+                    header.push(format!("{}>{}", from, to));
+                }
+            }
+        }
+
+        header.extend(
+            ["transitions", "transversions", "ts/tv", "G>A GG", "G>A GA", "G>A other"]
+                .iter()
+                .map(|x| x.to_string())
+        );
+
+        header
+    }
+
+    pub(crate) fn record(&self, name: &str) -> Vec<String> {
+        let mut record = vec![name.to_string()];
+        record.extend(self.classes().iter().map(|(_, x)| x.to_string()));
+        record.push(self.transitions().to_string());
+        record.push(self.transversions().to_string());
+        record.push(self.ts_tv().to_string());
+        record.push(self.g_to_a_rate(GContext::GG).to_string());
+        record.push(self.g_to_a_rate(GContext::GA).to_string());
+        record.push(self.g_to_a_rate(GContext::Other).to_string());
+
+        record
+    }
+}
@@ -0,0 +1,302 @@
+use std::cmp::Ordering;
+
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use statrs::distribution::{ChiSquared, ContinuousCDF, Normal};
+use statrs::function::factorial::{ln_binomial, ln_factorial};
+use statrs::function::beta::ln_beta;
+
+/// Log-probability of a 2x2 table `[[a, b], [c, d]]` under the hypergeometric distribution.
+fn ln_hypergeometric(a: u64, b: u64, c: u64, d: u64) -> f64 {
+    ln_factorial(a + b) + ln_factorial(c + d) + ln_factorial(a + c) + ln_factorial(b + d)
+        - ln_factorial(a) - ln_factorial(b) - ln_factorial(c) - ln_factorial(d)
+        - ln_factorial(a + b + c + d)
+}
+
+/// One-sided Fisher's exact test on `[[a, b], [c, d]]`, where the alternative is `a / (a + b) > c / (c + d)`.
+pub fn fisher_exact_greater(a: u64, b: u64, c: u64, d: u64) -> f64 {
+    let (row, col) = (a + b, a + c);
+    let max = row.min(col);
+
+    let p = (a..=max)
+        .map(|x| ln_hypergeometric(x, row - x, col - x, d + x - a).exp())
+        .sum::<f64>();
+
+    p.min(1.)
+}
+
+/// Two-sided Fisher's exact test on `[[a, b], [c, d]]`, summing the tables at most as likely as the observed one.
+pub fn fisher_exact(a: u64, b: u64, c: u64, d: u64) -> f64 {
+    let (row, col, n) = (a + b, a + c, a + b + c + d);
+    let observed = ln_hypergeometric(a, b, c, d);
+
+    let min = (row + col).saturating_sub(n);
+    let p = (min..=row.min(col))
+        .map(|x| ln_hypergeometric(x, row - x, col - x, n + x - row - col))
+        .filter(|x| *x <= observed + 1e-7)
+        .map(|x| x.exp())
+        .sum::<f64>();
+
+    p.min(1.)
+}
+
+/// Ranks of the values, starting from 1, with ties given their average rank.
+pub fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order = (0..values.len()).collect::<Vec<usize>>();
+    order.sort_by(|a, b| values[*a].partial_cmp(&values[*b]).unwrap_or(Ordering::Equal));
+
+    let mut ranks = vec![0.; values.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && values[order[j + 1]] == values[order[i]] {
+            j += 1;
+        }
+
+        for k in &order[i..=j] {
+            ranks[*k] = (i + j) as f64 / 2. + 1.;
+        }
+        i = j + 1;
+    }
+
+    ranks
+}
+
+/// Sizes of the groups of tied values.
+pub fn ties(values: &[f64]) -> Vec<usize> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    let mut ties: Vec<usize> = Vec::new();
+    for (i, x) in sorted.iter().enumerate() {
+        if i > 0 && sorted[i - 1] == *x {
+            *ties.last_mut().unwrap() += 1;
+        } else {
+            ties.push(1);
+        }
+    }
+
+    ties
+}
+
+/// Permutation p-value of a statistic computed on a labelling of the samples, where larger values
+/// are more extreme.
+///
+/// Labels are shuffled `permutations` times with a generator seeded with `seed`, so that results are
+/// reproducible; the p-value is `(k + 1) / (permutations + 1)`, `k` being the number of permutations
+/// at least as extreme as the observed labelling. It is NaN without permutations.
+pub fn permutation_p_value<F>(labels: &[usize], statistic: F, permutations: usize, seed: u64) -> f64
+    where F: Fn(&[usize]) -> f64
+{
+    if permutations == 0 {
+        return f64::NAN;
+    }
+
+    let observed = statistic(labels);
+    let tolerance = 1e-12 * observed.abs().max(1.);
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut shuffled = labels.to_vec();
+
+    let extreme = (0..permutations)
+        .filter(|_| {
+            shuffled.shuffle(&mut rng);
+            statistic(&shuffled) >= observed - tolerance
+        })
+        .count();
+
+    (extreme + 1) as f64 / (permutations + 1) as f64
+}
+
+/// G-test of independence on a contingency table, returning the statistic and its p-value.
+///
+/// Empty rows and columns are dropped; with fewer than two of either there is nothing to test and
+/// the p-value is 1.
+pub fn g_test(table: &[Vec<u64>]) -> (f64, f64) {
+    let ncols = table.iter().map(|x| x.len()).max().unwrap_or(0);
+    let rows = table.iter().filter(|x| x.iter().sum::<u64>() > 0).collect::<Vec<_>>();
+    let cols = (0..ncols).filter(|j| rows.iter().any(|x| x.get(*j).copied().unwrap_or(0) > 0)).collect::<Vec<_>>();
+
+    if rows.len() < 2 || cols.len() < 2 {
+        return (0., 1.);
+    }
+
+    let row_sums = rows.iter().map(|x| x.iter().sum::<u64>() as f64).collect::<Vec<f64>>();
+    let col_sums = cols.iter().map(|j| rows.iter().map(|x| x.get(*j).copied().unwrap_or(0)).sum::<u64>() as f64).collect::<Vec<f64>>();
+    let total = row_sums.iter().sum::<f64>();
+
+    let mut g = 0.;
+    for (row, row_sum) in rows.iter().zip(row_sums.iter()) {
+        for (j, col_sum) in cols.iter().zip(col_sums.iter()) {
+            let observed = row.get(*j).copied().unwrap_or(0) as f64;
+            if observed > 0. {
+                g += observed * (observed * total / (row_sum * col_sum)).ln();
+            }
+        }
+    }
+    g *= 2.;
+
+    let df = ((rows.len() - 1) * (cols.len() - 1)) as f64;
+
+    (g, ChiSquared::new(df).unwrap().sf(g.max(0.)))
+}
+
+/// Two-sided Mann–Whitney U test with the normal approximation, corrected for ties and continuity.
+///
+/// Returns U of the first sample and the p-value, which is NaN if a sample is empty.
+pub fn mann_whitney(x: &[f64], y: &[f64]) -> (f64, f64) {
+    let (n1, n2) = (x.len() as f64, y.len() as f64);
+    if x.is_empty() || y.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+
+    let values = x.iter().chain(y.iter()).copied().collect::<Vec<f64>>();
+    let u = ranks(&values)[..x.len()].iter().sum::<f64>() - n1 * (n1 + 1.) / 2.;
+
+    let n = n1 + n2;
+    let tied = ties(&values).iter().map(|t| (t.pow(3) - t) as f64).sum::<f64>();
+    let variance = n1 * n2 / 12. * ((n + 1.) - tied / (n * (n - 1.)));
+    if variance <= 0. {
+        return (u, 1.);
+    }
+
+    let z = ((u - n1 * n2 / 2.).abs() - 0.5).max(0.) / variance.sqrt();
+
+    (u, (2. * Normal::new(0., 1.).unwrap().sf(z)).min(1.))
+}
+
+/// Benjamini–Hochberg adjusted p-values (q-values); NaN p-values are left out and stay NaN.
+pub fn benjamini_hochberg(p_values: &[f64]) -> Vec<f64> {
+    let mut order = (0..p_values.len()).filter(|i| !p_values[*i].is_nan()).collect::<Vec<usize>>();
+    order.sort_by(|a, b| p_values[*a].partial_cmp(&p_values[*b]).unwrap_or(Ordering::Equal));
+
+    let m = order.len() as f64;
+    let mut q_values = vec![f64::NAN; p_values.len()];
+    let mut min = 1_f64;
+
+    for (rank, i) in order.iter().enumerate().rev() {
+        min = min.min(p_values[*i] * m / (rank + 1) as f64);
+        q_values[*i] = min;
+    }
+
+    q_values
+}
+
+/// Log-probability of `k` successes out of `n` under a beta-binomial distribution with mean `mu`
+/// and intra-class correlation (overdispersion) `rho`, which needs to lie strictly between 0 and 1.
+pub fn beta_binomial_ln_pmf(k: u64, n: u64, mu: f64, rho: f64) -> f64 {
+    let scale = (1. - rho) / rho;
+    let (alpha, beta) = (mu * scale, (1. - mu) * scale);
+
+    ln_binomial(n, k) + ln_beta(k as f64 + alpha, (n - k) as f64 + beta) - ln_beta(alpha, beta)
+}
+
+/// Maximum likelihood mean of a beta-binomial with known overdispersion, by golden-section search.
+fn beta_binomial_mean(observations: &[(u64, u64)], rho: f64) -> f64 {
+    let ln_likelihood = |mu: f64| observations.iter().map(|(k, n)| beta_binomial_ln_pmf(*k, *n, mu, rho)).sum::<f64>();
+    let ratio = (5_f64.sqrt() - 1.) / 2.;
+
+    let (mut lo, mut hi) = (1e-9, 1. - 1e-9);
+    while hi - lo > 1e-9 {
+        let (a, b) = (hi - ratio * (hi - lo), lo + ratio * (hi - lo));
+
+        if ln_likelihood(a) > ln_likelihood(b) {
+            hi = b;
+        } else {
+            lo = a;
+        }
+    }
+
+    (lo + hi) / 2.
+}
+
+/// Likelihood ratio test that `(successes, trials)` observations share the same beta-binomial mean,
+/// against a mean for each observation, with known overdispersion `rho`.
+///
+/// Returns the statistic and its p-value under a chi-square distribution with one degree of freedom
+/// less than the number of observations with at least one trial; with fewer than two, the p-value is NaN.
+pub fn beta_binomial_homogeneity(observations: &[(u64, u64)], rho: f64) -> (f64, f64) {
+    let observations = observations.iter().copied().filter(|(_, n)| *n > 0).collect::<Vec<_>>();
+    if observations.len() < 2 {
+        return (f64::NAN, f64::NAN);
+    }
+
+    let pooled = beta_binomial_mean(&observations, rho);
+    let null = observations.iter().map(|(k, n)| beta_binomial_ln_pmf(*k, *n, pooled, rho)).sum::<f64>();
+    let alternative = observations.iter()
+        .map(|(k, n)| beta_binomial_ln_pmf(*k, *n, beta_binomial_mean(&[(*k, *n)], rho), rho))
+        .sum::<f64>();
+
+    let statistic = (2. * (alternative - null)).max(0.);
+    let df = (observations.len() - 1) as f64;
+
+    (statistic, ChiSquared::new(df).unwrap().sf(statistic))
+}
+
+/// Least squares fit of `y = slope * x + intercept`, returning the slope, the intercept and R².
+pub fn linear_regression(x: &[f64], y: &[f64]) -> (f64, f64, f64) {
+    let n = x.len() as f64;
+    let (mean_x, mean_y) = (x.iter().sum::<f64>() / n, y.iter().sum::<f64>() / n);
+
+    let sxx = x.iter().map(|a| (a - mean_x).powi(2)).sum::<f64>();
+    let syy = y.iter().map(|b| (b - mean_y).powi(2)).sum::<f64>();
+    let sxy = x.iter().zip(y.iter()).map(|(a, b)| (a - mean_x) * (b - mean_y)).sum::<f64>();
+
+    let slope = sxy / sxx;
+
+    (slope, mean_y - slope * mean_x, sxy * sxy / (sxx * syy))
+}
+
+/// Quantile of the values, by linear interpolation between the closest ranks, ignoring NaN.
+pub fn quantile(values: &[f64], q: f64) -> f64 {
+    let mut sorted = values.iter().copied().filter(|x| !x.is_nan()).collect::<Vec<f64>>();
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    let position = q * (sorted.len() - 1) as f64;
+    let (lo, hi) = (position.floor() as usize, position.ceil() as usize);
+
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (position - lo as f64)
+}
+
+/// Columns of `length` positions split into at most `count` bins of nearly equal width.
+pub fn bins(length: usize, count: usize) -> Vec<(usize, usize)> {
+    let count = count.min(length);
+
+    (0..count).map(|k| (k * length / count, (k + 1) * length / count)).collect()
+}
+
+/// Mean of the values of each bin, leaving out those that are not a number.
+pub fn bin_means(values: &[f64], bins: &[(usize, usize)]) -> Vec<f64> {
+    bins.iter()
+        .map(|(start, end)| {
+            let (sum, n) = values[*start..*end].iter()
+                .filter(|x| !x.is_nan())
+                .fold((0., 0), |acc, x| (acc.0 + x, acc.1 + 1));
+
+            if n > 0 { sum / n as f64 } else { f64::NAN }
+        })
+        .collect()
+}
+
+/// Centred moving average over `window` positions, or the next odd number, leaving out the values
+/// that are not a number. The window is cut short at both ends of the values. Positions without a
+/// value stay without one, so that gaps in coverage are not bridged.
+pub fn moving_average(values: &[f64], window: usize) -> Vec<f64> {
+    let half = window / 2;
+
+    (0..values.len())
+        .map(|i| {
+            if values[i].is_nan() {
+                return f64::NAN;
+            }
+
+            let (sum, n) = values[i.saturating_sub(half)..(i + half + 1).min(values.len())].iter()
+                .filter(|x| !x.is_nan())
+                .fold((0., 0), |acc, x| (acc.0 + x, acc.1 + 1));
+
+            sum / n as f64
+        })
+        .collect()
+}
@@ -0,0 +1,202 @@
+use std::path::Path;
+
+use csv::WriterBuilder;
+use serde_json::{json, Map, Value};
+
+use crate::reads::ReadCounts;
+
+/// Version of the layout of the tidy tables, raised whenever a column is renamed, removed or
+/// changes meaning, so that pipelines can tell which layout they are reading.
+///
+/// Version 2 leaves unmapped records out of `reads` in the `samples` table and adds the counts of
+/// processed, unmapped and out-of-range reads and the breadth of coverage.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Depths at which the breadth of coverage is reported.
+pub const BREADTH_DEPTHS: [u64; 3] = [1, 100, 1000];
+
+/// Metrics of one position of a sample.
+#[derive(Debug, Clone)]
+pub struct PositionMetrics {
+    /// 1-based position on the reference.
+    pub position: usize,
+    pub depth: u64,
+    /// Counts of A, C, G and T.
+    pub counts: [u64; 4],
+    pub frequencies: [f64; 4],
+    /// Shannon entropy of the bases, in bits.
+    pub entropy: f64,
+    pub efficiency: f64,
+    /// Whether the position is not covered or its coverage is below the threshold.
+    pub masked: bool,
+}
+
+/// Summary of a sample: the reads it was built from, its depth and its α-diversity.
+#[derive(Debug, Clone)]
+pub struct SampleSummary {
+    pub sample: String,
+    /// Records of the BAM file, and those left out of the analysed range by reason.
+    pub read_counts: ReadCounts,
+    pub hypermutated: usize,
+    /// Reads left out of the matrices.
+    pub excluded: usize,
+    pub positions: usize,
+    pub mean_depth: f64,
+    pub median_depth: f64,
+    /// Fraction of the positions read at least as many times as each of `BREADTH_DEPTHS`.
+    pub breadth: [f64; 3],
+    /// Positions not covered by any read.
+    pub uncovered: usize,
+    pub masked: usize,
+    pub alpha: f64,
+    /// 95% bootstrap confidence interval of the α-diversity.
+    pub alpha_ci: (f64, f64),
+}
+
+impl SampleSummary {
+    /// Reads inside the analysed range the matrices were built from.
+    pub fn analysed_reads(&self) -> usize {
+        self.read_counts.kept().saturating_sub(self.excluded)
+    }
+
+    pub(crate) fn qc_header() -> Vec<String> {
+        let mut header = [
+            "sample", "processed reads", "unmapped reads", "reads outside range", "hypermutated reads",
+            "excluded reads", "analysed reads", "mean depth", "median depth",
+        ]
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>();
+        header.extend(BREADTH_DEPTHS.iter().map(|x| format!("breadth {}x", x)));
+        header.extend(["positions".to_string(), "masked positions".to_string()]);
+
+        header
+    }
+
+    pub(crate) fn qc_record(&self) -> Vec<String> {
+        let mut record = vec![
+            self.sample.clone(),
+            self.read_counts.processed.to_string(),
+            self.read_counts.unmapped.to_string(),
+            self.read_counts.outside_range.to_string(),
+            self.hypermutated.to_string(),
+            self.excluded.to_string(),
+            self.analysed_reads().to_string(),
+            self.mean_depth.to_string(),
+            self.median_depth.to_string(),
+        ];
+        record.extend(self.breadth.iter().map(|x| x.to_string()));
+        record.extend([self.positions.to_string(), self.masked.to_string()]);
+
+        record
+    }
+}
+
+/// A finite number, or null for NaN and infinities.
+pub(crate) fn number(x: f64) -> Value {
+    if x.is_finite() { json!(x) } else { Value::Null }
+}
+
+/// A table of typed values, written as tab-separated values or as JSON.
+///
+/// Both files carry the same columns. The JSON file also holds the name of the table, the version
+/// of the format and any attribute of the table, with its rows as arrays in the order of the
+/// columns. Missing values are `NA` in TSV and `null` in JSON.
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    pub attributes: Map<String, Value>,
+}
+
+impl Table {
+    pub fn new(name: &str, columns: &[&str]) -> Table {
+        Table {
+            name: name.to_string(),
+            columns: columns.iter().map(|x| x.to_string()).collect(),
+            rows: Vec::new(),
+            attributes: Map::new(),
+        }
+    }
+
+    pub fn with_attribute(&mut self, key: &str, value: Value) -> &mut Self {
+        self.attributes.insert(key.to_string(), value);
+
+        self
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) {
+        assert_eq!(row.len(), self.columns.len(), "row of {} values in a table of {} columns", row.len(), self.columns.len());
+
+        self.rows.push(row);
+    }
+
+    fn cell(value: &Value) -> String {
+        match value {
+            Value::Null => "NA".to_string(),
+            Value::String(x) => x.clone(),
+            x => x.to_string(),
+        }
+    }
+
+    pub fn to_tsv<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut writer = WriterBuilder::new()
+            .delimiter(b'\t')
+            .from_path(path.as_ref().join(Path::new(filename)))
+            .expect("could not open file");
+
+        writer.write_record(&self.columns).unwrap();
+
+        for row in &self.rows {
+            writer.write_record(row.iter().map(Table::cell)).unwrap();
+        }
+    }
+
+    pub fn to_json<P>(&self, path: P, filename: &str)
+        where P: AsRef<Path>
+    {
+        let mut table = json!({
+            "format": "qsa",
+            "version": FORMAT_VERSION,
+            "table": self.name,
+            "columns": self.columns,
+        });
+        for (key, value) in &self.attributes {
+            table[key] = value.clone();
+        }
+        table["rows"] = json!(self.rows);
+
+        std::fs::write(path.as_ref().join(Path::new(filename)), table.to_string()).expect("could not write file");
+    }
+
+    /// Writes `<name>.tsv` and `<name>.json`.
+    pub fn to_files<P>(&self, path: P)
+        where P: AsRef<Path>
+    {
+        self.to_tsv(path.as_ref(), (self.name.clone() + ".tsv").as_str());
+        self.to_json(path.as_ref(), (self.name.clone() + ".json").as_str());
+    }
+}
+
+/// Writes the list of the tables in `path`, with their files and columns, under the version of the format.
+pub fn manifest_to_json<P>(path: P, filename: &str, tables: &[&Table])
+    where P: AsRef<Path>
+{
+    let manifest = json!({
+        "format": "qsa",
+        "version": FORMAT_VERSION,
+        "tables": tables.iter()
+            .map(|x| json!({
+                "name": x.name,
+                "files": [x.name.clone() + ".tsv", x.name.clone() + ".json"],
+                "columns": x.columns,
+                "rows": x.rows.len(),
+            }))
+            .collect::<Vec<Value>>(),
+    });
+
+    std::fs::write(path.as_ref().join(Path::new(filename)), manifest.to_string()).expect("could not write file");
+}
@@ -0,0 +1,183 @@
+use std::cmp::Ordering;
+
+use ndarray::Axis;
+
+use crate::matrices::Matrices;
+use crate::spectrum::NUCLEOTIDES;
+use crate::stats::{beta_binomial_homogeneity, benjamini_hochberg};
+
+/// A sample's name, its time and its matrices.
+pub type TimedSample<'a> = (String, f64, &'a Matrices);
+
+/// Frequency of a minor allele at one position across the timepoints of an individual.
+#[derive(Debug, Clone)]
+pub struct Trajectory {
+    /// Column of the position in the matrices.
+    pub column: usize,
+    /// Most frequent base at the first timepoint covering the position.
+    pub baseline: usize,
+    pub allele: usize,
+    /// Allele and total counts at each timepoint, in the order of `Trajectories::samples`.
+    pub counts: Vec<(u64, u64)>,
+    pub statistic: f64,
+    pub p_value: f64,
+    pub q_value: f64,
+}
+
+impl Trajectory {
+    pub fn frequencies(&self) -> Vec<f64> {
+        self.counts.iter().map(|(k, n)| *k as f64 / *n as f64).collect()
+    }
+
+    /// Frequency at the last covered timepoint minus the one at the first.
+    pub fn change(&self) -> f64 {
+        let covered = self.frequencies().into_iter().filter(|x| !x.is_nan()).collect::<Vec<f64>>();
+
+        match (covered.first(), covered.last()) {
+            (Some(first), Some(last)) => last - first,
+            _ => f64::NAN,
+        }
+    }
+
+    /// Whether the allele rises or falls significantly at a false discovery rate of `fdr`.
+    pub fn direction(&self, fdr: f64) -> &'static str {
+        if self.q_value <= fdr {
+            if self.change() > 0. { "rising" } else { "falling" }
+        } else {
+            "stable"
+        }
+    }
+}
+
+/// Minor-allele trajectories of the samples of one individual, ordered by timepoint.
+///
+/// An allele is followed if it differs from the baseline base and reaches `min_freq` at some
+/// timepoint. Changes in its frequency are tested with a beta-binomial likelihood ratio test, whose
+/// overdispersion accounts for the noise of sequencing and amplification beyond sampling depth, and
+/// p-values are corrected with the Benjamini–Hochberg procedure over the trajectories.
+pub struct Trajectories {
+    pub series: String,
+    /// Names and timepoints of the samples.
+    pub samples: Vec<(String, f64)>,
+    trajectories: Vec<Trajectory>,
+}
+
+impl Trajectories {
+    pub fn new(series: &str, samples: &[TimedSample], min_freq: f64, overdispersion: f64) -> Trajectories {
+        let mut samples = samples.to_vec();
+        samples.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        let ncols = samples.iter().map(|x| x.2.get_pfm().ncols()).min().unwrap_or(0);
+        let mut trajectories: Vec<Trajectory> = Vec::new();
+
+        for column in 0..ncols {
+            let pfms = samples.iter().map(|x| x.2.get_pfm().index_axis_move(Axis(1), column)).collect::<Vec<_>>();
+
+            let baseline = match pfms.iter().find(|x| x.sum() > 0) {
+                Some(pfm) => (0..NUCLEOTIDES.len()).max_by_key(|i| pfm[*i]).unwrap(),
+                None => continue,
+            };
+
+            for allele in (0..NUCLEOTIDES.len()).filter(|x| *x != baseline) {
+                let counts = pfms.iter().map(|x| (x[allele], x.sum())).collect::<Vec<(u64, u64)>>();
+                if !counts.iter().any(|(k, n)| *n > 0 && *k as f64 / *n as f64 >= min_freq) {
+                    continue
+                }
+
+                let (statistic, p_value) = beta_binomial_homogeneity(&counts, overdispersion);
+
+                trajectories.push(Trajectory {
+                    column,
+                    baseline,
+                    allele,
+                    counts,
+                    statistic,
+                    p_value,
+                    q_value: f64::NAN,
+                });
+            }
+        }
+
+        let q_values = benjamini_hochberg(&trajectories.iter().map(|x| x.p_value).collect::<Vec<f64>>());
+        for (trajectory, q) in trajectories.iter_mut().zip(q_values) {
+            trajectory.q_value = q;
+        }
+
+        Trajectories {
+            series: series.to_string(),
+            samples: samples.into_iter().map(|x| (x.0, x.1)).collect(),
+            trajectories,
+        }
+    }
+
+    pub fn get_trajectories(&self) -> &[Trajectory] {
+        self.trajectories.as_slice()
+    }
+
+    /// The `n` trajectories changing the most, by p-value and then by absolute change.
+    pub fn top(&self, n: usize) -> Vec<&Trajectory> {
+        let mut sorted = self.trajectories.iter().filter(|x| !x.p_value.is_nan()).collect::<Vec<&Trajectory>>();
+        sorted.sort_by(|a, b| {
+            a.p_value.partial_cmp(&b.p_value)
+                .unwrap_or(Ordering::Equal)
+                .then(b.change().abs().partial_cmp(&a.change().abs()).unwrap_or(Ordering::Equal))
+        });
+        sorted.truncate(n);
+
+        sorted
+    }
+
+    pub(crate) fn header() -> Vec<String> {
+        ["series", "position", "baseline", "allele", "timepoints", "change", "statistic", "p-value", "q-value", "direction"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// One record per trajectory, numbered from `first` (1-based reference coordinate of the first column).
+    pub fn records(&self, first: usize, fdr: f64) -> Vec<Vec<String>> {
+        self.trajectories.iter()
+            .map(|x| vec![
+                self.series.clone(),
+                (first + x.column).to_string(),
+                NUCLEOTIDES[x.baseline].to_string(),
+                NUCLEOTIDES[x.allele].to_string(),
+                x.counts.iter().filter(|(_, n)| *n > 0).count().to_string(),
+                x.change().to_string(),
+                x.statistic.to_string(),
+                x.p_value.to_string(),
+                x.q_value.to_string(),
+                x.direction(fdr).to_string(),
+            ])
+            .collect()
+    }
+
+    pub(crate) fn tidy_header() -> Vec<String> {
+        ["series", "position", "allele", "sample", "timepoint", "count", "depth", "frequency"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    /// One record per trajectory and covered timepoint.
+    pub fn tidy_records(&self, first: usize) -> Vec<Vec<String>> {
+        let mut records: Vec<Vec<String>> = Vec::new();
+
+        for x in &self.trajectories {
+            for ((sample, timepoint), (k, n)) in self.samples.iter().zip(x.counts.iter()).filter(|(_, (_, n))| *n > 0) {
+                records.push(vec![
+                    self.series.clone(),
+                    (first + x.column).to_string(),
+                    NUCLEOTIDES[x.allele].to_string(),
+                    sample.clone(),
+                    timepoint.to_string(),
+                    k.to_string(),
+                    n.to_string(),
+                    (*k as f64 / *n as f64).to_string(),
+                ]);
+            }
+        }
+
+        records
+    }
+}
@@ -0,0 +1,234 @@
+use ndarray::{ArrayView, Ix2};
+
+/// A tree of samples, where each child hangs from its parent by a branch of given length.
+#[derive(Debug, Clone)]
+pub struct Tree {
+    pub label: Option<String>,
+    pub children: Vec<(Tree, f64)>,
+}
+
+/// Line segments and leaf positions needed to draw a rectangular dendrogram.
+#[derive(Debug, Clone, Default)]
+pub struct TreeLayout {
+    /// Segments as `[x0, y0, x1, y1]`, x being the distance from the root.
+    pub segments: Vec<[f64; 4]>,
+    /// Leaf labels with their `(x, y)` position.
+    pub leaves: Vec<(String, f64, f64)>,
+}
+
+impl Tree {
+    pub fn leaf(label: &str) -> Tree {
+        Tree {
+            label: Some(label.to_string()),
+            children: Vec::new(),
+        }
+    }
+
+    fn node(children: Vec<(Tree, f64)>) -> Tree {
+        Tree {
+            label: None,
+            children,
+        }
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Rooted, ultrametric tree built by UPGMA (average linkage).
+    pub fn upgma(distances: ArrayView<f64, Ix2>, labels: &[String]) -> Tree {
+        let mut d = distances.to_owned();
+        // cluster, its size and its height
+        let mut clusters: Vec<Option<(Tree, usize, f64)>> = labels.iter()
+            .map(|x| Some((Tree::leaf(x), 1, 0.)))
+            .collect();
+
+        loop {
+            let active = (0..clusters.len()).filter(|i| clusters[*i].is_some()).collect::<Vec<usize>>();
+            if active.len() < 2 {
+                break
+            }
+
+            let mut closest = (active[0], active[1]);
+            for (k, i) in active.iter().enumerate() {
+                for j in active.iter().skip(k + 1) {
+                    if d[[*i, *j]] < d[[closest.0, closest.1]] {
+                        closest = (*i, *j);
+                    }
+                }
+            }
+
+            let (i, j) = closest;
+            let height = d[[i, j]] / 2.;
+            let (ti, ni, hi) = clusters[i].take().unwrap();
+            let (tj, nj, hj) = clusters[j].take().unwrap();
+
+            for k in active.iter().filter(|k| **k != i && **k != j) {
+                let value = (d[[i, *k]] * ni as f64 + d[[j, *k]] * nj as f64) / (ni + nj) as f64;
+                d[[i, *k]] = value;
+                d[[*k, i]] = value;
+            }
+
+            clusters[i] = Some((
+                Tree::node(vec![(ti, (height - hi).max(0.)), (tj, (height - hj).max(0.))]),
+                ni + nj,
+                height,
+            ));
+        }
+
+        clusters.into_iter()
+            .flatten()
+            .next()
+            .map(|(tree, _, _)| tree)
+            .unwrap_or_else(|| Tree::node(Vec::new()))
+    }
+
+    /// Unrooted tree built by neighbour joining (Saitou and Nei), rooted at its last trifurcation.
+    ///
+    /// Negative branch lengths are set to 0.
+    pub fn neighbour_joining(distances: ArrayView<f64, Ix2>, labels: &[String]) -> Tree {
+        let mut d = distances.to_owned();
+        let mut nodes: Vec<Option<Tree>> = labels.iter().map(|x| Some(Tree::leaf(x))).collect();
+
+        loop {
+            let active = (0..nodes.len()).filter(|i| nodes[*i].is_some()).collect::<Vec<usize>>();
+            let n = active.len();
+
+            if n <= 3 {
+                let children = match n {
+                    0 => Vec::new(),
+                    1 => return nodes[active[0]].take().unwrap(),
+                    2 => {
+                        let half = d[[active[0], active[1]]] / 2.;
+
+                        active.iter().map(|i| (nodes[*i].take().unwrap(), half)).collect()
+                    },
+                    _ => {
+                        let (a, b, c) = (active[0], active[1], active[2]);
+                        let lengths = [
+                            (d[[a, b]] + d[[a, c]] - d[[b, c]]) / 2.,
+                            (d[[a, b]] + d[[b, c]] - d[[a, c]]) / 2.,
+                            (d[[a, c]] + d[[b, c]] - d[[a, b]]) / 2.,
+                        ];
+
+                        active.iter()
+                            .zip(lengths.iter())
+                            .map(|(i, l)| (nodes[*i].take().unwrap(), l.max(0.)))
+                            .collect()
+                    },
+                };
+
+                return Tree::node(children);
+            }
+
+            let r = active.iter()
+                .map(|i| active.iter().map(|k| d[[*i, *k]]).sum::<f64>())
+                .collect::<Vec<f64>>();
+
+            let mut best = (0, 1, f64::INFINITY);
+            for a in 0..n {
+                for b in (a + 1)..n {
+                    let q = (n as f64 - 2.) * d[[active[a], active[b]]] - r[a] - r[b];
+
+                    if q < best.2 {
+                        best = (a, b, q);
+                    }
+                }
+            }
+
+            let (a, b, _) = best;
+            let (i, j) = (active[a], active[b]);
+            let li = d[[i, j]] / 2. + (r[a] - r[b]) / (2. * (n as f64 - 2.));
+            let lj = d[[i, j]] - li;
+
+            for k in active.iter().filter(|k| **k != i && **k != j) {
+                let value = (d[[i, *k]] + d[[j, *k]] - d[[i, j]]) / 2.;
+                d[[i, *k]] = value;
+                d[[*k, i]] = value;
+            }
+
+            let (ti, tj) = (nodes[i].take().unwrap(), nodes[j].take().unwrap());
+            nodes[i] = Some(Tree::node(vec![(ti, li.max(0.)), (tj, lj.max(0.))]));
+        }
+    }
+
+    /// Labels of the leaves, from top to bottom.
+    pub fn leaves(&self) -> Vec<&str> {
+        if self.is_leaf() {
+            return self.label.iter().map(|x| x.as_str()).collect();
+        }
+
+        self.children.iter().flat_map(|(child, _)| child.leaves()).collect()
+    }
+
+    fn quote(label: &str) -> String {
+        if label.chars().any(|c| "()[]':;, \t".contains(c)) {
+            format!("'{}'", label.replace('\'', "''"))
+        } else {
+            label.to_string()
+        }
+    }
+
+    fn newick(&self) -> String {
+        let label = self.label.as_deref().map(Tree::quote).unwrap_or_default();
+
+        if self.is_leaf() {
+            return label;
+        }
+
+        let children = self.children.iter()
+            .map(|(child, length)| format!("{}:{}", child.newick(), length))
+            .collect::<Vec<String>>();
+
+        format!("({}){}", children.join(","), label)
+    }
+
+    pub fn to_newick(&self) -> String {
+        self.newick() + ";"
+    }
+
+    fn layout_from(&self, x: f64, next: &mut f64, layout: &mut TreeLayout) -> f64 {
+        if self.is_leaf() {
+            let y = *next;
+            *next += 1.;
+            layout.leaves.push((self.label.clone().unwrap_or_default(), x, y));
+
+            return y;
+        }
+
+        let ys = self.children.iter()
+            .map(|(child, length)| {
+                let y = child.layout_from(x + length, next, layout);
+                layout.segments.push([x, y, x + length, y]);
+
+                y
+            })
+            .collect::<Vec<f64>>();
+
+        let (min, max) = ys.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |acc, y| (acc.0.min(*y), acc.1.max(*y)));
+        layout.segments.push([x, min, x, max]);
+
+        (min + max) / 2.
+    }
+
+    /// Rectangular layout of the tree, with the root at x = 0 and one leaf per unit of y.
+    pub fn layout(&self) -> TreeLayout {
+        let mut layout = TreeLayout::default();
+        self.layout_from(0., &mut 0., &mut layout);
+
+        layout
+    }
+}
+
+/// Distance matrix in relaxed PHYLIP format, where names are separated from distances by a space.
+pub fn to_phylip(distances: ArrayView<f64, Ix2>, labels: &[String]) -> String {
+    let mut phylip = format!("{}\n", labels.len());
+
+    for (label, row) in labels.iter().zip(distances.rows()) {
+        let values = row.iter().map(|x| format!("{:.6}", x)).collect::<Vec<String>>();
+
+        phylip += &format!("{} {}\n", label.replace(char::is_whitespace, "_"), values.join(" "));
+    }
+
+    phylip
+}
@@ -1,5 +1,9 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use ndarray::{Array, ArrayView, Axis, Ix1, Ix2};
+
+use crate::error::{QSAError, Result};
 
 pub fn expand_dir(path: &str, extension: &str) -> Vec<PathBuf> {
     let mut ext_files: Vec<PathBuf> = vec![];
@@ -8,9 +12,9 @@ pub fn expand_dir(path: &str, extension: &str) -> Vec<PathBuf> {
         let entry = entry.unwrap();
         let path = entry.path();
 
-        if extension != "" {
+        if !extension.is_empty() {
             if let Some(ext) = path.extension() {
-                if ext.to_str().unwrap().to_lowercase() != extension.to_string() {
+                if ext.to_str().unwrap().to_lowercase() != extension {
                     continue
                 }
             } else {
@@ -22,4 +26,125 @@ pub fn expand_dir(path: &str, extension: &str) -> Vec<PathBuf> {
     }
 
     ext_files
-}
\ No newline at end of file
+}
+
+/// Maps a nucleotide to its row in the PFM: 0 = A, 1 = C, 2 = G, 3 = T|U, 4 = anything else.
+pub fn nt_index(v: u8) -> u8 {
+    match v % 32 {
+        1 => 0,     3 => 1,     // a|A  c|C
+        7 => 2,     20 => 3,    // g|G  t|T
+        21 => 3,    _ => 4,     // u|U  n|N
+    }
+}
+
+/// Reads the first sequence of a FASTA file, already mapped through `nt_index`.
+pub fn read_fasta<P>(path: P) -> Result<Vec<u8>>
+    where P: AsRef<Path>
+{
+    let content = fs::read_to_string(path).map_err(|_| QSAError::ReferenceNotFound)?;
+
+    let mut sequence: Vec<u8> = Vec::new();
+    let mut started = false;
+    for line in content.lines() {
+        if line.starts_with('>') {
+            if started {
+                break
+            }
+
+            started = true;
+            continue
+        }
+
+        sequence.extend(line.trim().bytes().map(nt_index));
+    }
+
+    Ok(sequence)
+}
+
+/// Eigenvalues and eigenvectors (as columns) of a symmetric matrix, by the cyclic Jacobi method.
+///
+/// Eigenvalues are sorted in decreasing order, together with their eigenvectors.
+pub fn symmetric_eigen(matrix: ArrayView<f64, Ix2>) -> (Array<f64, Ix1>, Array<f64, Ix2>) {
+    let n = matrix.nrows();
+    let mut a = matrix.to_owned();
+    let mut v = Array::<f64, Ix2>::eye(n);
+
+    for _ in 0..100 {
+        let off = (0..n)
+            .flat_map(|i| (0..n).filter(move |j| *j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[[i, j]] * a[[i, j]])
+            .sum::<f64>();
+
+        if off < 1e-22 {
+            break
+        }
+
+        for p in 0..n {
+            for q in (p + 1)..n {
+                if a[[p, q]].abs() < 1e-300 {
+                    continue
+                }
+
+                let theta = (a[[q, q]] - a[[p, p]]) / (2. * a[[p, q]]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+                let t = if theta == 0. { 1. } else { t };
+                let c = 1. / (t * t + 1.).sqrt();
+                let s = t * c;
+
+                for k in 0..n {
+                    let (akp, akq) = (a[[k, p]], a[[k, q]]);
+                    a[[k, p]] = c * akp - s * akq;
+                    a[[k, q]] = s * akp + c * akq;
+                }
+
+                for k in 0..n {
+                    let (apk, aqk) = (a[[p, k]], a[[q, k]]);
+                    a[[p, k]] = c * apk - s * aqk;
+                    a[[q, k]] = s * apk + c * aqk;
+                }
+
+                for k in 0..n {
+                    let (vkp, vkq) = (v[[k, p]], v[[k, q]]);
+                    v[[k, p]] = c * vkp - s * vkq;
+                    v[[k, q]] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    let mut order = (0..n).collect::<Vec<usize>>();
+    order.sort_by(|i, j| a[[*j, *j]].partial_cmp(&a[[*i, *i]]).unwrap_or(std::cmp::Ordering::Equal));
+
+    let values = order.iter().map(|i| a[[*i, *i]]).collect::<Array<f64, Ix1>>();
+    let vectors = v.select(Axis(1), &order);
+
+    (values, vectors)
+}
+
+/// Parses a date as a decimal year, either `YYYY-MM-DD` or a number such as `2019.5`.
+///
+/// Days are checked against the length of their month, leap years included, so that a date such
+/// as `2020-02-31` is an error rather than a day of March.
+pub fn decimal_year(date: &str) -> Result<f64> {
+    if let Ok(year) = date.parse::<f64>() {
+        return if year.is_finite() { Ok(year) } else { Err(QSAError::InvalidDate) };
+    }
+
+    let parts = date.split('-')
+        .map(|x| x.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>()
+        .ok_or(QSAError::InvalidDate)?;
+    let (year, month, day) = match parts.as_slice() {
+        [y, m, d] if (1..=12).contains(m) => (*y, *m, *d),
+        _ => return Err(QSAError::InvalidDate),
+    };
+
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days = [31, if leap { 29 } else { 28 }, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    if day == 0 || day > days[month as usize - 1] {
+        return Err(QSAError::InvalidDate);
+    }
+    let day_of_year = days[..month as usize - 1].iter().sum::<u32>() + day - 1;
+
+    Ok(year as f64 + day_of_year as f64 / if leap { 366. } else { 365. })
+}
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use qsalib::prelude::*;
+
+/// Writes `content` to a file of its own in the temporary directory.
+fn file(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("qsalib-annotation-{}-{}", std::process::id(), name));
+    std::fs::write(&path, content).unwrap();
+
+    path
+}
+
+/// Parses `content` as the annotation file `name`, told apart by its extension.
+fn parse(name: &str, content: &str) -> qsalib::error::Result<Annotation> {
+    let path = file(name, content);
+    let annotation = Annotation::from_path(&path);
+    std::fs::remove_file(path).unwrap();
+
+    annotation
+}
+
+#[test]
+fn gff3_features_and_lookup() {
+    let annotation = parse("features.gff3", "\
+##gff-version 3
+##sequence-region ref 1 100
+ref\tsrc\tregion\t1\t100\t.\t+\t.\tID=ref
+ref\tsrc\tgene\t5\t40\t.\t+\t.\tID=gag;Name=gag
+ref\tsrc\tCDS\t5\t10\t.\t+\t0\tID=cds1;Parent=gag
+ref\tsrc\tCDS\t20\t40\t.\t+\t0\tID=cds1;Parent=gag
+
+ref\tsrc\tCDS\t50\t55\t.\t-\t0\tgene=nef
+ref\tsrc\tmisc_feature\t60\t61\t.\t.\t.\tNote=none
+##FASTA
+>ref
+ACGT
+").unwrap();
+
+    let regions = annotation.get_regions();
+    let names = regions.iter().map(|x| x.name.as_str()).collect::<Vec<&str>>();
+    assert_eq!(names, vec!["gag", "cds1", "nef", "misc_feature:60-61"]);
+
+    // segments sharing an ID are joined, coordinates become 0-based and half-open
+    let cds = &regions[1];
+    assert_eq!(cds.segments, vec![(4, 10), (19, 40)]);
+    assert_eq!((cds.start(), cds.end(), cds.len()), (4, 40, 27));
+
+    let nef = &regions[2];
+    assert_eq!(nef.strand, Strand::Reverse);
+    assert_eq!(nef.positions(), vec![54, 53, 52, 51, 50, 49]);
+    assert_eq!(nef.columns((50, 100)), vec![4, 3, 2, 1, 0]);
+
+    assert_eq!(annotation.coding().len(), 2);
+    assert_eq!(annotation.of_kind("gene").len(), 1);
+
+    let at = |position| annotation.at(position).iter().map(|x| x.name.clone()).collect::<Vec<String>>();
+    assert_eq!(at(4), vec!["gag", "cds1"]);
+    assert_eq!(at(12), vec!["gag"]);
+    assert!(at(45).is_empty());
+}
+
+#[test]
+fn gff3_phase_of_the_first_translated_segment() {
+    let annotation = parse("phase.gff3", "\
+ref\tsrc\tCDS\t30\t40\t.\t+\t2\tID=fw
+ref\tsrc\tCDS\t5\t10\t.\t+\t1\tID=fw
+ref\tsrc\tCDS\t50\t55\t.\t-\t1\tID=rv
+ref\tsrc\tCDS\t60\t70\t.\t-\t2\tID=rv
+ref\tsrc\tgene\t5\t70\t.\t+\t.\tID=gene
+").unwrap();
+
+    let phases = annotation.get_regions().iter().map(|x| x.phase).collect::<Vec<usize>>();
+    assert_eq!(phases, vec![1, 2, 0]);
+}
+
+#[test]
+fn malformed_gff3_lines() {
+    let malformed = [
+        ("fields.gff3", "ref\tsrc\tgene\t5\t40\t.\t+\n"),
+        ("start.gff3", "ref\tsrc\tgene\tfive\t40\t.\t+\t.\tID=a\n"),
+        ("zero.gff3", "ref\tsrc\tgene\t0\t40\t.\t+\t.\tID=a\n"),
+        ("reversed.gff3", "ref\tsrc\tgene\t40\t5\t.\t+\t.\tID=a\n"),
+        ("spaces.gff3", "ref src gene 5 40 . + . ID=a\n"),
+        ("phase.gff3", "ref\tsrc\tCDS\t5\t40\t.\t+\t3\tID=a\n"),
+    ];
+
+    for (name, content) in malformed {
+        assert!(matches!(parse(name, content), Err(QSAError::AnnotationMalformed)), "{}", name);
+    }
+
+    assert!(matches!(Annotation::from_path("missing.gff3"), Err(QSAError::AnnotationNotFound)));
+}
+
+#[test]
+fn bed_lines() {
+    let annotation = parse("regions.bed", "\
+track name=genes
+#comment
+ref\t10\t20
+ref\t30\t60\tpol\t0\t-\t35\t55\t0\t2\t10,10,\t0,20,
+").unwrap();
+
+    let regions = annotation.get_regions();
+    assert_eq!(regions[0].name, "10-20");
+    assert_eq!((regions[0].kind.as_str(), regions[0].segments.clone()), ("region", vec![(10, 20)]));
+
+    // blocks restricted to the thick part make a CDS
+    assert_eq!(regions[1].name, "pol");
+    assert_eq!(regions[1].kind, "CDS");
+    assert_eq!(regions[1].strand, Strand::Reverse);
+    assert_eq!(regions[1].segments, vec![(35, 40), (50, 55)]);
+
+    assert!(matches!(parse("short.bed", "ref\t10\n"), Err(QSAError::AnnotationMalformed)));
+    assert!(matches!(parse("reversed.bed", "ref\t20\t10\n"), Err(QSAError::AnnotationMalformed)));
+    assert!(matches!(parse("blocks.bed", "ref\t30\t60\tpol\t0\t+\t30\t60\t0\t2\t10,x,\t0,20,\n"), Err(QSAError::AnnotationMalformed)));
+}
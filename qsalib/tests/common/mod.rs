@@ -0,0 +1,33 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use bam::{BamWriter, Header, Record, RecordWriter};
+
+/// A directory of its own for each test and run.
+pub fn temp_dir(test: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("qsalib-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&path).unwrap();
+
+    path
+}
+
+/// Writes a BAM file of reads, given as position and sequence, on a 1 kb reference.
+pub fn write_bam(path: &Path, reads: &[(usize, &str)]) {
+    let mut header = Header::new();
+    header.push_line("@SQ\tSN:ref\tLN:1000").unwrap();
+
+    let mut writer = BamWriter::from_path(path, header.clone()).unwrap();
+    for (i, (position, sequence)) in reads.iter().enumerate() {
+        let line = format!(
+            "r{}\t0\tref\t{}\t60\t{}M\t*\t0\t0\t{}\t{}",
+            i, position, sequence.len(), sequence, "I".repeat(sequence.len()),
+        );
+
+        let mut record = Record::new();
+        record.fill_from_sam(&line, &header).unwrap();
+        writer.write(&record).unwrap();
+    }
+    writer.finish().unwrap();
+}
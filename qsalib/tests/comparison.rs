@@ -0,0 +1,45 @@
+use ndarray::Array2;
+
+use qsalib::prelude::*;
+
+fn groups(sizes: &[usize]) -> Vec<(String, Vec<usize>)> {
+    let mut first = 0;
+
+    sizes.iter()
+        .enumerate()
+        .map(|(g, n)| {
+            first += n;
+            (g.to_string(), (first - n..first).collect())
+        })
+        .collect()
+}
+
+#[test]
+fn permanova_separates_distant_groups() {
+    // two tight clusters on a line, far apart
+    let points: [f64; 8] = [0., 0.1, 0.2, 0.3, 10., 10.1, 10.2, 10.3];
+    let distances = Array2::from_shape_fn((8, 8), |(i, j)| (points[i] - points[j]).abs());
+
+    let test = GroupTest::permanova(distances.view(), &groups(&[4, 4]), 999, 1).unwrap();
+
+    assert!(test.effect_size > 0.99);
+    // only the observed split and its mirror are as extreme among the 70 labellings
+    assert!(test.p_value < 0.05);
+    assert_eq!(test.p_value, GroupTest::permanova(distances.view(), &groups(&[4, 4]), 999, 1).unwrap().p_value);
+}
+
+#[test]
+fn mann_whitney_and_kruskal_wallis() {
+    let values = [1., 2., 3., 4., 5., 6.];
+
+    let test = GroupTest::rank_test(&values, "alpha", &groups(&[3, 3]), 0, 1).unwrap();
+    assert_eq!(test.test, "Mann-Whitney");
+    assert_eq!(test.statistic, 0.);
+    assert_eq!(test.effect_size, -1.);
+    assert!(test.p_value.is_nan());
+
+    let test = GroupTest::rank_test(&values, "alpha", &groups(&[2, 2, 2]), 0, 1).unwrap();
+    assert_eq!(test.test, "Kruskal-Wallis");
+    // H = 12 / (6 * 7) * (3² + 7² + 11²) / 2 - 3 * 7
+    assert!((test.statistic - 32. / 7.).abs() < 1e-9);
+}
@@ -0,0 +1,44 @@
+use ndarray::{Array, Ix2};
+
+use qsalib::prelude::*;
+
+#[test]
+fn coupled_positions_across_mates() {
+    // columns 1, 3 and 7 carry the same base, column 7 on the other mate; column 5 is independent
+    let mut reads: Vec<AlignedRead> = Vec::new();
+    for i in 0..40 {
+        let (coupled, free) = (if i % 2 == 0 { 0 } else { 2 }, if (i / 2) % 2 == 0 { 1 } else { 3 });
+
+        reads.push(AlignedRead { name: format!("read{}", i), offset: 0, bases: vec![0, coupled, 0, coupled] });
+        reads.push(AlignedRead { name: format!("read{}", i), offset: 4, bases: vec![0, free, 0, coupled] });
+    }
+
+    let mut pfm = Array::<u64, Ix2>::zeros((4, 8));
+    for read in &reads {
+        for col in read.offset..read.end() {
+            pfm[[read.base_at(col).unwrap() as usize, col]] += 1;
+        }
+    }
+
+    let covariation = Covariation::new(reads.into_iter(), pfm.view(), 0.05, 10, 99, 1);
+    assert_eq!(covariation.get_columns(), &[1, 3, 5, 7]);
+    assert_eq!(covariation.get_couplings().len(), 6);
+
+    let ranked = covariation.ranked();
+    let top = ranked.iter().take(3).map(|x| (x.a, x.b)).collect::<Vec<(usize, usize)>>();
+    assert_eq!(top, vec![(1, 3), (1, 7), (3, 7)]);
+
+    for x in &ranked[..3] {
+        assert!((x.mi - 1.).abs() < 1e-9);
+        assert!((x.apc - 1. / 9.).abs() < 1e-9);
+        assert!((x.p_value - 0.01).abs() < 1e-9);
+        assert_eq!(x.reads(), 40);
+    }
+    for x in &ranked[3..] {
+        assert!(x.mi.abs() < 1e-9);
+        assert!(x.p_value > 0.5);
+    }
+
+    let map = covariation.contact_map();
+    assert!((map[[0, 3]] - map[[3, 0]]).abs() < 1e-12);
+}
@@ -0,0 +1,25 @@
+use qsalib::stats::{benjamini_hochberg, g_test, mann_whitney};
+
+#[test]
+fn benjamini_hochberg_is_monotone() {
+    let q = benjamini_hochberg(&[0.01, 0.04, f64::NAN, 0.03, 0.5]);
+
+    assert!((q[0] - 0.04).abs() < 1e-12);
+    assert!((q[1] - 0.16 / 3.).abs() < 1e-12);
+    assert!(q[2].is_nan());
+    assert!((q[3] - 0.16 / 3.).abs() < 1e-12);
+    assert!((q[4] - 0.5).abs() < 1e-12);
+}
+
+#[test]
+fn g_test_and_mann_whitney() {
+    let (g, p) = g_test(&[vec![50, 50, 0, 0], vec![50, 50, 0, 0]]);
+    assert!(g.abs() < 1e-12 && (p - 1.).abs() < 1e-12);
+
+    let (_, p) = g_test(&[vec![90, 10, 0, 0], vec![10, 90, 0, 0]]);
+    assert!(p < 1e-20);
+
+    let (u, p) = mann_whitney(&[1., 2., 3., 4., 5.], &[6., 7., 8., 9., 10.]);
+    assert_eq!(u, 0.);
+    assert!(p < 0.05);
+}
@@ -0,0 +1,68 @@
+use ndarray::arr2;
+
+use qsalib::prelude::*;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-6
+}
+
+#[test]
+fn known_values_of_a_fixed_difference() {
+    // A in one population and C in the other
+    let a = arr2(&[[10], [0], [0], [0]]);
+    let b = arr2(&[[0], [10], [0], [0]]);
+    let differentiation = Differentiation::new(a.view(), b.view());
+
+    assert!(close(differentiation.fst().unwrap(), 1.));
+    assert!(close(differentiation.gst().unwrap(), 1.));
+    assert!(close(differentiation.jost_d().unwrap(), 1.));
+}
+
+#[test]
+fn known_values_of_shifted_frequencies() {
+    // 80/20 against 20/80: Hs = 0.32, Ht = 0.5, within = 0.32·10/9 and between = 0.68
+    let a = arr2(&[[8], [2], [0], [0]]);
+    let b = arr2(&[[2], [8], [0], [0]]);
+    let differentiation = Differentiation::new(a.view(), b.view());
+
+    assert!(close(differentiation.fst().unwrap(), 1. - 0.32 * 10. / 9. / 0.68));
+    assert!(close(differentiation.gst().unwrap(), 0.36));
+    assert!(close(differentiation.jost_d().unwrap(), 2. * 0.18 / 0.68));
+}
+
+#[test]
+fn identical_populations_are_not_differentiated() {
+    let a = arr2(&[[5], [5], [0], [0]]);
+    let differentiation = Differentiation::new(a.view(), a.view());
+
+    // Hudson's estimator is unbiased, so slightly negative without differentiation
+    assert!(close(differentiation.fst().unwrap(), -1. / 9.));
+    assert!(close(differentiation.gst().unwrap(), 0.));
+    assert!(close(differentiation.jost_d().unwrap(), 0.));
+}
+
+#[test]
+fn genome_wide_values_combine_the_shared_positions() {
+    // the fixed and the shifted positions above, and one covered by a single base in `b`
+    let a = arr2(&[[10, 8, 3], [0, 2, 0], [0, 0, 0], [0, 0, 0]]);
+    let b = arr2(&[[0, 2, 1], [10, 8, 0], [0, 0, 0], [0, 0, 0]]);
+    let differentiation = Differentiation::new(a.view(), b.view());
+
+    assert_eq!(differentiation.shared_positions(), 2);
+    assert!(differentiation.get_fst()[2].is_nan());
+    assert!(close(differentiation.fst().unwrap(), 1. - 0.32 * 10. / 9. / 1.68));
+    assert!(close(differentiation.gst().unwrap(), 0.68));
+    assert!(close(differentiation.jost_d().unwrap(), 2. * 0.34 / 0.84));
+}
+
+#[test]
+fn disjoint_coverage_is_an_error() {
+    let a = arr2(&[[10, 0], [0, 0], [0, 0], [0, 0]]);
+    let b = arr2(&[[0, 0], [0, 10], [0, 0], [0, 0]]);
+    let differentiation = Differentiation::new(a.view(), b.view());
+
+    assert_eq!(differentiation.shared_positions(), 0);
+    assert!(matches!(differentiation.fst(), Err(QSAError::NoSharedPositions)));
+    assert!(matches!(differentiation.gst(), Err(QSAError::NoSharedPositions)));
+    assert!(matches!(differentiation.jost_d(), Err(QSAError::NoSharedPositions)));
+}
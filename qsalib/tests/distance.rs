@@ -0,0 +1,74 @@
+use qsalib::prelude::*;
+use qsalib::distance::{AlphaDifference, BrayCurtis, Hellinger, JensenShannon, Nei};
+
+/// Matrices of `n` reads of `bases` starting at column `offset` of a 6-column range.
+fn sample(reads: &[(usize, Vec<u8>, usize)]) -> Matrices {
+    let reads = reads.iter()
+        .flat_map(|(offset, bases, n)| {
+            (0..*n).map(move |i| AlignedRead { name: format!("read{}", i), offset: *offset, bases: bases.clone() })
+        });
+
+    Matrices::new(reads, (0, 6)).unwrap()
+}
+
+fn metrics() -> Vec<Box<dyn Distance>> {
+    vec![Box::new(AlphaDifference), Box::new(JensenShannon), Box::new(Hellinger), Box::new(BrayCurtis), Box::new(Nei)]
+}
+
+#[test]
+fn identical_samples_are_at_zero() {
+    let a = sample(&[(0, vec![0, 1, 2, 3, 0, 1], 10), (0, vec![1, 1, 2, 3, 0, 0], 10)]);
+
+    for metric in metrics() {
+        let d = metric.distance(&a, &a).unwrap();
+        assert!(d.abs() < 1e-12, "{} is {}", metric.name(), d);
+    }
+}
+
+#[test]
+fn known_distances() {
+    // half A and half C against only A at every position
+    let a = sample(&[(0, vec![0; 6], 10), (0, vec![1; 6], 10)]);
+    let b = sample(&[(0, vec![0; 6], 20)]);
+
+    let jsd = 0.5 * (0.5 * (0.5_f64 / 0.75).log2() + 0.5 * 2_f64.log2()) + 0.5 * (1. / 0.75_f64).log2();
+    assert!((JensenShannon.distance(&a, &b).unwrap() - jsd).abs() < 1e-12);
+    assert!((Hellinger.distance(&a, &b).unwrap() - (1. - 0.5_f64.sqrt()).sqrt()).abs() < 1e-12);
+    assert!((BrayCurtis.distance(&a, &b).unwrap() - 0.5).abs() < 1e-12);
+    assert!((AlphaDifference.distance(&a, &b).unwrap() - 0.5).abs() < 1e-12);
+    let nei = Nei.distance(&a, &b).unwrap();
+    assert!((nei - 0.5_f64.sqrt().ln().abs()).abs() < 1e-12, "{}", nei);
+}
+
+#[test]
+fn disjoint_coverage_has_no_shared_positions() {
+    let a = sample(&[(0, vec![0, 1, 2], 10)]);
+    let b = sample(&[(3, vec![0, 1, 2], 10)]);
+
+    for metric in [Box::new(JensenShannon) as Box<dyn Distance>, Box::new(Hellinger), Box::new(Nei)] {
+        assert!(matches!(metric.distance(&a, &b), Err(QSAError::NoSharedPositions)), "{}", metric.name());
+    }
+
+    assert!((BrayCurtis.distance(&a, &b).unwrap() - 1.).abs() < 1e-12);
+    assert!(AlphaDifference.distance(&a, &b).unwrap().abs() < 1e-12);
+}
+
+#[test]
+fn no_shared_base_is_the_furthest() {
+    let a = sample(&[(0, vec![0; 6], 10)]);
+    let b = sample(&[(0, vec![1; 6], 10)]);
+
+    assert!((JensenShannon.distance(&a, &b).unwrap() - 1.).abs() < 1e-12);
+    assert!((Hellinger.distance(&a, &b).unwrap() - 1.).abs() < 1e-12);
+    assert!((BrayCurtis.distance(&a, &b).unwrap() - 1.).abs() < 1e-12);
+    assert!(matches!(Nei.distance(&a, &b), Err(QSAError::NoSharedBases)));
+}
+
+#[test]
+fn metrics_by_name() {
+    for name in Metric::NAMES {
+        assert_eq!(name.parse::<Metric>().unwrap().to_string(), name);
+    }
+
+    assert!("euclidean".parse::<Metric>().is_err());
+}
@@ -0,0 +1,42 @@
+use qsalib::haplotype::local_haplotypes;
+use qsalib::prelude::*;
+
+fn read(offset: usize, bases: &str) -> AlignedRead {
+    AlignedRead {
+        name: String::new(),
+        offset,
+        bases: bases.bytes().map(|x| "ACGTN".bytes().position(|y| x == y).unwrap() as u8).collect(),
+    }
+}
+
+#[test]
+fn haplotypes_are_collapsed_and_corrected() {
+    let mut reads = Vec::new();
+    reads.extend((0..60).map(|_| read(0, "ACGTACGT")));
+    reads.extend((0..38).map(|_| read(0, "ACCTACGA")));
+    // an error of the major haplotype and a read not covering the window
+    reads.push(read(0, "ACGTACGG"));
+    reads.push(read(0, "ACGTANGT"));
+    reads.push(read(2, "GTACGT"));
+
+    let windows = local_haplotypes(reads.into_iter(), 8, 8, 4, 0.05, 1);
+    assert_eq!(windows.len(), 1);
+
+    let window = &windows[0];
+    assert_eq!(window.reads, 100);
+
+    let haplotypes = window.get_haplotypes();
+    assert_eq!(haplotypes.len(), 2);
+    assert_eq!(haplotypes[0].sequence(), "ACGTACGT");
+    assert_eq!(haplotypes[0].count, 62);
+    assert!((haplotypes[1].frequency - 0.38).abs() < 1e-12);
+
+    assert!(window.to_fasta("s", 101).starts_with(">s_101-108_1 freq=0.620000 count=62\nACGTACGT\n"));
+
+    let complexity = window.complexity().unwrap();
+    let entropy = -(0.62_f64 * 0.62_f64.ln() + 0.38 * 0.38_f64.ln()) / 100_f64.ln();
+    assert_eq!(complexity.haplotypes, 2.);
+    assert!((complexity.entropy - entropy).abs() < 1e-12);
+    assert!((complexity.mutation_frequency - 0.38 * 2. / 8.).abs() < 1e-12);
+    assert!((complexity.mfe - 1. / 8.).abs() < 1e-12);
+}
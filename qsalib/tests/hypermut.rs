@@ -0,0 +1,49 @@
+mod common;
+
+use ndarray::Array;
+
+use qsalib::prelude::*;
+
+use common::{temp_dir, write_bam};
+
+#[test]
+fn hypermutated_read_is_flagged() {
+    // GAA is a GRD context, GCC a control one
+    let reference = Array::from_vec([2, 0, 0, 2, 1, 1].repeat(10));
+    let bases = [0, 0, 0, 2, 1, 1].repeat(10);
+
+    let read = AlignedRead { name: "read".to_string(), offset: 0, bases };
+    let hypermutation = ReadHypermutation::new(0, &read, reference.view());
+
+    assert_eq!(hypermutation.hyper, (10, 10));
+    assert_eq!(hypermutation.control, (0, 10));
+    assert_eq!(hypermutation.mismatches[2][0], 10);
+    assert!((hypermutation.p_value - 1. / 184756.).abs() < 1e-12);
+}
+
+#[test]
+fn hypermutated_reads_are_flagged_and_excluded_with_or_without_reference() {
+    let path = temp_dir("hypermut");
+    let bam = path.join("s.bam");
+    let (reference, hypermutated) = ("GAAGCC".repeat(10), "AAAGCC".repeat(10));
+    write_bam(&bam, &[(1, reference.as_str()), (1, hypermutated.as_str()), (1, reference.as_str())]);
+
+    let sequence = Array::from_vec([2, 0, 0, 2, 1, 1].repeat(10));
+    for reference in [Some(sequence), None] {
+        let kept = Bam::open(&bam, (0, 60), 0., reference.clone(), Some((0.05, false))).unwrap();
+        let flagged = kept.get_hypermutation().unwrap();
+        assert_eq!(flagged.total_reads(), 3);
+        assert_eq!(flagged.get_hypermutated().len(), 1);
+        assert_eq!(flagged.get_hypermutated()[0].index, 1);
+        assert_eq!(kept.matrices.get_pfm()[[0, 0]], 1);
+
+        let excluded = Bam::open(&bam, (0, 60), 0., reference, Some((0.05, true))).unwrap();
+        assert_eq!(excluded.get_hypermutation().unwrap().get_hypermutated().len(), 1);
+        assert_eq!(excluded.matrices.get_pfm()[[0, 0]], 0);
+        assert_eq!(excluded.matrices.get_pfm()[[2, 0]], 2);
+    }
+
+    assert!(Bam::new(&bam, (0, 60), 0.).unwrap().get_hypermutation().is_none());
+
+    std::fs::remove_dir_all(path).unwrap();
+}
@@ -0,0 +1,50 @@
+use ndarray::{Array, Ix2};
+
+use qsalib::prelude::*;
+
+fn pfm(reads: &[AlignedRead], ncols: usize) -> Array<u64, Ix2> {
+    let mut pfm = Array::<u64, Ix2>::zeros((4, ncols));
+    for read in reads {
+        for col in read.offset..read.end() {
+            if let Some(base) = read.base_at(col) {
+                pfm[[base as usize, col]] += 1;
+            }
+        }
+    }
+
+    pfm
+}
+
+#[test]
+fn linked_and_unlinked_sites() {
+    // sites 1 and 3 are in complete linkage, site 5 is independent of both
+    let reads = (0..40)
+        .map(|i| {
+            let linked = if i % 2 == 0 { 0 } else { 2 };
+            let free = if (i / 2) % 2 == 0 { 1 } else { 3 };
+
+            AlignedRead { name: format!("read{}", i), offset: 0, bases: vec![0, linked, 0, linked, 0, free] }
+        })
+        .collect::<Vec<AlignedRead>>();
+
+    let pfm = pfm(&reads, 6);
+    let linkage = Linkage::new(reads.into_iter(), pfm.view(), 0.05, 10);
+
+    let sites = linkage.get_sites().iter().map(|x| x.column).collect::<Vec<usize>>();
+    assert_eq!(sites, vec![1, 3, 5]);
+
+    let pairs = linkage.get_pairs();
+    assert_eq!(pairs.len(), 3);
+
+    let (a, b) = (&pairs[0], &pairs[1]);
+    assert_eq!((a.a, a.b), (0, 1));
+    assert_eq!(a.counts, [20, 0, 0, 20]);
+    assert!((a.d_prime - 1.).abs() < 1e-9);
+    assert!((a.r_squared - 1.).abs() < 1e-9);
+    assert!(a.p_value < 1e-6);
+
+    assert_eq!(b.counts, [10, 10, 10, 10]);
+    assert!(b.d.abs() < 1e-9);
+    assert!(b.r_squared.abs() < 1e-9);
+    assert!((b.p_value - 1.).abs() < 1e-9);
+}
@@ -0,0 +1,31 @@
+use ndarray::{arr1, arr2};
+
+use qsalib::prelude::*;
+use qsalib::logo::top_windows;
+
+#[test]
+fn logo_heights_and_windows() {
+    // a conserved A, an even A/G split, and a column without reads
+    let pfm = arr2(&[
+        [100, 50, 0],
+        [0, 0, 0],
+        [0, 50, 0],
+        [0, 0, 0],
+    ]);
+    let ppm = pfm.map(|x| *x as f64 / 100.);
+
+    let frequency = Logo::new(pfm.view(), ppm.view(), (0, 3), 11, LogoKind::Frequency);
+    assert_eq!(frequency.columns[1].position, 12);
+    assert_eq!(frequency.columns[1].heights, [0.5, 0., 0.5, 0.]);
+
+    let information = Logo::new(pfm.view(), ppm.view(), (0, 3), 11, LogoKind::Information);
+    let correction = 3. / (2. * 2_f64.ln() * 100.);
+    assert!((information.columns[0].heights[0] - (2. - correction)).abs() < 1e-12);
+    assert!((information.columns[1].heights[2] - 0.5 * (1. - correction)).abs() < 1e-12);
+    assert_eq!(information.columns[2].heights, [0.; 4]);
+    assert_eq!(information.columns[1].stacked().iter().map(|x| x.0).collect::<Vec<u8>>(), vec![2, 0]);
+
+    let efficiency = arr1(&[0., 0.9, 0.8, 0., 0., 0.5, 0.6, f64::NAN]);
+    assert_eq!(top_windows(efficiency.view(), 2, 2), vec![(1, 3), (5, 7)]);
+    assert_eq!(top_windows(efficiency.view(), 9, 1), vec![]);
+}
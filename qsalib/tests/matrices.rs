@@ -0,0 +1,16 @@
+use qsalib::prelude::*;
+
+#[test]
+fn absent_bases_add_no_entropy() {
+    // a conserved column, a column with two bases, one with all four and an uncovered one
+    let reads = (0..4u8).map(|i| AlignedRead { name: format!("read{}", i), offset: 0, bases: vec![0, i % 2, i] });
+    let matrices = Matrices::new(reads, (0, 4)).unwrap();
+
+    let efficiency = matrices.get_efficiency();
+    assert_eq!(efficiency[0], 0.);
+    assert!((efficiency[1] - 0.5).abs() < 1e-12);
+    assert!((efficiency[2] - 1.).abs() < 1e-12);
+    assert_eq!(efficiency[3], 0.);
+
+    assert!((matrices.alpha_diversity() - 1.5 / 4.).abs() < 1e-12);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use qsalib::prelude::*;
+
+use common::temp_dir;
+
+fn metadata(test: &str, dates: &[&str]) -> qsalib::error::Result<Metadata> {
+    let path = temp_dir(test);
+    let rows = dates.iter().enumerate().map(|(i, x)| format!("s{},{}\n", i, x)).collect::<String>();
+    std::fs::write(path.join("metadata.csv"), format!("sample,date\n{}", rows)).unwrap();
+
+    let metadata = Metadata::from_path(path.join("metadata.csv"));
+    std::fs::remove_dir_all(path).unwrap();
+
+    metadata
+}
+
+#[test]
+fn dates_are_decimal_years() {
+    let metadata = metadata("metadata-dates", &["2020-01-01", "2020-12-31", "2019-07-02", "2021.25", ""]).unwrap();
+
+    assert_eq!(metadata.date("s0"), Some(2020.));
+    assert!((metadata.date("s1").unwrap() - (2020. + 365. / 366.)).abs() < 1e-12);
+    assert!((metadata.date("s2").unwrap() - (2019. + 182. / 365.)).abs() < 1e-12);
+    assert_eq!(metadata.date("s3"), Some(2021.25));
+    assert_eq!(metadata.date("s4"), None);
+}
+
+#[test]
+fn leap_days_are_only_valid_in_leap_years() {
+    assert!(metadata("metadata-leap", &["2020-02-29", "2000-02-29"]).is_ok());
+
+    for date in ["2019-02-29", "1900-02-29"] {
+        assert!(matches!(metadata("metadata-not-leap", &[date]), Err(QSAError::InvalidDate)));
+    }
+}
+
+#[test]
+fn impossible_dates_are_an_error() {
+    for date in ["2020-02-31", "2020-04-31", "2020-13-01", "2020-01-00", "2020-01", "yesterday", "NaN", "inf"] {
+        assert!(matches!(metadata("metadata-invalid", &[date]), Err(QSAError::InvalidDate)), "{}", date);
+    }
+}
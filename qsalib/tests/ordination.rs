@@ -0,0 +1,21 @@
+use ndarray::arr2;
+
+use qsalib::prelude::*;
+
+#[test]
+fn pcoa_of_collinear_points() {
+    let distances = arr2(&[
+        [0., 1., 2.],
+        [1., 0., 1.],
+        [2., 1., 0.],
+    ]);
+
+    let ordination = Ordination::pcoa(distances.view());
+    let coordinates = ordination.get_coordinates();
+
+    assert_eq!(coordinates.ncols(), 1);
+    assert!((ordination.get_eigenvalues()[0] - 2.).abs() < 1e-9);
+    assert!((ordination.proportion_explained()[0] - 1.).abs() < 1e-9);
+    assert!(((coordinates[[0, 0]] - coordinates[[2, 0]]).abs() - 2.).abs() < 1e-9);
+    assert!(coordinates[[1, 0]].abs() < 1e-9);
+}
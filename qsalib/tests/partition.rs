@@ -0,0 +1,70 @@
+use ndarray::{arr1, arr2};
+
+use qsalib::prelude::*;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-6
+}
+
+#[test]
+fn hill_numbers_of_known_orders() {
+    let p = arr1(&[0.5, 0.25, 0.25, 0.]);
+
+    assert!(close(hill_number(p.view(), 0.), 3.));
+    assert!(close(hill_number(p.view(), 1.), (1.5 * 2_f64.ln()).exp()));
+    assert!(close(hill_number(p.view(), 2.), 1. / 0.375));
+}
+
+#[test]
+fn samples_without_a_base_in_common_double_gamma() {
+    // A in one sample and C in the other: α = 1 and γ = 2 whatever the order
+    let a = arr2(&[[1.], [0.], [0.], [0.]]);
+    let b = arr2(&[[0.], [1.], [0.], [0.]]);
+
+    for q in [0., 1., 2.] {
+        let partition = Partition::new(&[a.view(), b.view()], q);
+
+        assert!(close(partition.alpha().unwrap(), 1.));
+        assert!(close(partition.gamma().unwrap(), 2.));
+        assert!(close(partition.beta().unwrap(), 2.));
+        assert!(close(partition.additive_beta().unwrap(), 1.));
+    }
+}
+
+#[test]
+fn identical_samples_have_a_beta_of_one() {
+    let a = arr2(&[[0.5], [0.5], [0.], [0.]]);
+    let partition = Partition::new(&[a.view(), a.view()], 1.);
+
+    assert!(close(partition.alpha().unwrap(), 2.));
+    assert!(close(partition.beta().unwrap(), 1.));
+    assert!(close(partition.additive_beta().unwrap(), 0.));
+}
+
+#[test]
+fn known_partition_of_order_two() {
+    // Σp² = 0.5 and 1, so α = 1 / 0.75; pooled 0.75/0.25, so γ = 1 / 0.625
+    let a = arr2(&[[0.5, 0.5], [0.5, 0.], [0., 0.], [0., 0.]]);
+    let b = arr2(&[[1., 0.], [0., 0.], [0., 0.], [0., 0.]]);
+    let partition = Partition::new(&[a.view(), b.view()], 2.);
+
+    assert!(close(partition.get_alpha()[0], 1. / 0.75));
+    assert!(close(partition.get_gamma()[0], 1.6));
+    assert!(close(partition.get_beta()[0], 1.2));
+
+    // the second position is not covered by `b`, so it is left out of the genome-wide values
+    assert!(partition.get_alpha()[1].is_nan());
+    assert_eq!(partition.shared_positions(), 1);
+    assert!(close(partition.beta().unwrap(), 1.2));
+}
+
+#[test]
+fn disjoint_coverage_is_an_error() {
+    let a = arr2(&[[1., 0.], [0., 0.], [0., 0.], [0., 0.]]);
+    let b = arr2(&[[0., 0.], [0., 1.], [0., 0.], [0., 0.]]);
+    let partition = Partition::new(&[a.view(), b.view()], 1.);
+
+    assert_eq!(partition.shared_positions(), 0);
+    assert!(matches!(partition.alpha(), Err(QSAError::NoSharedPositions)));
+    assert!(matches!(partition.beta(), Err(QSAError::NoSharedPositions)));
+}
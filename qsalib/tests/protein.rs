@@ -0,0 +1,30 @@
+use qsalib::prelude::*;
+
+#[test]
+fn codons_follow_the_strand() {
+    // ATGAAA
+    let read = AlignedRead { name: "read".to_string(), offset: 0, bases: vec![0, 3, 2, 0, 0, 0] };
+
+    let forward = Region { name: "fw".to_string(), kind: "CDS".to_string(), strand: Strand::Forward, segments: vec![(0, 6)], phase: 0 };
+    let reverse = Region { name: "rv".to_string(), kind: "CDS".to_string(), strand: Strand::Reverse, segments: vec![(0, 6)], phase: 0 };
+
+    let proteins = ProteinMatrices::from_reads(vec![read].into_iter(), &[&forward, &reverse], (0, 6));
+
+    assert_eq!(proteins[0].consensus(), "MK");
+    assert_eq!(proteins[1].consensus(), "FH");
+    assert_eq!(proteins[0].alpha_diversity(), 0.);
+}
+
+#[test]
+fn codons_start_after_the_phase() {
+    // CATGAAA
+    let read = AlignedRead { name: "read".to_string(), offset: 0, bases: vec![1, 0, 3, 2, 0, 0, 0] };
+
+    let forward = Region { name: "fw".to_string(), kind: "CDS".to_string(), strand: Strand::Forward, segments: vec![(0, 7)], phase: 1 };
+    let reverse = Region { name: "rv".to_string(), kind: "CDS".to_string(), strand: Strand::Reverse, segments: vec![(0, 7)], phase: 1 };
+
+    let proteins = ProteinMatrices::from_reads(vec![read].into_iter(), &[&forward, &reverse], (0, 7));
+
+    assert_eq!(proteins[0].consensus(), "MK");
+    assert_eq!(proteins[1].consensus(), "FM");
+}
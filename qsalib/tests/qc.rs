@@ -0,0 +1,70 @@
+use qsalib::prelude::*;
+
+fn read(offset: usize, bases: Vec<u8>) -> AlignedRead {
+    AlignedRead { name: "read".to_string(), offset, bases }
+}
+
+#[test]
+fn read_counts() {
+    let counts = ReadCounts { processed: 10, unmapped: 2, outside_range: 3 };
+    assert_eq!(counts.kept(), 5);
+
+    // counts set by hand cannot underflow
+    assert_eq!(ReadCounts { processed: 1, unmapped: 2, outside_range: 0 }.kept(), 0);
+}
+
+#[test]
+fn depth_and_breadth() {
+    // unknown bases (4) add no depth
+    let reads = vec![read(0, vec![0, 1, 2, 3]), read(1, vec![0, 4, 2]), read(1, vec![0])];
+    let matrices = Matrices::new(reads.into_iter(), (0, 5)).unwrap();
+
+    assert_eq!(matrices.get_depth().to_vec(), vec![1, 3, 1, 2, 0]);
+    assert_eq!(matrices.get_coverage().to_vec(), vec![1. / 3., 1., 1. / 3., 2. / 3., 0.]);
+    assert!((matrices.breadth(1) - 0.8).abs() < 1e-12);
+    assert!((matrices.breadth(2) - 0.4).abs() < 1e-12);
+    assert!((matrices.breadth(3) - 0.2).abs() < 1e-12);
+    assert_eq!(matrices.breadth(4), 0.);
+
+    let empty = Matrices::new(std::iter::empty(), (0, 0)).unwrap();
+    assert_eq!(empty.breadth(1), 0.);
+}
+
+#[test]
+fn qc_table() {
+    let path = std::env::temp_dir().join(format!("qsalib-qc-test-{}", std::process::id()));
+    std::fs::create_dir_all(&path).unwrap();
+
+    let summary = SampleSummary {
+        sample: "s1".to_string(),
+        read_counts: ReadCounts { processed: 10, unmapped: 2, outside_range: 3 },
+        hypermutated: 1,
+        excluded: 1,
+        positions: 4,
+        mean_depth: 2.5,
+        median_depth: 2.,
+        breadth: [1., 0.5, 0.],
+        uncovered: 0,
+        masked: 1,
+        alpha: 0.1,
+        alpha_ci: (0.05, 0.15),
+    };
+    assert_eq!(summary.analysed_reads(), 4);
+
+    // more excluded reads than kept ones cannot underflow
+    let odd = SampleSummary { sample: "s2".to_string(), excluded: 9, ..summary.clone() };
+    assert_eq!(odd.analysed_reads(), 0);
+
+    BamData::default().qc_to_csv(&path, "qc.csv", &[summary, odd]);
+    let qc = std::fs::read_to_string(path.join("qc.csv")).unwrap();
+    std::fs::remove_dir_all(&path).unwrap();
+
+    let lines = qc.lines().collect::<Vec<&str>>();
+    assert_eq!(
+        lines[0],
+        "sample,processed reads,unmapped reads,reads outside range,hypermutated reads,excluded reads,analysed reads,\
+         mean depth,median depth,breadth 1x,breadth 100x,breadth 1000x,positions,masked positions",
+    );
+    assert_eq!(lines[1], "s1,10,2,3,1,1,4,2.5,2,1,0.5,0,4,1");
+    assert_eq!(lines[2], "s2,10,2,3,1,9,0,2.5,2,1,0.5,0,4,1");
+}
@@ -0,0 +1,75 @@
+use qsalib::prelude::*;
+use qsalib::stats::{linear_regression, quantile};
+
+/// 100 reads; at each column the first `k` reads carry `base` and the others `background`
+/// (4 leaves them uncovered).
+fn sample(columns: &[(u8, usize, u8)]) -> Matrices {
+    let reads = (0..100).map(|i| AlignedRead {
+        name: format!("read{}", i),
+        offset: 0,
+        bases: columns.iter().map(|(base, k, background)| if i < *k { *base } else { *background }).collect(),
+    });
+
+    Matrices::new(reads, (0, columns.len() as i32)).unwrap()
+}
+
+#[test]
+fn regression_of_a_line() {
+    let (slope, intercept, r_squared) = linear_regression(&[0., 1., 2., 3.], &[1., 3., 5., 7.]);
+
+    assert!((slope - 2.).abs() < 1e-12);
+    assert!((intercept - 1.).abs() < 1e-12);
+    assert!((r_squared - 1.).abs() < 1e-12);
+}
+
+#[test]
+fn quantiles_interpolate() {
+    let values = [3., 1., f64::NAN, 2., 4.];
+
+    assert_eq!(quantile(&values, 0.), 1.);
+    assert_eq!(quantile(&values, 0.5), 2.5);
+    assert_eq!(quantile(&values, 1.), 4.);
+}
+
+#[test]
+fn divergence_grows_linearly() {
+    // a G rising by 10% a year at the first column, a conserved A at the second, and a third column
+    // left uncovered in the second sample
+    let first = sample(&[(2, 0, 0), (0, 100, 0), (3, 0, 0)]);
+    let second = sample(&[(2, 10, 0), (0, 100, 0), (3, 0, 4)]);
+    let third = sample(&[(2, 20, 0), (0, 100, 0), (3, 100, 0)]);
+    let samples: Vec<TimedSample> = vec![
+        ("third".to_string(), 2012., &third),
+        ("first".to_string(), 2010., &first),
+        ("second".to_string(), 2011., &second),
+    ];
+
+    let rate = EvolutionaryRate::new("patient", &samples, 200, 7).unwrap();
+
+    assert_eq!(rate.sites(), 2);
+    assert_eq!(rate.samples, vec![("first".to_string(), 0.), ("second".to_string(), 1.), ("third".to_string(), 2.)]);
+
+    // mean divergence 0, 0.05, 0.1; mean diversity 0, (1 - 0.81 - 0.01) / 2, (1 - 0.64 - 0.04) / 2
+    assert!((rate.divergence_rate.rate - 0.05).abs() < 1e-12);
+    assert!((rate.diversity_rate.rate - 0.08).abs() < 1e-12);
+
+    for x in &[rate.divergence_rate, rate.diversity_rate] {
+        assert!(x.ci.0 <= x.rate && x.rate <= x.ci.1);
+    }
+
+    let again = EvolutionaryRate::new("patient", &samples, 200, 7).unwrap();
+    assert_eq!(again.divergence_rate.ci, rate.divergence_rate.ci);
+    assert_eq!(again.diversity_rate.ci, rate.diversity_rate.ci);
+}
+
+#[test]
+fn rate_needs_dates_and_shared_sites() {
+    let first = sample(&[(0, 100, 0), (2, 0, 4)]);
+    let second = sample(&[(0, 0, 4), (2, 20, 0)]);
+
+    let same_date: Vec<TimedSample> = vec![("first".to_string(), 2010., &first), ("second".to_string(), 2010., &first)];
+    assert!(EvolutionaryRate::new("patient", &same_date, 10, 7).is_none());
+
+    let disjoint: Vec<TimedSample> = vec![("first".to_string(), 2010., &first), ("second".to_string(), 2011., &second)];
+    assert!(EvolutionaryRate::new("patient", &disjoint, 10, 7).is_none());
+}
@@ -0,0 +1,46 @@
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use qsalib::prelude::*;
+
+#[test]
+fn em_recovers_two_haplotypes() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let major = (0..200).map(|_| rng.gen_range(0..4)).collect::<Vec<u8>>();
+    let mut minor = major.clone();
+    for i in (0..200).step_by(20) {
+        minor[i] = (minor[i] + 1) % 4;
+    }
+
+    // 70/30 mixture of reads with a 2% error rate, overhanging the region by 5 bases
+    let reads = (0..500)
+        .map(|i| {
+            let source = if i % 10 < 7 { &major } else { &minor };
+            let mut bases = vec![0; 5];
+            bases.extend(source.iter().map(|b| if rng.gen::<f64>() < 0.02 { (b + rng.gen_range(1..4)) % 4 } else { *b }));
+            bases.extend(vec![0; 5]);
+
+            AlignedRead { name: format!("read{}", i), offset: 0, bases }
+        })
+        .collect::<Vec<AlignedRead>>();
+
+    let global = GlobalHaplotypes::new(reads.into_iter(), 5, 205, 0.01, 0.05);
+    let haplotypes = global.get_haplotypes();
+
+    assert_eq!(haplotypes.len(), 2);
+    assert_eq!(haplotypes[0].bases, major);
+    assert_eq!(haplotypes[1].bases, minor);
+    assert!((haplotypes[0].frequency - 0.7).abs() < 0.01);
+    assert!((global.error_rate - 0.02).abs() < 0.005);
+    assert!(global.get_posteriors().rows().into_iter().all(|z| z.iter().any(|p| *p > 0.99)));
+}
+
+#[test]
+fn default_region_is_the_most_spanned_window_of_the_median_length() {
+    let read = |offset: usize, len: usize| AlignedRead { name: String::new(), offset, bases: vec![0; len] };
+
+    // median length 10: columns 5..15 are spanned by the three reads of length 10 or more
+    let reads = vec![read(0, 15), read(5, 10), read(3, 20), read(30, 4), read(40, 2)];
+    assert_eq!(spanned_region(&reads), Some((5, 15)));
+
+    assert_eq!(spanned_region(&[]), None);
+}
@@ -0,0 +1,23 @@
+use ndarray::Array;
+
+use qsalib::prelude::*;
+use qsalib::protein::codon_index;
+
+#[test]
+fn synonymous_and_nonsynonymous_diversity() {
+    let mut codons = Array::<u64, _>::zeros((64, 2));
+    // TTT/TTC (both F) at the first codon, AAA/AAC (K/N) at the second
+    codons[[codon_index([3, 3, 3]), 0]] = 5;
+    codons[[codon_index([3, 3, 1]), 0]] = 5;
+    codons[[codon_index([0, 0, 0]), 1]] = 5;
+    codons[[codon_index([0, 0, 1]), 1]] = 5;
+
+    let selection = Selection::new(&ProteinMatrices::new("p".to_string(), codons));
+    let (first, second) = (selection.get_codons()[0], selection.get_codons()[1]);
+
+    assert!((first.synonymous_sites - 1. / 3.).abs() < 1e-12);
+    assert!((first.synonymous_diffs - 25. / 45.).abs() < 1e-12);
+    assert_eq!(first.nonsynonymous_diffs, 0.);
+    assert_eq!(second.synonymous_diffs, 0.);
+    assert!((second.nonsynonymous_diffs - 25. / 45.).abs() < 1e-12);
+}
@@ -0,0 +1,35 @@
+use ndarray::{arr1, arr2};
+
+use qsalib::prelude::*;
+
+#[test]
+fn spectrum_classes_and_contexts() {
+    // reference: G G A C
+    let pfm = arr2(&[
+        [2, 1, 10, 0],
+        [0, 0, 0, 8],
+        [8, 9, 0, 0],
+        [0, 0, 0, 2],
+    ]);
+    let reference = arr1(&[2, 2, 0, 1]);
+
+    let spectrum = Spectrum::new(pfm.view(), reference.view());
+
+    assert_eq!(spectrum.transitions(), 5);
+    assert_eq!(spectrum.transversions(), 0);
+    assert_eq!(spectrum.ts_tv(), f64::INFINITY);
+    assert!((spectrum.g_to_a_rate(GContext::GG) - 0.2).abs() < 1e-12);
+    assert!((spectrum.g_to_a_rate(GContext::GA) - 0.1).abs() < 1e-12);
+    assert_eq!(spectrum.g_to_a_rate(GContext::Other), 0.);
+}
+
+#[test]
+fn ts_tv_without_substitutions() {
+    let pfm = arr2(&[[4, 0], [0, 0], [0, 3], [0, 0]]);
+    let reference = arr1(&[0, 2]);
+
+    let spectrum = Spectrum::new(pfm.view(), reference.view());
+
+    assert_eq!(spectrum.transitions() + spectrum.transversions(), 0);
+    assert!(spectrum.ts_tv().is_nan());
+}
@@ -0,0 +1,44 @@
+use qsalib::stats::{bin_means, bins, moving_average};
+
+#[test]
+fn bins_cover_every_column_once() {
+    assert_eq!(bins(10, 4), vec![(0, 2), (2, 5), (5, 7), (7, 10)]);
+    assert_eq!(bins(3, 800), vec![(0, 1), (1, 2), (2, 3)]);
+    assert!(bins(0, 800).is_empty());
+
+    let edges = bins(29_000, 800);
+    assert_eq!(edges.len(), 800);
+    assert_eq!(edges[0].0, 0);
+    assert_eq!(edges[799].1, 29_000);
+    assert!(edges.windows(2).all(|x| x[0].1 == x[1].0));
+    assert!(edges.iter().all(|(start, end)| end - start == 36 || end - start == 37));
+}
+
+#[test]
+fn bin_means_leave_out_missing_values() {
+    let values = [1., 3., f64::NAN, 4., f64::NAN, f64::NAN];
+    let means = bin_means(&values, &bins(6, 3));
+
+    assert_eq!(means[..2], [2., 4.]);
+    assert!(means[2].is_nan());
+}
+
+#[test]
+fn moving_average_of_a_known_series() {
+    let values = [0., 3., 6., 3., 0.];
+
+    assert_eq!(moving_average(&values, 1), values);
+    assert_eq!(moving_average(&values, 3), [1.5, 3., 4., 3., 1.5]);
+    // an even window is widened to the next odd one, so that it stays centred
+    assert_eq!(moving_average(&values, 4), moving_average(&values, 5));
+    assert_eq!(moving_average(&values, 5), [3., 3., 2.4, 3., 3.]);
+}
+
+#[test]
+fn moving_average_does_not_bridge_gaps() {
+    let smoothed = moving_average(&[2., f64::NAN, 4., 6.], 3);
+
+    assert_eq!(smoothed[0], 2.);
+    assert!(smoothed[1].is_nan());
+    assert_eq!(smoothed[2..], [5., 5.]);
+}
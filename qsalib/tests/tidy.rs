@@ -0,0 +1,116 @@
+mod common;
+
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use qsalib::prelude::*;
+
+use common::{temp_dir, write_bam};
+
+/// Two samples over 4 positions: `a` reads a single base at each of them and `b` two, at the last one.
+fn bamdata(path: &Path) -> BamData {
+    let (a, b) = (path.join("a.bam"), path.join("b.bam"));
+    write_bam(&a, &[(1, "ACGT"), (1, "ACGT")]);
+    write_bam(&b, &[(1, "ACGT"), (1, "ACGA")]);
+
+    BamDataBuilder::default()
+        .add_bams(vec![a, b]).unwrap()
+        .in_range((0, 4))
+        .with_distance(Metric::BrayCurtis.distance())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn table_files_share_columns_and_version() {
+    let path = temp_dir("tidy-table");
+
+    let mut table = Table::new("positions", &["sample", "position", "efficiency", "masked"]);
+    table.with_attribute("distance", json!("alpha"));
+    table.push(vec![json!("s1"), json!(1), json!(0.25), json!(false)]);
+    table.push(vec![json!("s1"), json!(2), Value::Null, json!(true)]);
+    table.to_files(&path);
+
+    let tsv = std::fs::read_to_string(path.join("positions.tsv")).unwrap();
+    assert_eq!(tsv, "sample\tposition\tefficiency\tmasked\ns1\t1\t0.25\tfalse\ns1\t2\tNA\ttrue\n");
+
+    let parsed: Value = serde_json::from_str(&std::fs::read_to_string(path.join("positions.json")).unwrap()).unwrap();
+    assert_eq!(parsed["version"], json!(FORMAT_VERSION));
+    assert_eq!(parsed["table"], json!("positions"));
+    assert_eq!(parsed["distance"], json!("alpha"));
+    assert_eq!(parsed["columns"][2], json!("efficiency"));
+    assert_eq!(parsed["rows"][1], json!(["s1", 2, null, true]));
+
+    std::fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn positions_table_has_a_row_per_sample_and_position() {
+    let path = temp_dir("tidy-positions");
+    let data = bamdata(&path);
+    let table = data.positions_table();
+
+    assert_eq!(table.columns, [
+        "sample", "position", "depth", "count_a", "count_c", "count_g", "count_t",
+        "freq_a", "freq_c", "freq_g", "freq_t", "entropy", "efficiency", "masked",
+    ]);
+    assert_eq!(table.rows.len(), 8);
+    assert_eq!(json!(table.rows[0]), json!(["a", 1, 2, 2, 0, 0, 0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, false]));
+    assert_eq!(json!(table.rows[4][..7]), json!(["b", 1, 2, 2, 0, 0, 0]));
+    assert_eq!(json!(table.rows[7][..11]), json!(["b", 4, 2, 1, 0, 0, 1, 0.5, 0.0, 0.0, 0.5]));
+    assert_eq!(table.rows[7][11], json!(1.0));
+
+    std::fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn samples_table_summarises_reads_depth_and_alpha() {
+    let path = temp_dir("tidy-samples");
+    let data = bamdata(&path);
+    let summaries = data.summaries(10, 1);
+    let table = data.samples_table(&summaries);
+    let column = |row: usize, name: &str| table.rows[row][table.columns.iter().position(|x| x == name).unwrap()].clone();
+
+    assert_eq!(table.rows.len(), 2);
+    assert_eq!(column(0, "sample"), json!("a"));
+    assert_eq!(column(1, "sample"), json!("b"));
+    assert_eq!(column(0, "group"), Value::Null);
+    assert_eq!(column(0, "reads"), json!(2));
+    assert_eq!(column(0, "processed_reads"), json!(2));
+    assert_eq!(column(0, "positions"), json!(4));
+    assert_eq!(column(0, "mean_depth"), json!(2.0));
+    assert_eq!(column(0, "uncovered_positions"), json!(0));
+    assert_eq!(column(0, "breadth_1x"), json!(1.0));
+    assert_eq!(column(0, "breadth_100x"), json!(0.0));
+    assert_eq!(column(0, "alpha"), json!(0.0));
+    assert!(column(1, "alpha").as_f64().unwrap() > 0.);
+
+    std::fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn beta_table_is_labelled_with_the_samples() {
+    let path = temp_dir("tidy-beta");
+    let data = bamdata(&path);
+    let table = data.beta_table();
+
+    assert_eq!(table.attributes["distance"], json!("bray-curtis"));
+    assert_eq!(table.columns, ["sample", "a", "b"]);
+    assert_eq!(json!(table.rows), json!([["a", 0.0, 0.125], ["b", 0.125, 0.0]]));
+
+    std::fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn pfm_csv_has_sample_and_position_columns() {
+    let path = temp_dir("tidy-pfm");
+    let data = bamdata(&path);
+    let bam = data.into_iter().nth(1).unwrap();
+    bam.pfm_to_csv(&path, "pfm.csv");
+
+    let csv = std::fs::read_to_string(path.join("pfm.csv")).unwrap();
+    assert_eq!(csv, "sample,position,A,C,G,T\nb,1,2,0,0,0\nb,2,0,2,0,0\nb,3,0,0,2,0\nb,4,1,0,0,1\n");
+
+    std::fs::remove_dir_all(path).unwrap();
+}
@@ -0,0 +1,81 @@
+use qsalib::prelude::*;
+use qsalib::stats::{beta_binomial_homogeneity, beta_binomial_ln_pmf};
+
+/// 200 reads over three columns: `rising` reads carry C at the first column (N at every read when
+/// `None`), 20 carry G at the second and one carries T at the third, on an A background.
+fn timepoint(rising: Option<usize>) -> Matrices {
+    let reads = (0..200).map(|i| AlignedRead {
+        name: format!("read{}", i),
+        offset: 0,
+        bases: vec![
+            rising.map_or(4, |k| if i < k { 1 } else { 0 }),
+            if i < 20 { 2 } else { 0 },
+            if i == 0 { 3 } else { 0 },
+        ],
+    });
+
+    Matrices::new(reads, (0, 3)).unwrap()
+}
+
+#[test]
+fn beta_binomial_is_a_distribution() {
+    let total = (0..=20).map(|k| beta_binomial_ln_pmf(k, 20, 0.3, 0.1).exp()).sum::<f64>();
+
+    assert!((total - 1.).abs() < 1e-9);
+}
+
+#[test]
+fn rising_allele_is_significant() {
+    let (_, stable) = beta_binomial_homogeneity(&[(10, 1000), (11, 1000), (9, 1000)], 0.01);
+    let (_, rising) = beta_binomial_homogeneity(&[(10, 1000), (200, 1000), (800, 1000)], 0.01);
+    let (_, single) = beta_binomial_homogeneity(&[(10, 1000), (0, 0)], 0.01);
+
+    assert!(stable > 0.5);
+    assert!(rising < 1e-6);
+    assert!(single.is_nan());
+}
+
+#[test]
+fn trajectories_follow_timepoints() {
+    let (t0, t1, t2, t3) = (timepoint(None), timepoint(Some(2)), timepoint(Some(60)), timepoint(Some(160)));
+    let samples: Vec<TimedSample> = vec![
+        ("t3".to_string(), 3., &t3),
+        ("t0".to_string(), 0., &t0),
+        ("t2".to_string(), 2., &t2),
+        ("t1".to_string(), 1., &t1),
+    ];
+
+    let trajectories = Trajectories::new("patient", &samples, 0.02, 0.01);
+
+    let order: Vec<&str> = trajectories.samples.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(order, vec!["t0", "t1", "t2", "t3"]);
+
+    // the T at 0.5% never reaches min_freq
+    let followed: Vec<(usize, usize)> = trajectories.get_trajectories().iter().map(|x| (x.column, x.allele)).collect();
+    assert_eq!(followed, vec![(0, 1), (1, 2)]);
+
+    let rising = &trajectories.get_trajectories()[0];
+    let flat = &trajectories.get_trajectories()[1];
+
+    // first column is not covered at t0, and C is the major base by t3
+    assert_eq!(rising.baseline, 0);
+    assert_eq!(rising.counts, vec![(0, 0), (2, 200), (60, 200), (160, 200)]);
+    assert!(rising.q_value <= 0.05);
+    assert_eq!(rising.direction(0.05), "rising");
+    assert_eq!(flat.baseline, 0);
+    assert_eq!(flat.direction(0.05), "stable");
+
+    let top = trajectories.top(1);
+    assert_eq!(top.len(), 1);
+    assert_eq!((top[0].column, top[0].allele), (0, 1));
+
+    let records = trajectories.records(1, 0.05);
+    assert_eq!(records[0][1..4], ["1", "A", "C"]);
+    assert_eq!(records[0][4], "3");
+    assert_eq!(records[0][9], "rising");
+
+    let tidy = trajectories.tidy_records(1);
+    assert_eq!(tidy.len(), 3 + 4);
+    assert!(!tidy.iter().any(|x| x[1] == "1" && x[3] == "t0"));
+    assert!(tidy.iter().any(|x| x[1] == "2" && x[3] == "t0"));
+}
@@ -0,0 +1,62 @@
+use ndarray::arr2;
+
+use qsalib::prelude::*;
+
+fn labels(names: &[&str]) -> Vec<String> {
+    names.iter().map(|x| x.to_string()).collect()
+}
+
+#[test]
+fn neighbour_joining_recovers_additive_tree() {
+    let distances = arr2(&[
+        [0., 5., 9., 9., 8.],
+        [5., 0., 10., 10., 9.],
+        [9., 10., 0., 8., 7.],
+        [9., 10., 8., 0., 3.],
+        [8., 9., 7., 3., 0.],
+    ]);
+
+    let names = ["a", "b", "c", "d", "e"];
+    let tree = Tree::neighbour_joining(distances.view(), &labels(&names));
+
+    assert_eq!(tree.leaves().len(), 5);
+    for (i, a) in names.iter().enumerate() {
+        for (j, b) in names.iter().enumerate().skip(i + 1) {
+            let path = patristic(&tree, a, b).unwrap();
+            assert!((path - distances[[i, j]]).abs() < 1e-9, "{} {} {}", a, b, path);
+        }
+    }
+}
+
+/// Depth of a leaf below a node, if it is there.
+fn depth(tree: &Tree, leaf: &str) -> Option<f64> {
+    if tree.is_leaf() {
+        return if tree.label.as_deref() == Some(leaf) { Some(0.) } else { None };
+    }
+
+    tree.children.iter().find_map(|(child, length)| depth(child, leaf).map(|x| x + length))
+}
+
+/// Length of the path between two leaves.
+fn patristic(tree: &Tree, a: &str, b: &str) -> Option<f64> {
+    for (child, _) in &tree.children {
+        if depth(child, a).is_some() && depth(child, b).is_some() {
+            return patristic(child, a, b);
+        }
+    }
+
+    Some(depth(tree, a)? + depth(tree, b)?)
+}
+
+#[test]
+fn upgma_is_ultrametric() {
+    let distances = arr2(&[
+        [0., 2., 6.],
+        [2., 0., 6.],
+        [6., 6., 0.],
+    ]);
+
+    let tree = Tree::upgma(distances.view(), &labels(&["a", "b", "my sample"]));
+
+    assert_eq!(tree.to_newick(), "((a:1,b:1):2,'my sample':3);");
+}
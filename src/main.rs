@@ -2,10 +2,15 @@ use std::path::PathBuf;
 use std::process::exit;
 
 use structopt::StructOpt;
-use inline_python::python;
 
 use qsalib::prelude::*;
 
+mod plot;
+mod report;
+
+use plot::*;
+use report::*;
+
 #[derive(Debug, StructOpt)]  // TODO: What **does** qsa exactly?
 /// QuasiSpecies Analyser (QSA) is a bioinformatics tool which enables the analysis
 /// of quasispecies viruses with ease.
@@ -34,11 +39,188 @@ struct QSAArgs {
     /// Minimum coverage value for the position to be considered valid.
     ///
     /// When reading a BAM file, the relative coverage of the reads over the
-    /// reference sequence is calculated. When the threshold is set, the tool
-    /// will discard the region of all read sequences where this threshold is
-    /// not met. Use a value of 0 to disable this function.
+    /// reference sequence is calculated. Positions whose coverage, relative to
+    /// the deepest position, does not meet the threshold are masked in the
+    /// tables, the QC summary and the report, and the threshold is drawn on
+    /// the depth plots. Use a value of 0 to disable this function.
     #[structopt(short, long, default_value = "0.65")]
     threshold: f64,
+    /// Reference sequence in FASTA format.
+    ///
+    /// The reference is used to classify the substitutions observed in the
+    /// reads. Only the first sequence of the file is read. When no reference
+    /// is supplied, the consensus sequence of each sample is used instead.
+    #[structopt(short, long)]
+    reference: Option<PathBuf>,
+    /// Genome annotation in GFF3 or BED format.
+    ///
+    /// When an annotation is supplied, diversity is also reported for each
+    /// annotated gene, ORF or region, and the regions are drawn as tracks
+    /// under the efficiency plots. CDS features are translated codon by codon
+    /// to report amino-acid diversity. BED files are told apart by their
+    /// extension, and their lines with a thick part are taken as CDS.
+    #[structopt(short, long)]
+    annotation: Option<PathBuf>,
+    /// Sample metadata in CSV or TSV format.
+    ///
+    /// The file needs a header row with a `sample` column holding the names
+    /// of the BAM files (without extension). A `group` column assigns each
+    /// sample to a group, so that groups can be compared with each other,
+    /// and `timepoint` or `date` and `patient` columns follow samples in time.
+    /// TSV files are told apart by their `tsv` or `txt` extension.
+    #[structopt(long)]
+    metadata: Option<PathBuf>,
+    /// Minimum frequency for a non-consensus base to be counted as a variant.
+    ///
+    /// The same frequency is used to follow minor alleles in time and to find
+    /// the variable sites of linkage and covariation.
+    #[structopt(short, long, default_value = "0.01")]
+    min_freq: f64,
+    /// Order of the Hill numbers used to partition diversity.
+    ///
+    /// Order 0 counts the bases present, order 1 weighs them by their
+    /// frequency (exponential of Shannon's entropy) and order 2 favours the
+    /// most frequent ones (inverse of Simpson's index).
+    #[structopt(long, default_value = "1")]
+    hill_order: f64,
+    /// Width, in codons, of the sliding window used to plot πN and πS.
+    #[structopt(long, default_value = "30")]
+    dnds_window: usize,
+    /// Detects hypermutated reads.
+    ///
+    /// Each read is tested for APOBEC-induced G→A hypermutation with a
+    /// one-sided Fisher's exact test comparing G→A mutations in the GRD
+    /// context against those in the control context, as Hypermut 2.0 does.
+    /// The reads are tested against the reference, or against the consensus
+    /// of the sample when there is none.
+    #[structopt(long)]
+    hypermutation: bool,
+    /// P-value under which a read is flagged as hypermutated.
+    #[structopt(long, default_value = "0.05")]
+    hypermut_pvalue: f64,
+    /// Leaves hypermutated reads out of the analysis, detecting them even without --hypermutation.
+    ///
+    /// Reads flagged as hypermutated are still reported, but they do not
+    /// contribute to the frequency matrices and to the metrics built on them.
+    #[structopt(long)]
+    exclude_hypermutated: bool,
+    /// Distance between samples used for β-diversity.
+    ///
+    /// `alpha` is the difference in α-diversity between two samples, while
+    /// `jsd` (Jensen–Shannon divergence) and `hellinger` compare the base
+    /// frequencies at each position and average them over the genome.
+    /// `bray-curtis` compares the whole PFMs, and `nei` is Nei's standard
+    /// genetic distance treating every position as a locus.
+    #[structopt(short, long, default_value = "alpha", possible_values = &Metric::NAMES)]
+    distance: Metric,
+    /// Number of permutations used to test differences between groups.
+    ///
+    /// Groups from the metadata are compared with a PERMANOVA on the
+    /// β-diversity matrix and with a Mann–Whitney (two groups) or
+    /// Kruskal–Wallis (more groups) test on α-diversity, whose p-values
+    /// come from permuting the group labels.
+    #[structopt(long, default_value = "999")]
+    permutations: usize,
+    /// Seed of the random permutations, so that p-values are reproducible.
+    #[structopt(long, default_value = "1")]
+    seed: u64,
+    /// Compares two groups of the metadata position by position.
+    ///
+    /// At each position the base composition of the pooled samples of the
+    /// two groups is compared with a G-test, and the efficiency of their
+    /// samples with a Mann–Whitney test. P-values are corrected with the
+    /// Benjamini–Hochberg procedure.
+    #[structopt(long, number_of_values = 2, value_names = &["GROUP_A", "GROUP_B"])]
+    compare: Vec<String>,
+    /// Overdispersion of allele counts used to test allele trajectories.
+    ///
+    /// Samples with a numeric `timepoint` in the metadata are ordered in
+    /// time, separately for each `patient` if the column exists, and the
+    /// frequency of each minor allele is followed across them. Changes are
+    /// tested with a beta-binomial model: the larger the overdispersion, the
+    /// less sequencing depth alone makes a change significant. It needs to be
+    /// greater than 0 and less than 1.
+    #[structopt(long, default_value = "0.01")]
+    overdispersion: f64,
+    /// Number of bootstrap resamplings of the sites for evolutionary rates.
+    ///
+    /// Samples with a `date` in the metadata (`YYYY-MM-DD` or a decimal
+    /// year) are used to estimate the rates at which each patient diverges
+    /// from the consensus of its earliest sample and accumulates diversity,
+    /// in substitutions per site per year.
+    #[structopt(long, default_value = "1000")]
+    bootstrap: usize,
+    /// Width of the windows in which local haplotypes are reconstructed.
+    ///
+    /// The reads covering a whole window are collapsed into distinct
+    /// haplotypes, written as FASTA files with their frequencies in the
+    /// headers. Haplotypes below the haplotype minimum frequency are taken as
+    /// sequencing errors and merged into a close, more frequent haplotype.
+    #[structopt(long)]
+    haplotype_window: Option<usize>,
+    /// Step between the starts of two haplotype windows, half their width by default.
+    #[structopt(long)]
+    haplotype_step: Option<usize>,
+    /// Mismatches within which a rare haplotype is merged into a frequent one.
+    #[structopt(long, default_value = "2")]
+    haplotype_mismatches: usize,
+    /// Frequency under which a local haplotype is taken as a sequencing error.
+    #[structopt(long, default_value = "0.01")]
+    haplotype_min_freq: f64,
+    /// Estimates full-length haplotypes from long reads.
+    ///
+    /// The reads spanning the whole region are clustered into candidate
+    /// haplotypes, whose sequences and frequencies are then refined by
+    /// expectation-maximisation under a per-base error model. Haplotypes are
+    /// written as FASTA, along with the posterior probability of each read
+    /// coming from each haplotype.
+    #[structopt(long)]
+    global_haplotypes: bool,
+    /// Region of the full-length haplotypes.
+    ///
+    /// By default, each sample uses the window as long as its median read
+    /// that the most reads span.
+    #[structopt(long, number_of_values = 2, value_names = &["START", "END"])]
+    global_region: Vec<usize>,
+    /// Initial per-base error rate of the reads, refined by the EM.
+    #[structopt(long, default_value = "0.01")]
+    error_rate: f64,
+    /// Frequency under which a full-length haplotype is dropped by the EM.
+    #[structopt(long, default_value = "0.01")]
+    global_min_freq: f64,
+    /// Measures linkage disequilibrium between variable sites.
+    ///
+    /// A site is variable when its second most frequent base reaches the
+    /// minimum frequency. For every pair of variable sites spanned by the
+    /// same reads, the four combinations of their major and minor bases are
+    /// counted, and D', r² and Fisher's exact test are reported in a table
+    /// and a heatmap for each sample.
+    #[structopt(long)]
+    linkage: bool,
+    /// Minimum number of reads spanning two sites to measure their linkage.
+    #[structopt(long, default_value = "10")]
+    linkage_min_reads: u64,
+    /// Looks for coevolving positions through mutual information.
+    ///
+    /// For every pair of variable sites covered by the same read or read pair,
+    /// the mutual information between their bases is computed and corrected
+    /// for background covariation with the average product correction, and
+    /// its significance is assessed by permuting the bases over the reads.
+    /// The coupled positions are ranked in a table and drawn as a contact map.
+    #[structopt(long)]
+    covariation: bool,
+    /// Minimum number of reads or read pairs covering two sites to measure their covariation.
+    #[structopt(long, default_value = "10")]
+    covariation_min_reads: u64,
+    /// Orders the samples of the efficiency heatmap by their UPGMA tree.
+    ///
+    /// Samples close in β-diversity are then drawn next to each other,
+    /// instead of in the order of the input.
+    #[structopt(long)]
+    cluster_samples: bool,
+    /// Width of the moving average smoothing the overlaid efficiency lines, in positions.
+    #[structopt(long, default_value = "25")]
+    smoothing: usize,
     /// Disables checks.
     ///
     /// As of now, the only check the program performs is that all BAMs have
@@ -54,149 +236,247 @@ struct QSAArgs {
     /// folder does not exist, it will be created.
     #[structopt(short, long, default_value = "qsaout")]
     out_dir: PathBuf,
+    /// File format of the plots.
+    ///
+    /// Plots are drawn natively, as vector graphics (`svg`) or as bitmaps
+    /// (`png`), without relying on any external program.
+    #[structopt(long, default_value = "png", possible_values = &PlotFormat::NAMES)]
+    plot_format: PlotFormat,
+    #[structopt(subcommand)]
+    command: Option<Command>,
 }
 
-impl QSAArgs {
-    fn validate(&self) -> () {
-        if self.bams.is_empty() {
-            eprintln!("You need to specify at least one or two BAM files.");
+#[derive(Debug, Clone, StructOpt)]
+enum Command {
+    /// Draws the sequence logos of a region or of the most efficient windows.
+    ///
+    /// The logos of all the samples are stacked in one figure, so that they
+    /// can be compared position by position. Only the logos are drawn, the
+    /// rest of the analysis is skipped.
+    Logo(LogoArgs),
+}
+
+#[derive(Debug, Clone, StructOpt)]
+struct LogoArgs {
+    /// Draws the logos of a region, from START to END (1-based, inclusive).
+    #[structopt(long, number_of_values = 2, value_names = &["START", "END"])]
+    region: Vec<usize>,
+    /// Draws the logos of this many windows with the highest efficiency.
+    ///
+    /// Efficiency is averaged over the samples, and the windows do not overlap.
+    #[structopt(long)]
+    windows: Option<usize>,
+    /// Width of the windows.
+    #[structopt(long, default_value = "20")]
+    width: usize,
+    /// Height of the letters.
+    ///
+    /// `information` scales the frequency of each base by the information
+    /// content of the position, in bits, corrected for the number of bases
+    /// read, while `frequency` shows the frequencies alone.
+    #[structopt(long = "type", value_name = "type", default_value = "information", possible_values = &LogoKind::NAMES)]
+    kind: LogoKind,
+}
+
+impl LogoArgs {
+    /// Checks the region against the analysed range, from `start` to `end` (0 when unbounded).
+    fn validate(&self, start: i32, end: i32) {
+        if self.region.is_empty() && self.windows.is_none() {
+            eprintln!("You need to choose a region or a number of windows to draw logos.");
             exit(3)
         }
-    }
 
-    fn into_bamdata(self) -> Result<BamData> {
-        let mut bams: Vec<PathBuf> = Vec::default();
-        let mut dirs: Vec<PathBuf> = Vec::default();
-        for path in self.bams {
-            if path.is_dir() {
-                dirs.push(path);
-            } else if path.is_file() {
-                bams.push(path);
+        if let [first, last] = self.region.as_slice() {
+            if *first == 0 || first > last || *first as i32 <= start || (end > 0 && *last as i32 > end) {
+                eprintln!("The logo region needs to start at 1 or after, end after its start and lie inside the analysed range.");
+                exit(3)
             }
         }
 
-        BamDataBuilder::default()
-            .add_bams(bams)?
-            .add_dirs(dirs)?
-            .in_range((self.start, self.end))
-            .with_threshold(self.threshold)
-            .with_checks(!self.no_checks)
-            .build()
+        if self.windows.is_some() && self.width == 0 {
+            eprintln!("The width of the logo windows needs to be at least 1.");
+            exit(3)
+        }
     }
-}
-
-fn efficiency2graph(path: String, bam: &Bam) {
-    let efficiency = bam.matrices.get_efficiency().to_vec();
-    let filename = path + "/" + bam.name.as_str() + "-efficiency.png";
-
-    python! {
-        import matplotlib.pyplot as plt
-
-        plt.figure(figsize=[10, 5])
-
-        ax = plt.subplot(111)
 
-        ax.plot('efficiency, "r", linewidth=1)
-
-        ax.spines["right"].set_visible(False)
-        ax.spines["top"].set_visible(False)
-
-        ax.set_axisbelow(True)
-        ax.yaxis.grid(color="gray", linestyle="dashed")
-        //ax.xaxis.grid(color="gray")
-
-        plt.xlabel("position")
-        plt.ylabel("efficiency")
+    /// Draws the logos of the region and of the windows, failing when the region lies outside the data.
+    fn draw(&self, path: String, data: &BamData, format: PlotFormat) {
+        let mut windows = data.top_efficiency_windows(self.width, self.windows.unwrap_or(0));
+        if let [start, end] = self.region.as_slice() {
+            match data.region_columns(*start, *end) {
+                Ok(columns) => windows.insert(0, columns),
+                Err(why) => {
+                    eprintln!("{}", why);
+                    exit(3)
+                },
+            }
+        }
 
-        //plt.show()
-        plt.margins(x=0.0075)
-        plt.tight_layout(pad=0.15)
-        plt.savefig('filename, transparent=True, bbox_inches="tight")
-        plt.clf()
+        for columns in windows {
+            logo2graph(path.clone(), &data.logos(columns, self.kind), format);
+        }
     }
 }
 
-fn alphadiv2graph(path: String, bamdata: &BamData) {
-    let alpha = bamdata.alpha_diversity().to_vec();
-    let labels = bamdata.get_names();
-    let filename = path + "/alpha-diversity.png";
-
-    python! {
-        import matplotlib.pyplot as plt
-
-        COLORS = ["#e6194b", "#3cb44b", "#ffe119", "#4363d8", "#f58231", "#911eb4", "#46f0f0", "#f032e6", "#bcf60c", "#fabebe", "#008080", "#e6beff", "#9a6324", "#fffac8", "#800000", "#aaffc3", "#808000", "#ffd8b1", "#000075", "#808080", "#000000"]
-
-        plt.figure(figsize=[8, 5])
+impl QSAArgs {
+    fn validate(&self) {
+        if self.bams.is_empty() {
+            eprintln!("You need to specify at least one or two BAM files.");
+            exit(3)
+        }
 
-        ax = plt.subplot(111)
+        if let Some(Command::Logo(logo)) = &self.command {
+            logo.validate(self.start, self.end);
+        }
 
-        ax.bar('labels, 'alpha, color=COLORS)
+        if !self.compare.is_empty() && self.metadata.is_none() {
+            eprintln!("You need to specify a metadata file with groups to compare them.");
+            exit(3)
+        }
 
-        ax.spines["right"].set_visible(False)
-        ax.spines["top"].set_visible(False)
+        if !(self.overdispersion > 0. && self.overdispersion < 1.) {
+            eprintln!("The overdispersion needs to be greater than 0 and less than 1.");
+            exit(3)
+        }
 
-        ax.set_axisbelow(True)
-        ax.yaxis.grid(color="gray", linestyle="dashed")
+        if !(0. ..1.).contains(&self.haplotype_min_freq) || !(0. ..1.).contains(&self.global_min_freq) {
+            eprintln!("The minimum frequencies of haplotypes need to be at least 0 and less than 1.");
+            exit(3)
+        }
 
-        //plt.xlabel("sample")
-        plt.ylabel("α-diversity")
+        if self.haplotype_window == Some(0) || self.haplotype_step == Some(0) {
+            eprintln!("The width and the step of the haplotype windows need to be at least 1.");
+            exit(3)
+        }
 
-        //plt.show()
-        plt.margins(x=0.015)
-        plt.tight_layout(pad=0.15)
-        plt.savefig('filename, transparent=True, bbox_inches="tight")
-        plt.clf()
+        if let Some(width) = self.haplotype_window {
+            if self.end > 0 && width as i32 > self.end - self.start {
+                eprintln!("Warning: the haplotype windows are wider than the analysed range, no haplotype will be reconstructed.");
+            }
+        }
     }
-}
 
-fn betadiv2graph(path: String, bamdata: &BamData) {
-    let beta = bamdata.beta_diversity();
-    let mut betav: Vec<f64> = Vec::new();
-    for i in 0..beta.nrows() {
-        for j in (i+1)..beta.nrows() {
-            betav.push(beta[[i, j]]);
+    /// Options of the run, by name, as shown in the report.
+    fn parameters(&self) -> Vec<(String, String)> {
+        let path = |x: &Option<PathBuf>| x.as_ref().map_or("none".to_string(), |x| x.display().to_string());
+        let range = match (self.start, self.end) {
+            (0, 0) => "whole reference".to_string(),
+            (start, end) => format!("{}–{}", start, end),
+        };
+
+        let mut parameters = vec![
+            ("BAM files", self.bams.iter().map(|x| x.display().to_string()).collect::<Vec<String>>().join(", ")),
+            ("Range", range),
+            ("Coverage threshold", self.threshold.to_string()),
+            ("Reference", path(&self.reference)),
+            ("Annotation", path(&self.annotation)),
+            ("Metadata", path(&self.metadata)),
+            ("Minimum frequency", self.min_freq.to_string()),
+            ("Hill order", self.hill_order.to_string()),
+            ("dN/dS window (codons)", self.dnds_window.to_string()),
+            ("Hypermutated reads", match (self.hypermutation, self.exclude_hypermutated) {
+                (_, true) => format!("excluded, p-value {}", self.hypermut_pvalue),
+                (true, false) => format!("detected, p-value {}", self.hypermut_pvalue),
+                (false, false) => "not detected".to_string(),
+            }),
+            ("β-diversity distance", self.distance.to_string()),
+            ("Permutations", self.permutations.to_string()),
+            ("Seed", self.seed.to_string()),
+            ("Bootstrap resamplings", self.bootstrap.to_string()),
+            ("Overdispersion", self.overdispersion.to_string()),
+        ];
+
+        if let [a, b] = self.compare.as_slice() {
+            parameters.push(("Compared groups", format!("{} and {}", a, b)));
+        }
+        if let Some(width) = self.haplotype_window {
+            let step = self.haplotype_step.unwrap_or((width / 2).max(1));
+            parameters.push((
+                "Haplotype windows",
+                format!("width {}, step {}, {} mismatches, minimum frequency {}", width, step, self.haplotype_mismatches, self.haplotype_min_freq),
+            ));
         }
+        if self.global_haplotypes {
+            let region = match self.global_region.as_slice() {
+                [start, end] => format!("{}–{}", start, end),
+                _ => "most spanned window".to_string(),
+            };
+            parameters.push(("Full-length haplotypes", format!("{}, error rate {}, minimum frequency {}", region, self.error_rate, self.global_min_freq)));
+        }
+        if self.linkage {
+            parameters.push(("Linkage minimum reads", self.linkage_min_reads.to_string()));
+        }
+        if self.covariation {
+            parameters.push(("Covariation minimum reads", self.covariation_min_reads.to_string()));
+        }
+        parameters.push(("Samples clustered in the efficiency heatmap", self.cluster_samples.to_string()));
+        parameters.push(("Efficiency smoothing window", self.smoothing.to_string()));
+        parameters.push(("Checks", (!self.no_checks).to_string()));
+        parameters.push(("Plot format", self.plot_format.to_string()));
+
+        parameters.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
     }
 
-    let labels = bamdata.get_names();
-    let filename = path + "/beta-diversity.png";
+    fn into_bamdata(self) -> Result<BamData> {
+        let mut bams: Vec<PathBuf> = Vec::default();
+        let mut dirs: Vec<PathBuf> = Vec::default();
+        for path in self.bams {
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.is_file() {
+                bams.push(path);
+            }
+        }
 
-    python! {
-        import networkx as nx
-        from networkx.drawing.nx_agraph import graphviz_layout
-        import matplotlib.pyplot as plt
-        from itertools import combinations as cmb
+        let mut builder = BamDataBuilder::default();
 
-        COLORS = ["#e6194b", "#3cb44b", "#ffe119", "#4363d8", "#f58231", "#911eb4", "#46f0f0", "#f032e6", "#bcf60c", "#fabebe", "#008080", "#e6beff", "#9a6324", "#fffac8", "#800000", "#aaffc3", "#808000", "#ffd8b1", "#000075", "#808080", "#000000"]
+        builder
+            .add_bams(bams)?
+            .add_dirs(dirs)?
+            .in_range((self.start, self.end))
+            .with_threshold(self.threshold)
+            .with_checks(!self.no_checks)
+            .with_distance(self.distance.distance())
+            .with_haplotype_correction(self.haplotype_min_freq, self.haplotype_mismatches);
 
-        plt.figure(figsize=[6.4, 4.8])
+        if self.hypermutation || self.exclude_hypermutated {
+            builder.with_hypermutation(self.hypermut_pvalue, self.exclude_hypermutated);
+        }
 
-        labels_comb = list(cmb('labels, 2))
-        edges_len = {labels_comb[i]: {"len": 'betav[i]} for i in range(len('betav))}
+        if self.global_haplotypes {
+            let region = match self.global_region.as_slice() {
+                [start, end] => Some((start.saturating_sub(1), *end)),
+                _ => None,
+            };
 
-        G = nx.Graph()
+            builder.with_global_haplotypes(region, self.error_rate, self.global_min_freq);
+        }
 
-        G.add_nodes_from('labels)
-        G.add_edges_from(labels_comb)
+        if self.linkage {
+            builder.with_linkage(self.min_freq, self.linkage_min_reads);
+        }
 
-        nx.set_edge_attributes(G, edges_len)
+        if self.covariation {
+            builder.with_covariation(self.min_freq, self.covariation_min_reads, self.permutations, self.seed);
+        }
 
-        ax = plt.subplot(111)
+        if let Some(width) = self.haplotype_window {
+            builder.with_haplotype_windows(width, self.haplotype_step.unwrap_or((width / 2).max(1)));
+        }
 
-        ax.set_title("β-diversity")
+        if let Some(reference) = self.reference {
+            builder.with_reference(reference)?;
+        }
 
-        ax.spines["right"].set_visible(False)
-        ax.spines["top"].set_visible(False)
-        ax.spines["bottom"].set_visible(False)
-        ax.spines["left"].set_visible(False)
+        if let Some(annotation) = self.annotation {
+            builder.with_annotation(annotation)?;
+        }
 
-        nx.draw_networkx_nodes(G, pos=graphviz_layout(G, prog="neato"), node_color=COLORS[:len('labels)])
-        nx.draw_networkx_labels(G, pos=graphviz_layout(G, prog="neato"))
-        //nx.draw_networkx(G, pos=graphviz_layout(G, prog="neato"), node_color=COLORS[:len('labels)])
+        if let Some(metadata) = self.metadata {
+            builder.with_metadata(metadata)?;
+        }
 
-        plt.tight_layout(pad=0.15)
-        plt.savefig('filename, transparent=True, bbox_inches="tight")
-        plt.clf()
+        builder.build()
     }
 }
 
@@ -206,6 +486,18 @@ fn main() {
     args.validate();
 
     let out_dir = args.out_dir.to_str().unwrap().to_owned();
+    let min_freq = args.min_freq;
+    let dnds_window = args.dnds_window;
+    let hill_order = args.hill_order;
+    let (permutations, seed) = (args.permutations, args.seed);
+    let compare = args.compare.clone();
+    let overdispersion = args.overdispersion;
+    let bootstrap = args.bootstrap;
+    let format = args.plot_format;
+    let threshold = args.threshold;
+    let command = args.command.clone();
+    let (cluster_samples, smoothing) = (args.cluster_samples, args.smoothing.max(1));
+    let parameters = args.parameters();
     std::fs::create_dir_all(out_dir.clone()).expect("could not create output directory");
 
     let bam_data = args.into_bamdata();
@@ -214,14 +506,118 @@ fn main() {
         Ok(data) => {
             println!("All is OK, data built successfully");
 
+            if let Some(Command::Logo(logo)) = command {
+                return logo.draw(out_dir, &data, format);
+            }
+
             for bam in &data {
-                efficiency2graph(out_dir.clone(), bam);
+                efficiency2graph(out_dir.clone(), bam, data.get_annotation(), format);
+                depth2graph(out_dir.clone(), bam, threshold, data.get_annotation(), format);
                 bam.pfm_to_csv(out_dir.clone(), (bam.name.clone() + ".csv").as_str());
+                if bam.get_hypermutation().is_some() {
+                    bam.hypermutated_to_csv(out_dir.clone(), (bam.name.clone() + "-hypermutated.csv").as_str());
+                }
+
+                if !bam.get_haplotypes().is_empty() {
+                    bam.haplotypes_to_fasta(out_dir.clone(), (bam.name.clone() + "-haplotypes").as_str());
+                }
+
+                bam.global_haplotypes_to_files(
+                    out_dir.clone(),
+                    (bam.name.clone() + "-global-haplotypes.fasta").as_str(),
+                    (bam.name.clone() + "-posteriors.csv").as_str(),
+                );
+                if let Some(global) = bam.get_global_haplotypes().filter(|x| x.get_reads().is_empty()) {
+                    let first = bam.get_range().0 as usize + 1;
+                    eprintln!(
+                        "{}: no read spans the whole region {}–{}, so it has no full-length haplotype.",
+                        bam.name, first + global.start, first + global.end - 1,
+                    );
+                }
+
+                if let Some(linkage) = bam.get_linkage() {
+                    bam.linkage_to_csv(out_dir.clone(), (bam.name.clone() + "-ld.csv").as_str());
+                    linkage2graph(out_dir.clone(), bam, linkage, format);
+                }
+
+                if let Some(covariation) = bam.get_covariation() {
+                    bam.covariation_to_csv(out_dir.clone(), (bam.name.clone() + "-mi.csv").as_str());
+                    covariation2graph(out_dir.clone(), bam, covariation, format);
+                }
+
+                if !bam.get_proteins().is_empty() {
+                    bam.proteins_to_csv(out_dir.clone(), (bam.name.clone() + "-proteins.csv").as_str());
+                    bam.selection_to_csv(out_dir.clone(), (bam.name.clone() + "-dnds.csv").as_str());
+                    dnds2graph(out_dir.clone(), bam, dnds_window, format);
+                }
             }
 
-            alphadiv2graph(out_dir.clone(), &data);
+            alphadiv2graph(out_dir.clone(), &data, format);
+            efficiency_heatmap2graph(out_dir.clone(), &data, cluster_samples, format);
+            efficiency_overlay2graph(out_dir.clone(), &data, smoothing, format);
+            if (&data).into_iter().any(|bam| !bam.get_haplotypes().is_empty()) {
+                data.complexity_to_csv(out_dir.clone(), "complexity.csv", "complexity-windows.csv");
+            }
+            if (&data).into_iter().any(|bam| bam.get_global_haplotypes().is_some()) {
+                data.global_haplotypes_to_csv(out_dir.clone(), "global-haplotypes.csv");
+            }
             
-            betadiv2graph(out_dir.clone(), &data);
+            spectrum2graph(out_dir.clone(), &data, format);
+            data.spectrum_to_csv(out_dir.clone(), "substitution-spectrum.csv");
+            if (&data).into_iter().any(|bam| bam.get_hypermutation().is_some()) {
+                data.hypermutation_to_csv(out_dir.clone(), "hypermutation.csv");
+            }
+            data.regions_to_csv(out_dir.clone(), "regions.csv", min_freq);
+            data.proteins_to_csv(out_dir.clone(), "proteins.csv");
+            data.selection_to_csv(out_dir.clone(), "dnds.csv");
+            data.differentiation_to_csv(out_dir.clone(), "differentiation.csv", "differentiation-positions.csv");
+            data.partition_to_csv(out_dir.clone(), "partition.csv", "partition-positions.csv", hill_order);
+
+            betadiv2graph(out_dir.clone(), &data, format);
+
+            pcoa2graph(out_dir.clone(), &data, format);
+
+            // ordination and trees need every distance between the samples to be defined
+            let trees = data.ordination_to_csv(out_dir.clone(), "pcoa.csv", "pcoa-eigenvalues.csv")
+                .and_then(|_| Ok((data.upgma()?, data.neighbour_joining()?)));
+            match trees {
+                Ok((upgma, nj)) => {
+                    dendrogram2graph(out_dir.clone(), &data, &upgma, "UPGMA", format);
+                    dendrogram2graph(out_dir.clone(), &data, &nj, "NJ", format);
+                    data.trees_to_newick(out_dir.clone(), "upgma.nwk", "nj.nwk").unwrap();
+                    data.beta_to_phylip(out_dir.clone(), "beta-diversity.phy").unwrap();
+                },
+                Err(why) => eprintln!("{}", why),
+            }
+
+            data.group_tests_to_csv(out_dir.clone(), "group-tests.csv", permutations, seed);
+
+            if let [a, b] = compare.as_slice() {
+                match data.differential_diversity(a, b) {
+                    Ok(differential) => {
+                        manhattan2graph(out_dir.clone(), &data, &differential, format);
+                        data.differential_to_csv(out_dir.clone(), "differential.csv", &differential);
+                    },
+                    Err(why) => eprintln!("{}", why),
+                }
+            }
+
+            let trajectories = data.trajectories(min_freq, overdispersion);
+            for series in &trajectories {
+                trajectories2graph(out_dir.clone(), &data, series, 10, format);
+            }
+            data.trajectories_to_csv(out_dir.clone(), "trajectories.csv", "trajectories-frequencies.csv", &trajectories);
+
+            let rates = data.evolutionary_rates(bootstrap, seed);
+            for rate in &rates {
+                rate2graph(out_dir.clone(), rate, format);
+            }
+            data.evolutionary_rates_to_csv(out_dir.clone(), "evolutionary-rate.csv", "evolutionary-rate-samples.csv", &rates);
+
+            let summaries = data.summaries(bootstrap, seed);
+            data.tables_to_files(out_dir.clone(), &summaries);
+            data.qc_to_csv(out_dir.clone(), "qc.csv", &summaries);
+            report2html(out_dir.clone(), &data, &summaries, &parameters, threshold, bootstrap);
         },
         Err(why) => {
             eprintln!("{}", why);
@@ -0,0 +1,1379 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use plotters::coord::Shift;
+use plotters::coord::types::RangedCoordf64;
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+
+use qsalib::prelude::*;
+use qsalib::spectrum::NUCLEOTIDES;
+use qsalib::stats::{bin_means, bins, moving_average};
+
+const FONT: &str = "sans-serif";
+
+const COLORS: [RGBColor; 21] = [
+    RGBColor(0xe6, 0x19, 0x4b), RGBColor(0x3c, 0xb4, 0x4b), RGBColor(0xff, 0xe1, 0x19), RGBColor(0x43, 0x63, 0xd8),
+    RGBColor(0xf5, 0x82, 0x31), RGBColor(0x91, 0x1e, 0xb4), RGBColor(0x46, 0xf0, 0xf0), RGBColor(0xf0, 0x32, 0xe6),
+    RGBColor(0xbc, 0xf6, 0x0c), RGBColor(0xfa, 0xbe, 0xbe), RGBColor(0x00, 0x80, 0x80), RGBColor(0xe6, 0xbe, 0xff),
+    RGBColor(0x9a, 0x63, 0x24), RGBColor(0xff, 0xfa, 0xc8), RGBColor(0x80, 0x00, 0x00), RGBColor(0xaa, 0xff, 0xc3),
+    RGBColor(0x80, 0x80, 0x00), RGBColor(0xff, 0xd8, 0xb1), RGBColor(0x00, 0x00, 0x75), RGBColor(0x80, 0x80, 0x80),
+    RGBColor(0x00, 0x00, 0x00),
+];
+
+const HIGHLIGHT: RGBColor = COLORS[0];
+const BLUE: RGBColor = COLORS[3];
+const NAVY: RGBColor = COLORS[18];
+const GRAY: RGBColor = COLORS[19];
+
+type PlotResult = std::result::Result<(), Box<dyn Error>>;
+
+/// Name, first and last column (exclusive) of an annotated region, and whether it is on the minus strand.
+type Gene = (String, usize, usize, bool);
+
+/// File format of the plots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlotFormat {
+    Svg,
+    Png,
+}
+
+impl PlotFormat {
+    pub const NAMES: [&'static str; 2] = ["svg", "png"];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PlotFormat::Svg => "svg",
+            PlotFormat::Png => "png",
+        }
+    }
+}
+
+impl FromStr for PlotFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "svg" => Ok(PlotFormat::Svg),
+            "png" => Ok(PlotFormat::Png),
+            _ => Err(format!("Unknown plot format, expected one of: {}", PlotFormat::NAMES.join(", "))),
+        }
+    }
+}
+
+impl fmt::Display for PlotFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+/// Draws a figure with the backend of the format, in `<stem>.<extension>`.
+///
+/// A figure that cannot be drawn is reported and skipped, so that the rest of the run goes on.
+macro_rules! render {
+    ($format:expr, $stem:expr, $size:expr, $draw:ident($($arg:expr),*)) => {{
+        let filename = format!("{}.{}", $stem, $format.extension());
+
+        let result = match $format {
+            PlotFormat::Svg => $draw(SVGBackend::new(&filename, $size).into_drawing_area(), $($arg),*),
+            PlotFormat::Png => $draw(BitMapBackend::new(&filename, $size).into_drawing_area(), $($arg),*),
+        };
+
+        if let Err(why) = result {
+            eprintln!("Could not draw {}: {}", filename, why);
+        }
+    }};
+}
+
+/// Style of the grid lines, light and thin like a dashed matplotlib grid.
+fn grid() -> ShapeStyle {
+    GRAY.mix(0.3).stroke_width(1)
+}
+
+fn text(size: u32, color: RGBColor, h: HPos, v: VPos) -> TextStyle<'static> {
+    (FONT, size).into_font().color(&color).pos(Pos::new(h, v))
+}
+
+/// Range of the values, widened by `margin` of its width on both sides (and made non-empty).
+fn padded<'a, I>(values: I, margin: f64) -> std::ops::Range<f64>
+    where I: IntoIterator<Item = &'a f64>
+{
+    let (min, max) = values.into_iter()
+        .filter(|x| x.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |acc, x| (acc.0.min(*x), acc.1.max(*x)));
+
+    match (min.is_finite(), max > min) {
+        (true, true) => (min - margin * (max - min))..(max + margin * (max - min)),
+        (true, false) => (min - 1.)..(max + 1.),
+        _ => 0. ..1.,
+    }
+}
+
+/// The range of an axis, or an error if one of its ends is not finite, which plotters cannot draw.
+fn finite(range: std::ops::Range<f64>) -> std::result::Result<std::ops::Range<f64>, Box<dyn Error>> {
+    if range.start.is_finite() && range.end.is_finite() {
+        Ok(range)
+    } else {
+        Err(format!("the axis goes from {} to {}", range.start, range.end).into())
+    }
+}
+
+/// A name from the metadata made safe to use in a file name, so that it cannot point outside the
+/// output directory.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|x| if x.is_alphanumeric() || x == '-' || x == '_' { x } else { '_' })
+        .collect()
+}
+
+/// Upper end of a y axis starting at zero, with some headroom.
+fn ceiling<'a, I>(values: I) -> f64
+    where I: IntoIterator<Item = &'a f64>
+{
+    let max = values.into_iter().filter(|x| x.is_finite()).fold(0., |acc: f64, x| acc.max(*x));
+
+    if max > 0. { max * 1.05 } else { 1. }
+}
+
+/// A legend of coloured squares, one entry per line from `(x, y)`.
+fn legend<DB>(area: &DrawingArea<DB, Shift>, entries: &[(String, ShapeStyle)], (x, y): (i32, i32)) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    for (i, (label, style)) in entries.iter().enumerate() {
+        let y = y + 18 * i as i32;
+
+        area.draw(&Rectangle::new([(x, y - 5), (x + 10, y + 5)], *style))?;
+        area.draw_text(label, &text(12, BLACK, HPos::Left, VPos::Center), (x + 16, y))?;
+    }
+
+    Ok(())
+}
+
+/// Categorical labels under an x axis going from -0.5 to `labels.len() - 0.5`.
+fn categories<DB>(root: &DrawingArea<DB, Shift>, chart: &ChartContext<DB, Cartesian2d<RangedCoordf64, RangedCoordf64>>, labels: &[String], y: f64) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    for (i, label) in labels.iter().enumerate() {
+        let (px, py) = chart.backend_coord(&(i as f64, y));
+        root.draw_text(label, &text(12, BLACK, HPos::Center, VPos::Top), (px, py + 6))?;
+    }
+
+    Ok(())
+}
+
+/// Annotated regions as arrows pointing in their direction, stacked in rows so that they do not overlap.
+fn tracks<DB>(area: &DrawingArea<DB, Shift>, genes: &[Gene], x: std::ops::Range<f64>, y_label_area: u32) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    let mut rows: Vec<usize> = Vec::new();
+    let mut placed: Vec<(&Gene, usize)> = Vec::new();
+    for gene in genes {
+        let row = rows.iter().position(|last| *last < gene.1).unwrap_or(rows.len());
+        if row == rows.len() {
+            rows.push(gene.2);
+        } else {
+            rows[row] = gene.2;
+        }
+
+        placed.push((gene, row));
+    }
+
+    let mut chart = ChartBuilder::on(area)
+        .margin(5)
+        .y_label_area_size(y_label_area)
+        .build_cartesian_2d(finite(x)?, finite((-(rows.len() as f64) + 0.4)..0.6)?)?;
+
+    for ((name, start, end, reverse), row) in placed {
+        let (start, end, y) = (*start as f64, *end as f64, -(row as f64));
+        let head = (end - start).min(50.);
+
+        let (tip, base, neck) = if *reverse { (start, end, start + head) } else { (end, start, end - head) };
+        let arrow = vec![
+            (base, y - 0.25), (neck, y - 0.25), (neck, y - 0.35), (tip, y),
+            (neck, y + 0.35), (neck, y + 0.25), (base, y + 0.25),
+        ];
+
+        chart.draw_series(std::iter::once(Polygon::new(arrow, BLUE.filled())))?;
+        chart.draw_series(std::iter::once(Text::new(name.clone(), ((start + end) / 2., y), text(10, WHITE, HPos::Center, VPos::Center))))?;
+    }
+
+    Ok(())
+}
+
+fn draw_efficiency<DB>(root: DrawingArea<DB, Shift>, efficiency: &[f64], genes: &[Gene]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let (upper, lower) = if genes.is_empty() {
+        (root.clone(), None)
+    } else {
+        let (upper, lower) = root.split_vertically(root.dim_in_pixel().1 * 5 / 6);
+        (upper, Some(lower))
+    };
+
+    let margin = 0.0075 * efficiency.len() as f64;
+    let x = -margin..(efficiency.len() as f64 + margin);
+
+    let mut chart = ChartBuilder::on(&upper)
+        .margin(5)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(finite(x.clone())?, finite(0. ..ceiling(efficiency))?)?;
+
+    chart.configure_mesh()
+        .disable_x_mesh()
+        .light_line_style(TRANSPARENT)
+        .bold_line_style(grid())
+        .x_desc("position")
+        .x_label_formatter(&|x| format!("{:.0}", x))
+        .y_desc("efficiency")
+        .label_style((FONT, 12))
+        .draw()?;
+
+    chart.draw_series(LineSeries::new(efficiency.iter().enumerate().map(|(i, y)| (i as f64, *y)), RED.stroke_width(1)))?;
+
+    if let Some(lower) = lower {
+        tracks(&lower, genes, x, 60)?;
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// Annotated regions falling in the analysed range, in columns of the range.
+fn genes(annotation: Option<&Annotation>, range: (i32, i32)) -> Vec<Gene> {
+    let mut genes: Vec<Gene> = Vec::new();
+    if let Some(annotation) = annotation {
+        for region in annotation.get_regions() {
+            let columns = region.columns(range);
+
+            if let (Some(start), Some(end)) = (columns.iter().min(), columns.iter().max()) {
+                genes.push((region.name.clone(), *start, *end + 1, region.strand == Strand::Reverse));
+            }
+        }
+    }
+
+    genes
+}
+
+/// Efficiency along the analysed range, with the annotated regions drawn as tracks underneath.
+pub fn efficiency2graph(path: String, bam: &Bam, annotation: Option<&Annotation>, format: PlotFormat) {
+    let efficiency = bam.matrices.get_efficiency().to_vec();
+    let stem = path + "/" + bam.name.as_str() + "-efficiency";
+    let genes = genes(annotation, bam.get_range());
+
+    let size = if genes.is_empty() { (1000, 500) } else { (1000, 600) };
+    render!(format, stem, size, draw_efficiency(&efficiency, &genes));
+}
+
+fn draw_efficiency_heatmap<DB>(root: DrawingArea<DB, Shift>, names: &[String], values: &[Vec<f64>], bins: &[(usize, usize)], max: f64, genes: &[Gene]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let (width, height) = root.dim_in_pixel();
+    let (area, side) = root.split_horizontally(width - 110);
+    let track_height = if genes.is_empty() { 0 } else { 100 };
+    let (upper, lower) = area.split_vertically(height - track_height);
+
+    // the tracks share the label area of the samples, to stay aligned with the columns
+    let label_area = names.iter().map(|x| 7 * x.chars().count() as u32 + 10).max().unwrap_or(0).max(60);
+    let n = names.len();
+    let length = bins.last().map_or(0, |x| x.1);
+    let x = 0. ..length as f64;
+
+    let flipped = |y: &f64| {
+        let i = y.round();
+        if (y - i).abs() < 1e-6 && i >= 0. && (i as usize) < n { names[n - 1 - i as usize].clone() } else { String::new() }
+    };
+
+    let mut chart = ChartBuilder::on(&upper)
+        .margin(5)
+        .x_label_area_size(40)
+        .y_label_area_size(label_area)
+        .build_cartesian_2d(finite(x.clone())?, finite(-0.5..(n as f64 - 0.5))?)?;
+
+    chart.configure_mesh()
+        .disable_mesh()
+        .x_desc("position")
+        .x_label_formatter(&|x| format!("{:.0}", x))
+        .y_labels(n)
+        .y_label_formatter(&flipped)
+        .label_style((FONT, 12))
+        .draw()?;
+
+    for (i, values) in values.iter().enumerate() {
+        let row = (n - 1 - i) as f64;
+
+        chart.draw_series(bins.iter().zip(values).filter(|(_, v)| !v.is_nan()).map(|((start, end), v)| {
+            Rectangle::new([(*start as f64, row - 0.5), (*end as f64, row + 0.5)], Scale::Viridis.color(*v, 0., max).filled())
+        }))?;
+    }
+
+    colorbar(&side, Scale::Viridis, (0., max), "efficiency", (5, 40 + track_height))?;
+
+    if !genes.is_empty() {
+        tracks(&lower, genes, x, label_area)?;
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// Efficiency of every sample along the analysed range, one row per sample, with the annotated regions
+/// drawn as tracks underneath.
+///
+/// Positions are averaged in bins when the range is wider than the figure. The samples are in the
+/// order of the input, or in the order of the leaves of their UPGMA tree when `cluster` is set, so that
+/// similar samples are next to each other.
+pub fn efficiency_heatmap2graph(path: String, bamdata: &BamData, cluster: bool, format: PlotFormat) {
+    let names = bamdata.get_names();
+    let tree = if cluster && names.len() > 2 { bamdata.upgma().ok() } else { None };
+    let order: Vec<usize> = match tree {
+        Some(tree) => tree.leaves()
+            .iter()
+            .filter_map(|leaf| names.iter().position(|x| x == leaf))
+            .collect(),
+        None => (0..names.len()).collect(),
+    };
+
+    let efficiency: Vec<Vec<f64>> = bamdata.into_iter().map(|bam| bam.matrices.get_efficiency().to_vec()).collect();
+    let bins = bins(efficiency.first().map_or(0, |x| x.len()), 800);
+    let values: Vec<Vec<f64>> = order.iter().map(|i| bin_means(&efficiency[*i], &bins)).collect();
+    let names: Vec<String> = order.iter().map(|i| names[*i].clone()).collect();
+
+    let max = values.iter().flatten().filter(|x| x.is_finite()).fold(0., |acc: f64, x| acc.max(*x));
+    let max = if max > 0. { max } else { 1. };
+
+    let range = bamdata.into_iter().next().map_or((0, 0), |bam| bam.get_range());
+    let genes = genes(bamdata.get_annotation(), range);
+
+    let stem = path + "/efficiency-heatmap";
+    let height = 60 + 16 * names.len().max(10) as u32 + if genes.is_empty() { 0 } else { 100 };
+    render!(format, stem, (1100, height), draw_efficiency_heatmap(&names, &values, &bins, max, &genes));
+}
+
+type Line = (Vec<f64>, RGBColor);
+
+fn draw_efficiency_overlay<DB>(root: DrawingArea<DB, Shift>, lines: &[Line], entries: &[(String, ShapeStyle)], genes: &[Gene]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let (width, height) = root.dim_in_pixel();
+    let (area, side) = root.split_horizontally(width - 160);
+    let (upper, lower) = if genes.is_empty() {
+        (area.clone(), None)
+    } else {
+        let (upper, lower) = area.split_vertically(height * 5 / 6);
+        (upper, Some(lower))
+    };
+
+    let length = lines.iter().map(|(x, _)| x.len()).max().unwrap_or(0);
+    let margin = 0.0075 * length as f64;
+    let x = -margin..(length as f64 + margin);
+
+    let mut chart = ChartBuilder::on(&upper)
+        .margin(5)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(finite(x.clone())?, finite(0. ..ceiling(lines.iter().flat_map(|(x, _)| x)))?)?;
+
+    chart.configure_mesh()
+        .disable_x_mesh()
+        .light_line_style(TRANSPARENT)
+        .bold_line_style(grid())
+        .x_desc("position")
+        .x_label_formatter(&|x| format!("{:.0}", x))
+        .y_desc("smoothed efficiency")
+        .label_style((FONT, 12))
+        .draw()?;
+
+    for (values, color) in lines {
+        // one line per covered stretch, so that the gaps stay visible
+        let mut stretch: Vec<(f64, f64)> = Vec::new();
+        for (i, y) in values.iter().enumerate().chain(std::iter::once((values.len(), &f64::NAN))) {
+            if y.is_nan() {
+                if !stretch.is_empty() {
+                    chart.draw_series(LineSeries::new(stretch.drain(..), color.mix(0.8).stroke_width(1)))?;
+                }
+            } else {
+                stretch.push((i as f64, *y));
+            }
+        }
+    }
+
+    legend(&side, entries, (10, 20))?;
+
+    if let Some(lower) = lower {
+        tracks(&lower, genes, x, 60)?;
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// Efficiency of all the samples along the analysed range, smoothed over `window` positions and drawn
+/// on top of each other, with the annotated regions drawn as tracks underneath.
+///
+/// Lines are coloured by group when the samples have groups in the metadata, and by sample otherwise.
+pub fn efficiency_overlay2graph(path: String, bamdata: &BamData, window: usize, format: PlotFormat) {
+    let names = bamdata.get_names();
+    let groups = bamdata.get_groups();
+
+    let (colors, entries): (Vec<RGBColor>, Vec<(String, ShapeStyle)>) = if groups.iter().any(|x| x.is_some()) {
+        let mut distinct: Vec<&Option<String>> = Vec::new();
+        for group in &groups {
+            if !distinct.contains(&group) {
+                distinct.push(group);
+            }
+        }
+
+        let color = |group: &Option<String>| COLORS[distinct.iter().position(|x| *x == group).unwrap() % COLORS.len()];
+        let entries = distinct.iter()
+            .map(|x| (x.as_deref().unwrap_or("no group").to_string(), color(x).filled()))
+            .collect();
+
+        (groups.iter().map(color).collect(), entries)
+    } else {
+        let colors: Vec<RGBColor> = (0..names.len()).map(|i| COLORS[i % COLORS.len()]).collect();
+        // beyond the number of colours, a legend would be ambiguous
+        let entries = if names.len() <= COLORS.len() {
+            names.iter().zip(&colors).map(|(x, c)| (x.clone(), c.filled())).collect()
+        } else {
+            Vec::new()
+        };
+
+        (colors, entries)
+    };
+
+    let lines: Vec<Line> = bamdata.into_iter()
+        .zip(colors)
+        .map(|(bam, color)| (moving_average(&bam.matrices.get_efficiency().to_vec(), window), color))
+        .collect();
+
+    let range = bamdata.into_iter().next().map_or((0, 0), |bam| bam.get_range());
+    let genes = genes(bamdata.get_annotation(), range);
+
+    let stem = path + "/efficiency-overlay";
+    let size = if genes.is_empty() { (1100, 500) } else { (1100, 600) };
+    render!(format, stem, size, draw_efficiency_overlay(&lines, &entries, &genes));
+}
+
+fn draw_depth<DB>(root: DrawingArea<DB, Shift>, depth: &[u64], threshold: f64, genes: &[Gene]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let (upper, lower) = if genes.is_empty() {
+        (root.clone(), None)
+    } else {
+        let (upper, lower) = root.split_vertically(root.dim_in_pixel().1 * 5 / 6);
+        (upper, Some(lower))
+    };
+
+    let margin = 0.0075 * depth.len() as f64;
+    let x = -margin..(depth.len() as f64 + margin);
+    let max = depth.iter().copied().max().unwrap_or(0).max(1) as f64;
+
+    let mut chart = ChartBuilder::on(&upper)
+        .margin(5)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(finite(x.clone())?, finite(0.8..max * 2.)?.log_scale())?;
+
+    chart.configure_mesh()
+        .disable_x_mesh()
+        .light_line_style(TRANSPARENT)
+        .bold_line_style(grid())
+        .x_desc("position")
+        .x_label_formatter(&|x| format!("{:.0}", x))
+        .y_desc("depth")
+        .y_label_formatter(&|y| format!("{:.0}", y))
+        .label_style((FONT, 12))
+        .draw()?;
+
+    // positions without reads cannot be drawn on a log axis, so they break the line
+    let mut segment: Vec<(f64, f64)> = Vec::new();
+    for (i, y) in depth.iter().enumerate().chain(std::iter::once((depth.len(), &0))) {
+        if *y > 0 {
+            segment.push((i as f64, *y as f64));
+        } else if !segment.is_empty() {
+            chart.draw_series(LineSeries::new(segment.drain(..), BLUE.stroke_width(1)))?;
+        }
+    }
+
+    if threshold > 0. {
+        let level = threshold * max;
+        chart.draw_series(DashedLineSeries::new(vec![(x.start, level), (x.end, level)], 8, 4, HIGHLIGHT.stroke_width(2)))?
+            .label(format!("threshold ({} of the maximum)", threshold))
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], HIGHLIGHT.stroke_width(2)));
+
+        chart.configure_series_labels()
+            .position(SeriesLabelPosition::LowerRight)
+            .background_style(WHITE.mix(0.8))
+            .border_style(GRAY)
+            .label_font((FONT, 12))
+            .draw()?;
+    }
+
+    if let Some(lower) = lower {
+        tracks(&lower, genes, x, 60)?;
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// Depth along the analysed range on a log axis, with the coverage threshold and the annotated regions.
+pub fn depth2graph(path: String, bam: &Bam, threshold: f64, annotation: Option<&Annotation>, format: PlotFormat) {
+    let depth = bam.matrices.get_depth().to_vec();
+    let stem = path + "/" + bam.name.as_str() + "-depth";
+    let genes = genes(annotation, bam.get_range());
+
+    let size = if genes.is_empty() { (1000, 500) } else { (1000, 600) };
+    render!(format, stem, size, draw_depth(&depth, threshold, &genes));
+}
+
+type Window = (Vec<usize>, Vec<f64>, Vec<f64>);
+
+fn draw_dnds<DB>(root: DrawingArea<DB, Shift>, names: &[String], windows: &[Window]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let panels = root.split_evenly((names.len().max(1), 1));
+    for (k, (panel, (name, (x, pin, pis)))) in panels.iter().zip(names.iter().zip(windows.iter())).enumerate() {
+        let last = k + 1 == names.len();
+        let codons = x.iter().map(|x| *x as f64).collect::<Vec<f64>>();
+
+        let mut chart = ChartBuilder::on(panel)
+            .margin(5)
+            .caption(name, (FONT, 14))
+            .x_label_area_size(if last { 40 } else { 20 })
+            .y_label_area_size(60)
+            .build_cartesian_2d(finite(padded(&codons, 0.0075))?, finite(0. ..ceiling(pin.iter().chain(pis.iter())))?)?;
+
+        let mut mesh = chart.configure_mesh();
+        mesh.disable_x_mesh()
+            .light_line_style(TRANSPARENT)
+            .bold_line_style(grid())
+            .y_desc("π")
+            .label_style((FONT, 12));
+        if last {
+            mesh.x_desc("codon").x_label_formatter(&|x| format!("{:.0}", x));
+        }
+        mesh.draw()?;
+
+        chart.draw_series(LineSeries::new(codons.iter().zip(pin.iter()).map(|(x, y)| (*x, *y)), RED.stroke_width(1)))?;
+        chart.draw_series(LineSeries::new(codons.iter().zip(pis.iter()).map(|(x, y)| (*x, *y)), BLUE.stroke_width(1)))?;
+
+        if k == 0 {
+            let (x, y) = chart.backend_coord(&(chart.x_range().end, chart.y_range().end));
+            legend(&root, &[("πN".to_string(), RED.filled()), ("πS".to_string(), BLUE.filled())], (x - 50, y + 10))?;
+        }
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// πN and πS of every coding region in sliding windows of `width` codons, one panel per region.
+pub fn dnds2graph(path: String, bam: &Bam, width: usize, format: PlotFormat) {
+    let mut names: Vec<String> = Vec::new();
+    let mut windows: Vec<Window> = Vec::new();
+    for selection in bam.selection() {
+        let window = selection.sliding_window(width, 1);
+
+        names.push(selection.name.clone());
+        windows.push((
+            window.iter().map(|(x, _)| *x).collect(),
+            window.iter().map(|(_, d)| d.pi_n()).collect(),
+            window.iter().map(|(_, d)| d.pi_s()).collect(),
+        ));
+    }
+
+    let stem = path + "/" + bam.name.as_str() + "-dnds";
+    render!(format, stem, (1000, 250 * names.len().max(1) as u32), draw_dnds(&names, &windows));
+}
+
+fn draw_alphadiv<DB>(root: DrawingArea<DB, Shift>, labels: &[String], alpha: &[f64], haplotypes: &[f64]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let n = labels.len() as f64;
+    let mut chart = ChartBuilder::on(&root)
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(60)
+        .build_cartesian_2d(finite(-0.6..(n - 0.4))?, finite(0. ..ceiling(alpha.iter().chain(haplotypes.iter())))?)?;
+
+    chart.configure_mesh()
+        .disable_x_mesh()
+        .x_labels(0)
+        .light_line_style(TRANSPARENT)
+        .bold_line_style(grid())
+        .y_desc("α-diversity")
+        .label_style((FONT, 12))
+        .draw()?;
+
+    let color = |i: usize| COLORS[i % COLORS.len()];
+
+    if haplotypes.is_empty() {
+        chart.draw_series(alpha.iter().enumerate().map(|(i, y)| {
+            Rectangle::new([(i as f64 - 0.4, 0.), (i as f64 + 0.4, *y)], color(i).filled())
+        }))?;
+    } else {
+        chart.draw_series(alpha.iter().enumerate().map(|(i, y)| {
+            Rectangle::new([(i as f64 - 0.4, 0.), (i as f64, *y)], color(i).filled())
+        }))?;
+        chart.draw_series(haplotypes.iter().enumerate().filter(|(_, y)| y.is_finite()).map(|(i, y)| {
+            Rectangle::new([(i as f64, 0.), (i as f64 + 0.4, *y)], color(i).mix(0.5).filled())
+        }))?;
+
+        let (x, y) = chart.backend_coord(&(n - 0.4, chart.y_range().end));
+        legend(&root, &[("α-diversity".to_string(), GRAY.filled()), ("haplotype entropy".to_string(), GRAY.mix(0.5).filled())], (x - 140, y + 10))?;
+    }
+
+    categories(&root, &chart, labels, 0.)?;
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// α-diversity of every sample, next to its haplotype entropy when haplotypes were reconstructed.
+pub fn alphadiv2graph(path: String, bamdata: &BamData, format: PlotFormat) {
+    let alpha = bamdata.alpha_diversity().to_vec();
+    let labels = bamdata.get_names();
+    let stem = path + "/alpha-diversity";
+
+    // haplotype entropy is drawn beside α-diversity when haplotypes were reconstructed
+    let haplotypes: Vec<f64> = bamdata.into_iter()
+        .map(|bam| bam.haplotype_complexity().map(|x| x.entropy).unwrap_or(f64::NAN))
+        .collect();
+    let haplotypes = if haplotypes.iter().any(|x| !x.is_nan()) { haplotypes } else { Vec::new() };
+
+    render!(format, stem, (800, 500), draw_alphadiv(&labels, &alpha, &haplotypes));
+}
+
+fn draw_betadiv<DB>(root: DrawingArea<DB, Shift>, title: &str, labels: &[String], positions: &[(f64, f64)]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let xs = positions.iter().map(|x| x.0).collect::<Vec<f64>>();
+    let ys = positions.iter().map(|x| x.1).collect::<Vec<f64>>();
+
+    let mut chart = ChartBuilder::on(&root)
+        .margin(20)
+        .caption(title, (FONT, 16))
+        .build_cartesian_2d(finite(padded(&xs, 0.15))?, finite(padded(&ys, 0.15))?)?;
+
+    for (i, a) in positions.iter().enumerate() {
+        chart.draw_series(positions.iter().skip(i + 1).map(|b| PathElement::new(vec![*a, *b], GRAY.mix(0.5))))?;
+    }
+
+    chart.draw_series(positions.iter().enumerate().map(|(i, x)| Circle::new(*x, 12, COLORS[i % COLORS.len()].filled())))?;
+    for (label, position) in labels.iter().zip(positions.iter()) {
+        root.draw_text(label, &text(12, BLACK, HPos::Center, VPos::Center), chart.backend_coord(position))?;
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// β-diversity between samples as a graph whose nodes are laid out so that their distances follow
+/// the β-diversity matrix, using the first two axes of its principal coordinates.
+pub fn betadiv2graph(path: String, bamdata: &BamData, format: PlotFormat) {
+    let ordination = match bamdata.ordination() {
+        Ok(ordination) => ordination,
+        Err(why) => return eprintln!("Could not draw the β-diversity graph: {}", why),
+    };
+    let coordinates = ordination.get_coordinates();
+    let positions: Vec<(f64, f64)> = coordinates.rows()
+        .into_iter()
+        .map(|x| (x.get(0).copied().unwrap_or(0.), x.get(1).copied().unwrap_or(0.)))
+        .collect();
+
+    let labels = bamdata.get_names();
+    let title = format!("β-diversity ({})", bamdata.get_distance().name());
+    let stem = path + "/beta-diversity";
+
+    render!(format, stem, (640, 480), draw_betadiv(&title, &labels, &positions));
+}
+
+fn draw_spectrum<DB>(root: DrawingArea<DB, Shift>, labels: &[String], classes: &[String], fractions: &[Vec<f64>]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let (area, side) = root.split_horizontally(root.dim_in_pixel().0 * 4 / 5);
+
+    let n = labels.len() as f64;
+    let mut chart = ChartBuilder::on(&area)
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(60)
+        .build_cartesian_2d(finite(-0.6..(n - 0.4))?, 0. ..1.05)?;
+
+    chart.configure_mesh()
+        .disable_x_mesh()
+        .x_labels(0)
+        .light_line_style(TRANSPARENT)
+        .bold_line_style(grid())
+        .y_desc("fraction of substitutions")
+        .label_style((FONT, 12))
+        .draw()?;
+
+    let mut bottom = vec![0.; labels.len()];
+    for (k, _) in classes.iter().enumerate() {
+        let color = COLORS[k % COLORS.len()];
+
+        chart.draw_series(fractions.iter().zip(bottom.iter()).enumerate().map(|(i, (f, b))| {
+            Rectangle::new([(i as f64 - 0.4, *b), (i as f64 + 0.4, b + f[k])], color.filled())
+        }))?;
+
+        for (b, f) in bottom.iter_mut().zip(fractions.iter()) {
+            *b += f[k];
+        }
+    }
+
+    categories(&root, &chart, labels, 0.)?;
+
+    let entries = classes.iter()
+        .enumerate()
+        .map(|(k, x)| (x.clone(), COLORS[k % COLORS.len()].filled()))
+        .collect::<Vec<(String, ShapeStyle)>>();
+    let top = (side.dim_in_pixel().1 as i32 - 18 * entries.len() as i32) / 2;
+    legend(&side, &entries, (10, top))?;
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// Substitution spectrum of every sample, as stacked fractions of the substitution classes.
+pub fn spectrum2graph(path: String, bamdata: &BamData, format: PlotFormat) {
+    let labels = bamdata.get_names();
+    let mut classes: Vec<String> = Vec::new();
+    let mut fractions: Vec<Vec<f64>> = Vec::new();
+    for bam in bamdata {
+        let spectrum = bam.spectrum().classes();
+        let total = spectrum.iter().map(|(_, x)| *x).sum::<u64>().max(1) as f64;
+
+        classes = spectrum.iter().map(|(c, _)| c.clone()).collect();
+        fractions.push(spectrum.iter().map(|(_, x)| *x as f64 / total).collect());
+    }
+
+    let stem = path + "/substitution-spectrum";
+    render!(format, stem, (900, 500), draw_spectrum(&labels, &classes, &fractions));
+}
+
+fn draw_pcoa<DB>(root: DrawingArea<DB, Shift>, labels: &[String], groups: &[String], points: &[(f64, f64)], explained: (f64, f64)) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let xs = points.iter().map(|x| x.0).collect::<Vec<f64>>();
+    let ys = points.iter().map(|x| x.1).collect::<Vec<f64>>();
+
+    let mut chart = ChartBuilder::on(&root)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(finite(padded(&xs, 0.1))?, finite(padded(&ys, 0.1))?)?;
+
+    chart.configure_mesh()
+        .light_line_style(TRANSPARENT)
+        .bold_line_style(grid())
+        .x_desc(format!("PCo1 ({:.1}%)", 100. * explained.0))
+        .y_desc(format!("PCo2 ({:.1}%)", 100. * explained.1))
+        .label_style((FONT, 12))
+        .draw()?;
+
+    let mut distinct: Vec<&String> = Vec::new();
+    for group in groups {
+        if !distinct.contains(&group) {
+            distinct.push(group);
+        }
+    }
+
+    for (i, group) in distinct.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let members = points.iter().zip(groups.iter()).filter(|(_, g)| g == group).map(|(p, _)| *p);
+
+        chart.draw_series(members.map(|p| Circle::new(p, 4, color.filled())))?;
+    }
+
+    for (label, point) in labels.iter().zip(points.iter()) {
+        let (x, y) = chart.backend_coord(point);
+        root.draw_text(label, &text(11, BLACK, HPos::Left, VPos::Bottom), (x + 4, y - 4))?;
+    }
+
+    if distinct.len() > 1 {
+        let entries = distinct.iter()
+            .enumerate()
+            .map(|(i, g)| (if g.is_empty() { "no group".to_string() } else { g.to_string() }, COLORS[i % COLORS.len()].filled()))
+            .collect::<Vec<(String, ShapeStyle)>>();
+        let (x, y) = chart.backend_coord(&(chart.x_range().end, chart.y_range().end));
+        legend(&root, &entries, (x - 100, y + 10))?;
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// First two principal coordinates of the β-diversity matrix, coloured by group.
+pub fn pcoa2graph(path: String, bamdata: &BamData, format: PlotFormat) {
+    let ordination = match bamdata.ordination() {
+        Ok(ordination) => ordination,
+        Err(why) => return eprintln!("Could not draw the PCoA: {}", why),
+    };
+    let coordinates = ordination.get_coordinates();
+    let explained = ordination.proportion_explained().to_vec();
+
+    let points: Vec<(f64, f64)> = coordinates.rows()
+        .into_iter()
+        .map(|x| (x.get(0).copied().unwrap_or(0.), x.get(1).copied().unwrap_or(0.)))
+        .collect();
+    let explained = (explained.first().copied().unwrap_or(0.), explained.get(1).copied().unwrap_or(0.));
+
+    let labels = bamdata.get_names();
+    let groups: Vec<String> = bamdata.get_groups().into_iter().map(|x| x.unwrap_or_default()).collect();
+    let stem = path + "/pcoa";
+
+    render!(format, stem, (640, 480), draw_pcoa(&labels, &groups, &points, explained));
+}
+
+fn draw_dendrogram<DB>(root: DrawingArea<DB, Shift>, title: &str, segments: &[[f64; 4]], leaves: &[(String, String, f64, f64)]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    // leaves are numbered from the top, so y is flipped
+    let xs = segments.iter().flat_map(|x| vec![x[0], x[2]]).collect::<Vec<f64>>();
+    let ys = segments.iter().flat_map(|x| vec![-x[1], -x[3]]).chain(leaves.iter().map(|x| -x.3)).collect::<Vec<f64>>();
+    let x_max = xs.iter().cloned().fold(0., f64::max);
+    if !x_max.is_finite() {
+        return Err(format!("the tree is {} high", x_max).into());
+    }
+
+    let mut chart = ChartBuilder::on(&root)
+        .margin(10)
+        .margin_right(160)
+        .caption(title, (FONT, 16))
+        .x_label_area_size(40)
+        .y_label_area_size(10)
+        .build_cartesian_2d(finite(0. ..(if x_max > 0. { x_max * 1.02 } else { 1. }))?, finite(padded(&ys, 0.05))?)?;
+
+    chart.configure_mesh()
+        .disable_y_mesh()
+        .disable_y_axis()
+        .light_line_style(TRANSPARENT)
+        .bold_line_style(grid())
+        .x_desc("Distance")
+        .label_style((FONT, 12))
+        .draw()?;
+
+    chart.draw_series(segments.iter().map(|x| PathElement::new(vec![(x[0], -x[1]), (x[2], -x[3])], BLACK.stroke_width(1))))?;
+
+    let mut groups: Vec<&String> = Vec::new();
+    for (_, group, _, _) in leaves {
+        if !group.is_empty() && !groups.contains(&group) {
+            groups.push(group);
+        }
+    }
+
+    for (label, group, x, y) in leaves {
+        let (color, label) = match groups.iter().position(|g| *g == group) {
+            Some(i) => (COLORS[i % COLORS.len()], format!("{} ({})", label, group)),
+            None => (BLACK, label.clone()),
+        };
+
+        let (px, py) = chart.backend_coord(&(*x, -y));
+        root.draw_text(&label, &text(11, color, HPos::Left, VPos::Center), (px + 4, py))?;
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// A tree of the samples as a rectangular dendrogram, with leaves coloured by group.
+pub fn dendrogram2graph(path: String, bamdata: &BamData, tree: &Tree, name: &str, format: PlotFormat) {
+    let layout = tree.layout();
+
+    let names = bamdata.get_names();
+    let groups = bamdata.get_groups();
+    let leaves: Vec<(String, String, f64, f64)> = layout.leaves.iter()
+        .map(|(label, x, y)| {
+            let group = names.iter()
+                .position(|n| n == label)
+                .and_then(|i| groups[i].clone())
+                .unwrap_or_default();
+
+            (label.clone(), group, *x, *y)
+        })
+        .collect();
+
+    let title = format!("{} tree ({})", name, bamdata.get_distance().name());
+    let stem = format!("{}/{}-tree", path, name.to_lowercase());
+    let height = (35 * leaves.len() as u32).max(240) + 80;
+
+    render!(format, stem, (640, height), draw_dendrogram(&title, &layout.segments, &leaves));
+}
+
+type Panel = (Vec<f64>, Vec<bool>);
+
+fn draw_manhattan<DB>(root: DrawingArea<DB, Shift>, title: &str, x: &[f64], labels: &[String], panels: &[(&str, Panel)]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let mut genes: Vec<(&String, f64, f64)> = Vec::new();
+    for (position, label) in x.iter().zip(labels.iter()).filter(|(_, l)| !l.is_empty()) {
+        match genes.iter_mut().find(|(g, _, _)| *g == label) {
+            Some((_, start, end)) => {
+                *start = start.min(*position);
+                *end = end.max(*position);
+            },
+            None => genes.push((label, *position, *position)),
+        }
+    }
+    let shade = |label: &String| genes.iter().position(|(g, _, _)| *g == label).map(|i| if i % 2 == 0 { BLUE } else { NAVY });
+
+    let x_range = padded(x, 0.0075);
+    let areas = root.split_evenly((panels.len(), 1));
+
+    for (k, (area, (name, (p_values, significant)))) in areas.iter().zip(panels.iter()).enumerate() {
+        let (first, last) = (k == 0, k + 1 == panels.len());
+        let y = p_values.iter().map(|p| -p.max(1e-300).log10()).collect::<Vec<f64>>();
+
+        let mut builder = ChartBuilder::on(area);
+        builder.margin(5)
+            .x_label_area_size(if last { 40 } else { 10 })
+            .y_label_area_size(60);
+        if first {
+            builder.caption(title, (FONT, 16)).margin_top(20);
+        }
+        let mut chart = builder.build_cartesian_2d(finite(x_range.clone())?, finite(0. ..ceiling(&y))?)?;
+
+        let mut mesh = chart.configure_mesh();
+        mesh.disable_x_mesh()
+            .light_line_style(TRANSPARENT)
+            .bold_line_style(grid())
+            .y_desc(format!("-log10(p), {}", name))
+            .label_style((FONT, 12));
+        if last {
+            mesh.x_desc("position").x_label_formatter(&|x| format!("{:.0}", x));
+        } else {
+            mesh.x_labels(0);
+        }
+        mesh.draw()?;
+
+        let top = chart.y_range().end;
+        chart.draw_series(genes.iter().enumerate().map(|(i, (_, start, end))| {
+            let color = if i % 2 == 0 { BLUE } else { NAVY };
+            Rectangle::new([(*start, 0.), (*end, top)], color.mix(0.08).filled())
+        }))?;
+
+        chart.draw_series(x.iter().zip(y.iter()).zip(significant.iter().zip(labels.iter())).filter(|((_, y), _)| y.is_finite()).map(|((x, y), (s, l))| {
+            let color = if *s { HIGHLIGHT } else { shade(l).unwrap_or(GRAY) };
+            Circle::new((*x, *y), 2, color.filled())
+        }))?;
+
+        if first {
+            for (i, (gene, start, end)) in genes.iter().enumerate() {
+                let (px, py) = chart.backend_coord(&((start + end) / 2., top));
+                let color = if i % 2 == 0 { BLUE } else { NAVY };
+                root.draw_text(gene, &text(10, color, HPos::Center, VPos::Bottom), (px, py - 2))?;
+            }
+        }
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// Manhattan plot of the position tests between two groups, with the annotated regions shaded.
+pub fn manhattan2graph(path: String, bamdata: &BamData, differential: &DifferentialDiversity, format: PlotFormat) {
+    let first = bamdata.into_iter().next().map(|x| x.get_range().0 as usize + 1).unwrap_or(1);
+    let regions = bamdata.column_regions();
+
+    let positions = differential.get_positions();
+    let x: Vec<f64> = positions.iter().map(|p| (first + p.column) as f64).collect();
+    let labels: Vec<String> = positions.iter().map(|p| regions[p.column].clone()).collect();
+    let panels = [
+        ("G-test", (positions.iter().map(|p| p.g_p_value).collect(), positions.iter().map(|p| p.g_q_value <= 0.05).collect())),
+        ("Mann-Whitney", (positions.iter().map(|p| p.u_p_value).collect(), positions.iter().map(|p| p.u_q_value <= 0.05).collect())),
+    ];
+
+    let title = format!("{} vs {}", differential.a, differential.b);
+    let stem = path + "/differential";
+
+    render!(format, stem, (1000, 600), draw_manhattan(&title, &x, &labels, &panels));
+}
+
+fn draw_trajectories<DB>(root: DrawingArea<DB, Shift>, timepoints: &[f64], lines: &[(String, Vec<f64>)]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let (area, side) = root.split_horizontally(root.dim_in_pixel().0 * 4 / 5);
+
+    let mut chart = ChartBuilder::on(&area)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(finite(padded(timepoints, 0.05))?, 0. ..1.)?;
+
+    chart.configure_mesh()
+        .light_line_style(TRANSPARENT)
+        .bold_line_style(grid())
+        .x_desc("timepoint")
+        .y_desc("allele frequency")
+        .label_style((FONT, 12))
+        .draw()?;
+
+    for (i, (_, frequencies)) in lines.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let points = timepoints.iter()
+            .zip(frequencies.iter())
+            .filter(|(_, f)| !f.is_nan())
+            .map(|(t, f)| (*t, *f))
+            .collect::<Vec<(f64, f64)>>();
+
+        chart.draw_series(LineSeries::new(points.clone(), color.stroke_width(2)))?;
+        chart.draw_series(points.into_iter().map(|p| Circle::new(p, 4, color.filled())))?;
+    }
+
+    let entries = lines.iter()
+        .enumerate()
+        .map(|(i, (label, _))| (label.clone(), COLORS[i % COLORS.len()].filled()))
+        .collect::<Vec<(String, ShapeStyle)>>();
+    legend(&side, &entries, (10, 20))?;
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// Frequencies of the `top` minor alleles changing the most in the samples of one individual.
+pub fn trajectories2graph(path: String, bamdata: &BamData, trajectories: &Trajectories, top: usize, format: PlotFormat) {
+    let first = bamdata.into_iter().next().map(|x| x.get_range().0 as usize + 1).unwrap_or(1);
+    let timepoints: Vec<f64> = trajectories.samples.iter().map(|x| x.1).collect();
+
+    let lines: Vec<(String, Vec<f64>)> = trajectories.top(top)
+        .into_iter()
+        .map(|x| {
+            let label = format!("{}{}{}", NUCLEOTIDES[x.baseline], first + x.column, NUCLEOTIDES[x.allele]);
+
+            (label, x.frequencies())
+        })
+        .collect();
+
+    let stem = format!("{}/{}-trajectories", path, file_name(&trajectories.series));
+    render!(format, stem, (800, 480), draw_trajectories(&timepoints, &lines));
+}
+
+type Measure = (&'static str, Vec<f64>, Regression);
+
+fn draw_rate<DB>(root: DrawingArea<DB, Shift>, labels: &[String], years: &[f64], measures: &[Measure]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let panels = root.split_evenly((1, measures.len()));
+    let last = years.iter().cloned().fold(0., f64::max);
+
+    for ((area, color), (name, values, regression)) in panels.iter().zip([HIGHLIGHT, BLUE]).zip(measures.iter()) {
+        let fitted = [regression.intercept, regression.intercept + regression.rate * last];
+        let title = format!("{:.2e} subs/site/year [{:.2e}, {:.2e}]", regression.rate, regression.ci.0, regression.ci.1);
+
+        let mut chart = ChartBuilder::on(area)
+            .margin(10)
+            .caption(title, (FONT, 13))
+            .x_label_area_size(40)
+            .y_label_area_size(70)
+            .build_cartesian_2d(finite(padded(years.iter().chain([0.].iter()), 0.05))?, finite(padded(values.iter().chain(fitted.iter()), 0.1))?)?;
+
+        chart.configure_mesh()
+            .light_line_style(TRANSPARENT)
+            .bold_line_style(grid())
+            .x_desc("years since first sample")
+            .y_desc(*name)
+            .y_label_formatter(&|y| format!("{:.2e}", y))
+            .label_style((FONT, 12))
+            .draw()?;
+
+        chart.draw_series(DashedLineSeries::new(vec![(0., fitted[0]), (last, fitted[1])], 8, 4, color.stroke_width(2)))?;
+        chart.draw_series(years.iter().zip(values.iter()).map(|(x, y)| Circle::new((*x, *y), 4, color.filled())))?;
+
+        for (label, point) in labels.iter().zip(years.iter().zip(values.iter())) {
+            let (x, y) = chart.backend_coord(&(*point.0, *point.1));
+            root.draw_text(label, &text(10, BLACK, HPos::Left, VPos::Bottom), (x + 4, y - 4))?;
+        }
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// Root-to-tip style regressions of divergence and diversity on the time since the first sample.
+pub fn rate2graph(path: String, rate: &EvolutionaryRate, format: PlotFormat) {
+    let years: Vec<f64> = rate.samples.iter().map(|x| x.1).collect();
+    let labels: Vec<String> = rate.samples.iter().map(|x| x.0.clone()).collect();
+    let measures = [
+        ("divergence", rate.divergence(), rate.divergence_rate),
+        ("diversity", rate.diversity(), rate.diversity_rate),
+    ];
+
+    let stem = format!("{}/{}-rate", path, file_name(&rate.series));
+    render!(format, stem, (1000, 420), draw_rate(&labels, &years, &measures));
+}
+
+/// Colour scale of the heatmaps.
+#[derive(Clone, Copy)]
+enum Scale {
+    Reds,
+    Viridis,
+}
+
+impl Scale {
+    fn color(&self, value: f64, min: f64, max: f64) -> RGBColor {
+        let h = if max > min { ((value - min) / (max - min)).clamp(0., 1.) } else { 0. };
+
+        match self {
+            Scale::Reds => {
+                let (from, to) = ((255., 245., 240.), (103., 0., 13.));
+                let mix = |a: f64, b: f64| (a + (b - a) * h).round() as u8;
+
+                RGBColor(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+            },
+            Scale::Viridis => ViridisRGB.get_color(h as f32),
+        }
+    }
+}
+
+/// Vertical bar of the colours of `scale` over `range`, leaving `top` and `bottom` pixels around it.
+fn colorbar<DB>(area: &DrawingArea<DB, Shift>, scale: Scale, range: (f64, f64), desc: &str, (top, bottom): (u32, u32)) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    let mut colorbar = ChartBuilder::on(area)
+        .margin(10)
+        .margin_top(top)
+        .x_label_area_size(bottom)
+        .y_label_area_size(0)
+        .right_y_label_area_size(50)
+        .build_cartesian_2d(0. ..1., finite(range.0..range.1)?)?
+        .set_secondary_coord(0. ..1., range.0..range.1);
+
+    let steps = 100;
+    colorbar.draw_series((0..steps).map(|k| {
+        let (a, b) = (k as f64 / steps as f64, (k + 1) as f64 / steps as f64);
+        let (y0, y1) = (range.0 + a * (range.1 - range.0), range.0 + b * (range.1 - range.0));
+
+        Rectangle::new([(0., y0), (1., y1)], scale.color((y0 + y1) / 2., range.0, range.1).filled())
+    }))?;
+    colorbar.configure_secondary_axes()
+        .x_labels(0)
+        .y_desc(desc)
+        .label_style((FONT, 10))
+        .draw()?;
+
+    Ok(())
+}
+
+/// Square heatmap between positions, with the first row at the top, optional marks and a colour bar.
+#[allow(clippy::too_many_arguments)]
+fn heatmap<DB>(root: DrawingArea<DB, Shift>, title: &str, positions: &[usize], values: &[Vec<f64>], marks: &[(usize, usize)], scale: Scale, range: (f64, f64), bar: &str) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    let (area, side) = root.split_horizontally(root.dim_in_pixel().0 * 5 / 6);
+
+    let n = positions.len();
+    let label = |x: &f64| {
+        let i = x.round();
+        if (x - i).abs() < 1e-6 && i >= 0. && (i as usize) < n { positions[i as usize].to_string() } else { String::new() }
+    };
+    let flipped = |y: &f64| label(&(n as f64 - 1. - y));
+
+    let mut chart = ChartBuilder::on(&area)
+        .margin(10)
+        .caption(title, (FONT, 16))
+        .x_label_area_size(50)
+        .y_label_area_size(50)
+        .build_cartesian_2d(finite(-0.5..(n as f64 - 0.5))?, finite(-0.5..(n as f64 - 0.5))?)?;
+
+    chart.configure_mesh()
+        .disable_mesh()
+        .x_labels(20.min(n))
+        .y_labels(20.min(n))
+        .x_label_formatter(&label)
+        .y_label_formatter(&flipped)
+        .label_style((FONT, 10))
+        .draw()?;
+
+    let row = |i: usize| (n - 1 - i) as f64;
+    for (i, values) in values.iter().enumerate() {
+        chart.draw_series(values.iter().enumerate().filter(|(_, v)| !v.is_nan()).map(|(j, v)| {
+            Rectangle::new([(j as f64 - 0.5, row(i) - 0.5), (j as f64 + 0.5, row(i) + 0.5)], scale.color(*v, range.0, range.1).filled())
+        }))?;
+    }
+    chart.draw_series(marks.iter().map(|(i, j)| Circle::new((*j as f64, row(*i)), 3, HIGHLIGHT.filled())))?;
+
+    colorbar(&side, scale, range, bar, (40, 50))?;
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// Linkage disequilibrium between the variable sites of a sample, r² above the diagonal and |D'| below it.
+pub fn linkage2graph(path: String, bam: &Bam, linkage: &Linkage, format: PlotFormat) {
+    let first = bam.get_range().0 as usize + 1;
+    let positions: Vec<usize> = linkage.get_sites().iter().map(|x| first + x.column).collect();
+
+    let mut values = vec![vec![f64::NAN; positions.len()]; positions.len()];
+    for x in linkage.get_pairs() {
+        values[x.a][x.b] = x.r_squared;
+        values[x.b][x.a] = x.d_prime.abs();
+    }
+
+    let stem = path + "/" + bam.name.as_str() + "-ld";
+    render!(format, stem, (840, 700), heatmap("linkage disequilibrium", &positions, &values, &[], Scale::Reds, (0., 1.), "r² (upper) / |D'| (lower)"));
+}
+
+/// Contact map of the APC-corrected mutual information between the variable sites of a sample,
+/// marking the significant pairs.
+pub fn covariation2graph(path: String, bam: &Bam, covariation: &Covariation, format: PlotFormat) {
+    let first = bam.get_range().0 as usize + 1;
+    let columns = covariation.get_columns();
+    let positions: Vec<usize> = columns.iter().map(|x| first + x).collect();
+    let values: Vec<Vec<f64>> = covariation.contact_map().outer_iter().map(|x| x.to_vec()).collect();
+
+    let mut marks: Vec<(usize, usize)> = Vec::new();
+    for x in covariation.get_couplings().iter().filter(|x| x.q_value <= 0.05) {
+        let (i, j) = (columns.binary_search(&x.a).unwrap(), columns.binary_search(&x.b).unwrap());
+        marks.push((i, j));
+        marks.push((j, i));
+    }
+
+    let finite = values.iter().flatten().filter(|x| x.is_finite());
+    let range = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |acc, x| (acc.0.min(*x), acc.1.max(*x)));
+    let range = if range.1 > range.0 { range } else { (0., 1.) };
+
+    let stem = path + "/" + bam.name.as_str() + "-mi";
+    render!(format, stem, (840, 700), heatmap("mutual information (APC)", &positions, &values, &marks, Scale::Viridis, range, "MIp (bits)"));
+}
+
+/// Colours of A, C, G and T in sequence logos.
+const BASE_COLORS: [RGBColor; 4] = [
+    RGBColor(0x10, 0x96, 0x48), RGBColor(0x25, 0x5c, 0x99), RGBColor(0xf7, 0xb3, 0x2b), RGBColor(0xd6, 0x28, 0x39),
+];
+
+type Outline = Vec<(f64, f64)>;
+
+/// Outline of a letter in a unit square, drawn with its holes in white.
+fn glyph(base: u8) -> (Vec<Outline>, Vec<Outline>) {
+    // a ring of an ellipse between two angles (in degrees), as used by C and G
+    let ring = |from: f64, to: f64| {
+        let steps = 24;
+        let angles = (0..=steps).map(|i| (from + (to - from) * i as f64 / steps as f64).to_radians()).collect::<Vec<f64>>();
+
+        angles.iter()
+            .map(|a| (0.5 + 0.5 * a.cos(), 0.5 + 0.5 * a.sin()))
+            .chain(angles.iter().rev().map(|a| (0.5 + 0.31 * a.cos(), 0.5 + 0.33 * a.sin())))
+            .collect::<Outline>()
+    };
+    let rectangle = |x0: f64, y0: f64, x1: f64, y1: f64| vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+
+    match base {
+        0 => (
+            vec![vec![(0., 0.), (0.4, 1.), (0.6, 1.), (1., 0.), (0.78, 0.), (0.68, 0.27), (0.32, 0.27), (0.22, 0.)]],
+            vec![vec![(0.39, 0.43), (0.61, 0.43), (0.5, 0.73)]],
+        ),
+        1 => (vec![ring(45., 315.)], Vec::new()),
+        2 => (vec![ring(40., 360.), rectangle(0.55, 0.38, 1., 0.52), rectangle(0.84, 0.1, 1., 0.52)], Vec::new()),
+        _ => (vec![rectangle(0., 0.84, 1., 1.), rectangle(0.42, 0., 0.58, 0.84)], Vec::new()),
+    }
+}
+
+fn draw_logo<DB>(root: DrawingArea<DB, Shift>, logos: &[(String, Logo)]) -> PlotResult
+    where DB: DrawingBackend, DB::ErrorType: 'static
+{
+    root.fill(&WHITE)?;
+
+    for (area, (name, logo)) in root.split_evenly((logos.len(), 1)).iter().zip(logos) {
+        let (first, last) = match (logo.columns.first(), logo.columns.last()) {
+            (Some(first), Some(last)) => (first.position as f64, last.position as f64),
+            _ => continue,
+        };
+        let y_desc = match logo.kind {
+            LogoKind::Information => "bits",
+            LogoKind::Frequency => "frequency",
+        };
+
+        let mut chart = ChartBuilder::on(area)
+            .caption(name, (FONT, 16))
+            .margin(5)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d(finite((first - 0.6)..(last + 0.6))?, finite(0. ..logo.kind.max_height())?)?;
+
+        chart.configure_mesh()
+            .disable_x_mesh()
+            .light_line_style(TRANSPARENT)
+            .bold_line_style(grid())
+            .x_labels(logo.columns.len().min(30))
+            .x_label_formatter(&|x| format!("{:.0}", x))
+            .y_desc(y_desc)
+            .label_style((FONT, 12))
+            .draw()?;
+
+        for column in &logo.columns {
+            let mut bottom = 0.;
+
+            for (base, height) in column.stacked() {
+                let place = |(x, y): &(f64, f64)| (column.position as f64 - 0.45 + 0.9 * x, bottom + height * y);
+                let (shapes, holes) = glyph(base);
+
+                chart.draw_series(shapes.iter().map(|x| Polygon::new(x.iter().map(place).collect::<Vec<_>>(), BASE_COLORS[base as usize].filled())))?;
+                chart.draw_series(holes.iter().map(|x| Polygon::new(x.iter().map(place).collect::<Vec<_>>(), WHITE.filled())))?;
+
+                bottom += height;
+            }
+        }
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+/// Sequence logos of the same region of every sample, stacked to compare them, in `logo-<start>-<end>`.
+pub fn logo2graph(path: String, logos: &[(String, Logo)], format: PlotFormat) {
+    let (start, end) = match logos.first().map(|x| &x.1.columns) {
+        Some(columns) if !columns.is_empty() => (columns[0].position, columns[columns.len() - 1].position),
+        _ => return,
+    };
+
+    let width = (120 + 28 * (end - start + 1)).clamp(600, 2400) as u32;
+    let stem = format!("{}/logo-{}-{}", path, start, end);
+    render!(format, stem, (width, 40 + 180 * logos.len() as u32), draw_logo(logos));
+}
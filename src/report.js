@@ -0,0 +1,581 @@
+"use strict";
+
+// Interactive charts of the report, drawn as SVG from the DATA object embedded in the page.
+
+const NS = "http://www.w3.org/2000/svg";
+const COLORS = [
+  "#e6194b", "#3cb44b", "#ffe119", "#4363d8", "#f58231", "#911eb4", "#46f0f0", "#f032e6",
+  "#bcf60c", "#fabebe", "#008080", "#e6beff", "#9a6324", "#fffac8", "#800000", "#aaffc3",
+  "#808000", "#ffd8b1", "#000075", "#808080", "#000000",
+];
+const VIRIDIS = [[68, 1, 84], [59, 82, 139], [33, 145, 140], [94, 201, 98], [253, 231, 37]];
+const MARGIN = { top: 16, right: 24, bottom: 48, left: 72 };
+
+function element(name, attributes, parent) {
+  const node = document.createElementNS(NS, name);
+  for (const [key, value] of Object.entries(attributes || {})) {
+    node.setAttribute(key, value);
+  }
+  if (parent) {
+    parent.appendChild(node);
+  }
+  return node;
+}
+
+function label(parent, x, y, content, attributes) {
+  const node = element("text", Object.assign({ x: x, y: y }, attributes || {}), parent);
+  node.textContent = content;
+  return node;
+}
+
+function control(name, attributes, parent, content) {
+  const node = document.createElement(name);
+  for (const [key, value] of Object.entries(attributes || {})) {
+    node.setAttribute(key, value);
+  }
+  if (content !== undefined) {
+    node.textContent = content;
+  }
+  parent.appendChild(node);
+  return node;
+}
+
+function format(x) {
+  if (x === null || x === undefined || !isFinite(x)) {
+    return "n/a";
+  }
+  if (Number.isInteger(x)) {
+    return x.toLocaleString("en");
+  }
+  const magnitude = Math.abs(x);
+  if (magnitude !== 0 && (magnitude < 1e-3 || magnitude >= 1e5)) {
+    return x.toExponential(2);
+  }
+  return String(+x.toPrecision(4));
+}
+
+// Round values between lo and hi, about count of them.
+function ticks(lo, hi, count) {
+  const span = hi - lo;
+  if (!(span > 0)) {
+    return [lo];
+  }
+  const magnitude = Math.pow(10, Math.floor(Math.log10(span / count)));
+  const step = [1, 2, 5, 10].map(x => x * magnitude).find(x => span / x <= count);
+  const values = [];
+  for (let x = Math.ceil(lo / step) * step; x <= hi + step * 1e-9; x += step) {
+    values.push(+x.toPrecision(12));
+  }
+  return values;
+}
+
+function viridis(t) {
+  const x = Math.min(Math.max(t, 0), 1) * (VIRIDIS.length - 1);
+  const i = Math.min(Math.floor(x), VIRIDIS.length - 2);
+  const [a, b] = [VIRIDIS[i], VIRIDIS[i + 1]];
+  const mix = a.map((c, k) => Math.round(c + (b[k] - c) * (x - i)));
+  return `rgb(${mix[0]},${mix[1]},${mix[2]})`;
+}
+
+// Colours of the metadata groups, in order of appearance; samples without a group are blue.
+function groupColors(groups) {
+  const names = [...new Set(groups.filter(x => x !== null))];
+  const colors = new Map(names.map((x, i) => [x, COLORS[i % COLORS.length]]));
+  return { names: names, color: group => group === null ? COLORS[3] : colors.get(group) };
+}
+
+const tooltip = control("div", { class: "tooltip" }, document.body);
+
+function showTooltip(event, lines) {
+  tooltip.replaceChildren();
+  for (const line of lines) {
+    control("div", {}, tooltip, line);
+  }
+  tooltip.style.display = "block";
+  tooltip.style.left = `${event.clientX + 14}px`;
+  tooltip.style.top = `${event.clientY + 14}px`;
+}
+
+function hideTooltip() {
+  tooltip.style.display = "none";
+}
+
+// Position of the pointer in the coordinates of the viewBox.
+function pointer(svg, event) {
+  const point = svg.createSVGPoint();
+  point.x = event.clientX;
+  point.y = event.clientY;
+  return point.matrixTransform(svg.getScreenCTM().inverse());
+}
+
+function linear(domain, range) {
+  const scale = x => range[0] + (x - domain[0]) / (domain[1] - domain[0] || 1) * (range[1] - range[0]);
+  scale.invert = y => domain[0] + (y - range[0]) / (range[1] - range[0]) * (domain[1] - domain[0]);
+  return scale;
+}
+
+// Grid, ticks and labels of a chart in box, for the x and y scales.
+function axes(svg, box, x, y, options) {
+  const grid = element("g", { class: "grid" }, svg);
+  const xTicks = options.xTicks || ticks(x.domain[0], x.domain[1], 8);
+  const yTicks = options.yTicks || ticks(y.domain[0], y.domain[1], 5);
+
+  for (const value of xTicks) {
+    const px = x(value);
+    element("line", { x1: px, x2: px, y1: box.top, y2: box.bottom }, grid);
+    label(svg, px, box.bottom + 16, (options.xFormat || format)(value), { class: "tick", "text-anchor": "middle" });
+  }
+  for (const value of yTicks) {
+    const py = y(value);
+    element("line", { x1: box.left, x2: box.right, y1: py, y2: py }, grid);
+    label(svg, box.left - 6, py + 4, (options.yFormat || format)(value), { class: "tick", "text-anchor": "end" });
+  }
+
+  element("rect", { x: box.left, y: box.top, width: box.right - box.left, height: box.bottom - box.top, class: "frame" }, svg);
+  if (options.xLabel) {
+    label(svg, (box.left + box.right) / 2, box.bottom + 38, options.xLabel, { class: "axis", "text-anchor": "middle" });
+  }
+  if (options.yLabel) {
+    const py = (box.top + box.bottom) / 2;
+    label(svg, 18, py, options.yLabel, { class: "axis", "text-anchor": "middle", transform: `rotate(-90 18 ${py})` });
+  }
+}
+
+// Rows of the annotated regions, so that regions on the same row do not overlap.
+function stack(regions) {
+  const ends = [];
+  return regions.map(region => {
+    let row = ends.findIndex(end => end < region.start);
+    if (row < 0) {
+      row = ends.length;
+      ends.push(region.end);
+    } else {
+      ends[row] = region.end;
+    }
+    return row;
+  });
+}
+
+// Values along the positions, one line per series, zoomed by dragging over the positions or with
+// the wheel, and reset by a double click.
+//
+// options: { first, series: [{ name, color, values }], xLabel, yLabel, yMax, log, select, regions }
+function lineChart(container, options) {
+  const width = 960;
+  const regions = options.regions || [];
+  const rows = stack(regions);
+  const tracks = regions.length ? Math.max(...rows) + 1 : 0;
+  const box = { left: MARGIN.left, right: width - MARGIN.right, top: MARGIN.top, bottom: 300 };
+  const height = box.bottom + MARGIN.bottom + (tracks ? 12 + 22 * tracks : 0);
+
+  const length = Math.max(0, ...options.series.map(s => s.values.length));
+  const full = [options.first, options.first + Math.max(length - 1, 1)];
+  let view = full.slice();
+  let shown = options.series.map((_, i) => !options.select || i === 0);
+  let log = false;
+
+  const bar = control("div", { class: "controls" }, container);
+  if (options.select && options.series.length > 1) {
+    const select = control("select", {}, bar);
+    control("option", { value: "all" }, select, "All samples");
+    options.series.forEach((s, i) => control("option", { value: i }, select, s.name));
+    select.value = "0";
+    select.addEventListener("change", () => {
+      shown = options.series.map((_, i) => select.value === "all" || String(i) === select.value);
+      draw();
+    });
+  }
+  if (options.log) {
+    const toggle = control("label", {}, bar);
+    const checkbox = control("input", { type: "checkbox" }, toggle);
+    toggle.appendChild(document.createTextNode(" log scale"));
+    checkbox.addEventListener("change", () => {
+      log = checkbox.checked;
+      draw();
+    });
+  }
+  const reset = control("button", { type: "button" }, bar, "Reset zoom");
+  reset.addEventListener("click", () => {
+    view = full.slice();
+    draw();
+  });
+  control("span", { class: "hint" }, bar, "Drag over the chart or use the wheel to zoom, double-click to reset.");
+
+  const svg = element("svg", { viewBox: `0 0 ${width} ${height}`, class: "chart" }, container);
+  let x = null;
+
+  function transform(v) {
+    if (v === null || !isFinite(v)) {
+      return null;
+    }
+    if (log) {
+      return v > 0 ? Math.log10(v) : null;
+    }
+    return v;
+  }
+
+  // Path of a series in view, keeping the extremes of the values falling on the same pixel.
+  function path(values, y) {
+    const [lo, hi] = [Math.max(0, Math.floor(view[0] - options.first)), Math.min(values.length - 1, Math.ceil(view[1] - options.first))];
+    const bins = Math.max(1, Math.floor((box.right - box.left) / 2));
+    const per = Math.max(1, (hi - lo + 1) / bins);
+    let d = "";
+    let pen = false;
+
+    for (let start = lo; start <= hi; start += per) {
+      const stop = Math.min(hi + 1, Math.floor(start + per));
+      let [min, max, at, last] = [Infinity, -Infinity, 0, 0];
+      for (let i = Math.floor(start); i < Math.max(stop, Math.floor(start) + 1); i++) {
+        const v = transform(values[i]);
+        if (v === null) {
+          continue;
+        }
+        if (v < min) {
+          min = v;
+        }
+        if (v > max) {
+          max = v;
+        }
+        [at, last] = [i, v];
+      }
+      if (min === Infinity) {
+        pen = false;
+        continue;
+      }
+
+      const px = x(options.first + (per > 1 ? at : Math.floor(start))).toFixed(1);
+      d += `${pen ? "L" : "M"}${px},${y(min).toFixed(1)}`;
+      if (max !== min) {
+        d += `L${px},${y(max).toFixed(1)}L${px},${y(last).toFixed(1)}`;
+      }
+      pen = true;
+    }
+
+    return d;
+  }
+
+  function draw() {
+    svg.replaceChildren();
+    x = linear(view, [box.left, box.right]);
+    x.domain = view;
+
+    const visible = options.series.filter((_, i) => shown[i]);
+    let top = options.yMax;
+    if (top === undefined || log) {
+      const [lo, hi] = [Math.max(0, Math.floor(view[0] - options.first)), Math.ceil(view[1] - options.first)];
+      top = visible.reduce((max, s) => s.values.slice(lo, hi + 1).reduce((m, v) => Math.max(m, transform(v) ?? m), max), 0);
+      top = top > 0 ? top * 1.05 : 1;
+    }
+    const y = linear([0, top], [box.bottom, box.top]);
+    y.domain = [0, top];
+
+    const integer = value => String(Math.round(value));
+    axes(svg, box, x, y, {
+      xLabel: options.xLabel,
+      yLabel: log ? `${options.yLabel} (log10)` : options.yLabel,
+      xTicks: ticks(view[0], view[1], 8).filter(Number.isInteger),
+      xFormat: integer,
+      yFormat: log ? (v => format(Math.pow(10, v))) : format,
+    });
+
+    const clip = `clip-${container.id}`;
+    element("rect", { x: box.left, y: box.top, width: box.right - box.left, height: box.bottom - box.top }, element("clipPath", { id: clip }, svg));
+    const lines = element("g", { "clip-path": `url(#${clip})` }, svg);
+    options.series.forEach((s, i) => {
+      if (shown[i]) {
+        element("path", { d: path(s.values, y), stroke: s.color, class: "line" }, lines);
+      }
+    });
+
+    if (tracks) {
+      const tracksTop = box.bottom + MARGIN.bottom + 12;
+      const group = element("g", { "clip-path": `url(#${clip}-tracks)` }, svg);
+      element("rect", { x: box.left, y: tracksTop - 4, width: box.right - box.left, height: 22 * tracks + 8 }, element("clipPath", { id: `${clip}-tracks` }, svg));
+
+      regions.forEach((region, i) => {
+        const [start, end] = [x(region.start), x(region.end)];
+        const middle = tracksTop + 22 * rows[i] + 9;
+        const head = Math.min(10, end - start);
+        const [tip, base, neck] = region.reverse ? [start, end, start + head] : [end, start, end - head];
+        const points = [[base, middle - 5], [neck, middle - 5], [neck, middle - 8], [tip, middle], [neck, middle + 8], [neck, middle + 5], [base, middle + 5]];
+        const arrow = element("polygon", { points: points.map(p => p.join(",")).join(" "), fill: COLORS[i % COLORS.length], class: "region" }, group);
+        arrow.addEventListener("click", () => {
+          view = [Math.max(full[0], region.start), Math.min(full[1], region.end)];
+          draw();
+        });
+        arrow.addEventListener("mousemove", event => showTooltip(event, [region.name, `${format(region.start)}–${format(region.end)}`, "Click to zoom in"]));
+        arrow.addEventListener("mouseleave", hideTooltip);
+        if (end - start > 8 * region.name.length) {
+          label(group, (start + end) / 2, middle - 10, region.name, { class: "tick", "text-anchor": "middle" });
+        }
+      });
+    }
+
+    const guide = element("line", { y1: box.top, y2: box.bottom, class: "guide", visibility: "hidden" }, svg);
+    const selection = element("rect", { y: box.top, height: box.bottom - box.top, class: "selection", visibility: "hidden" }, svg);
+    const area = element("rect", { x: box.left, y: box.top, width: box.right - box.left, height: box.bottom - box.top, class: "area" }, svg);
+    let anchor = null;
+
+    area.addEventListener("mousedown", event => {
+      anchor = pointer(svg, event).x;
+      event.preventDefault();
+    });
+    area.addEventListener("mousemove", event => {
+      const px = Math.min(Math.max(pointer(svg, event).x, box.left), box.right);
+      if (anchor !== null) {
+        selection.setAttribute("x", Math.min(anchor, px));
+        selection.setAttribute("width", Math.abs(px - anchor));
+        selection.setAttribute("visibility", "visible");
+      }
+
+      const position = Math.round(x.invert(px));
+      guide.setAttribute("x1", x(position));
+      guide.setAttribute("x2", x(position));
+      guide.setAttribute("visibility", "visible");
+      const values = options.series
+        .filter((_, i) => shown[i])
+        .slice(0, 12)
+        .map(s => `${s.name}: ${format(s.values[position - options.first])}`);
+      showTooltip(event, [`Position ${format(position)}`].concat(values));
+    });
+    area.addEventListener("mouseup", event => {
+      if (anchor !== null) {
+        const px = Math.min(Math.max(pointer(svg, event).x, box.left), box.right);
+        const [a, b] = [x.invert(Math.min(anchor, px)), x.invert(Math.max(anchor, px))].map(Math.round);
+        anchor = null;
+        if (b - a >= 2) {
+          view = [a, b];
+          draw();
+        } else {
+          selection.setAttribute("visibility", "hidden");
+        }
+      }
+    });
+    area.addEventListener("mouseleave", () => {
+      anchor = null;
+      selection.setAttribute("visibility", "hidden");
+      guide.setAttribute("visibility", "hidden");
+      hideTooltip();
+    });
+    area.addEventListener("dblclick", () => {
+      view = full.slice();
+      draw();
+    });
+    area.addEventListener("wheel", event => {
+      event.preventDefault();
+      const at = x.invert(pointer(svg, event).x);
+      const factor = event.deltaY < 0 ? 0.8 : 1.25;
+      const span = Math.min(full[1] - full[0], Math.max(10, (view[1] - view[0]) * factor));
+      let lo = Math.round(at - (at - view[0]) * span / (view[1] - view[0]));
+      lo = Math.min(Math.max(lo, full[0]), full[1] - span);
+      view = [lo, lo + span];
+      draw();
+    }, { passive: false });
+  }
+
+  draw();
+}
+
+// Bars with their confidence intervals, coloured by group.
+//
+// options: { labels, values, lower, upper, groups, yLabel }
+function barChart(container, options) {
+  const n = options.labels.length;
+  const width = Math.max(480, Math.min(960, 120 + 40 * n));
+  const longest = Math.max(0, ...options.labels.map(x => x.length));
+  const rotate = n > 8 || longest > 12;
+  const box = { left: MARGIN.left, right: width - MARGIN.right, top: MARGIN.top, bottom: 280 };
+  const height = box.bottom + (rotate ? 24 + 6 * Math.min(longest, 30) : MARGIN.bottom);
+  const svg = element("svg", { viewBox: `0 0 ${width} ${height}`, class: "chart", style: `max-width: ${width}px` }, container);
+
+  const top = Math.max(0, ...options.values.concat(options.upper).filter(v => v !== null && isFinite(v))) * 1.1 || 1;
+  const band = (box.right - box.left) / Math.max(n, 1);
+  const x = i => box.left + band * (i + 0.5);
+  const y = linear([0, top], [box.bottom, box.top]);
+  y.domain = [0, top];
+  const colors = groupColors(options.groups);
+
+  axes(svg, box, x, y, { xTicks: [], yLabel: options.yLabel });
+
+  options.labels.forEach((name, i) => {
+    const value = options.values[i];
+    const rect = element("rect", {
+      x: x(i) - band * 0.35, width: band * 0.7,
+      y: y(Math.max(value, 0)), height: Math.max(0, box.bottom - y(Math.max(value, 0))),
+      fill: colors.color(options.groups[i]), class: "bar",
+    }, svg);
+    const [lo, hi] = [options.lower[i], options.upper[i]];
+    if (lo !== null && hi !== null) {
+      const whisker = Math.min(8, band * 0.2);
+      element("path", {
+        d: `M${x(i)},${y(lo)}V${y(hi)}M${x(i) - whisker},${y(lo)}h${2 * whisker}M${x(i) - whisker},${y(hi)}h${2 * whisker}`,
+        class: "whisker",
+      }, svg);
+    }
+    rect.addEventListener("mousemove", event => showTooltip(event, [
+      name,
+      options.groups[i] === null ? "no group" : `group ${options.groups[i]}`,
+      `α = ${format(value)}`,
+      `95% CI ${format(lo)}–${format(hi)}`,
+    ]));
+    rect.addEventListener("mouseleave", hideTooltip);
+
+    if (rotate) {
+      label(svg, x(i), box.bottom + 12, name, { class: "tick", "text-anchor": "end", transform: `rotate(-45 ${x(i)} ${box.bottom + 12})` });
+    } else {
+      label(svg, x(i), box.bottom + 16, name, { class: "tick", "text-anchor": "middle" });
+    }
+  });
+
+  legend(container, colors.names.map(name => [name, colors.color(name)]));
+}
+
+function legend(container, entries) {
+  if (!entries.length) {
+    return;
+  }
+  const list = control("div", { class: "legend" }, container);
+  for (const [name, color] of entries) {
+    const entry = control("span", {}, list);
+    control("i", { style: `background: ${color}` }, entry);
+    entry.appendChild(document.createTextNode(name));
+  }
+}
+
+// Symmetric matrix of distances between samples, with a colour bar.
+//
+// options: { labels, matrix, title }
+function heatmap(container, options) {
+  const n = options.labels.length;
+  const cell = Math.max(4, Math.min(40, Math.floor(480 / Math.max(n, 1))));
+  const named = cell >= 10;
+  const margin = named ? 8 + 7 * Math.min(24, Math.max(0, ...options.labels.map(x => x.length))) : 8;
+  const size = margin + cell * n;
+  const svg = element("svg", { viewBox: `0 0 ${size + 110} ${size + 8}`, class: "chart", style: `max-width: ${size + 110}px` }, container);
+
+  const values = options.matrix.flat().filter(v => v !== null && isFinite(v));
+  const [lo, hi] = [Math.min(0, ...values), Math.max(0, ...values)];
+  const scale = v => viridis(hi > lo ? (v - lo) / (hi - lo) : 0);
+
+  options.matrix.forEach((row, i) => row.forEach((value, j) => {
+    const rect = element("rect", {
+      x: margin + cell * j, y: cell * i, width: cell, height: cell,
+      fill: value === null ? "#ffffff" : scale(value), class: "cell",
+    }, svg);
+    rect.addEventListener("mousemove", event => showTooltip(event, [`${options.labels[i]} × ${options.labels[j]}`, `${options.title}: ${format(value)}`]));
+    rect.addEventListener("mouseleave", hideTooltip);
+  }));
+
+  if (named) {
+    options.labels.forEach((name, i) => {
+      const py = cell * i + cell / 2 + 4;
+      label(svg, margin - 6, py, name, { class: "tick", "text-anchor": "end" });
+    });
+  }
+
+  const gradient = element("linearGradient", { id: `${container.id}-gradient`, x1: 0, x2: 0, y1: 1, y2: 0 }, element("defs", {}, svg));
+  VIRIDIS.forEach((_, k) => element("stop", { offset: k / (VIRIDIS.length - 1), "stop-color": viridis(k / (VIRIDIS.length - 1)) }, gradient));
+  const [barX, barHeight] = [size + 20, Math.min(cell * n, 240)];
+  element("rect", { x: barX, y: 0, width: 14, height: barHeight, fill: `url(#${container.id}-gradient)`, class: "frame" }, svg);
+  label(svg, barX + 20, 10, format(hi), { class: "tick" });
+  label(svg, barX + 20, barHeight, format(lo), { class: "tick" });
+}
+
+// Samples on two principal coordinates, coloured by group.
+//
+// options: { labels, x, y, groups, xLabel, yLabel }
+function scatter(container, options) {
+  const width = 640;
+  const box = { left: MARGIN.left, right: width - MARGIN.right, top: MARGIN.top, bottom: 400 };
+  const svg = element("svg", { viewBox: `0 0 ${width} ${box.bottom + MARGIN.bottom}`, class: "chart", style: `max-width: ${width}px` }, container);
+
+  const padded = values => {
+    const [lo, hi] = [Math.min(...values), Math.max(...values)];
+    const margin = hi > lo ? (hi - lo) * 0.1 : 1;
+    return [lo - margin, hi + margin];
+  };
+  const x = linear(padded(options.x), [box.left, box.right]);
+  x.domain = padded(options.x);
+  const y = linear(padded(options.y), [box.bottom, box.top]);
+  y.domain = padded(options.y);
+  const colors = groupColors(options.groups);
+
+  axes(svg, box, x, y, { xLabel: options.xLabel, yLabel: options.yLabel });
+
+  options.labels.forEach((name, i) => {
+    const [px, py] = [x(options.x[i]), y(options.y[i])];
+    const point = element("circle", { cx: px, cy: py, r: 6, fill: colors.color(options.groups[i]), class: "point" }, svg);
+    point.addEventListener("mousemove", event => showTooltip(event, [
+      name,
+      options.groups[i] === null ? "no group" : `group ${options.groups[i]}`,
+      `${options.xLabel}: ${format(options.x[i])}`,
+      `${options.yLabel}: ${format(options.y[i])}`,
+    ]));
+    point.addEventListener("mouseleave", hideTooltip);
+    if (options.labels.length <= 40) {
+      label(svg, px + 9, py + 4, name, { class: "tick" });
+    }
+  });
+
+  legend(container, colors.names.map(name => [name, colors.color(name)]));
+}
+
+function render() {
+  const samples = DATA.samples;
+  const series = key => samples.map((s, i) => ({ name: s.name, color: COLORS[i % COLORS.length], values: s[key] }));
+
+  if (!samples.length) {
+    return;
+  }
+
+  lineChart(document.getElementById("efficiency"), {
+    first: DATA.first,
+    series: series("efficiency"),
+    xLabel: "Position",
+    yLabel: "Efficiency",
+    yMax: 1,
+    select: true,
+    regions: DATA.regions,
+  });
+
+  lineChart(document.getElementById("coverage"), {
+    first: DATA.first,
+    series: series("depth"),
+    xLabel: "Position",
+    yLabel: "Depth",
+    log: true,
+    select: true,
+    regions: DATA.regions,
+  });
+
+  barChart(document.getElementById("alpha"), {
+    labels: samples.map(s => s.name),
+    values: samples.map(s => s.alpha),
+    lower: samples.map(s => s.ci[0]),
+    upper: samples.map(s => s.ci[1]),
+    groups: samples.map(s => s.group),
+    yLabel: "α-diversity",
+  });
+
+  if (samples.length > 1) {
+    heatmap(document.getElementById("beta"), {
+      labels: samples.map(s => s.name),
+      matrix: DATA.beta.matrix,
+      title: DATA.beta.distance,
+    });
+
+    if (DATA.pcoa) {
+      const explained = DATA.pcoa.explained.map(p => ` (${(100 * p).toFixed(1)}%)`);
+      scatter(document.getElementById("pcoa"), {
+        labels: samples.map(s => s.name),
+        x: DATA.pcoa.coordinates.map(c => c[0]),
+        y: DATA.pcoa.coordinates.map(c => c[1]),
+        groups: samples.map(s => s.group),
+        xLabel: `PCo1${explained[0] || ""}`,
+        yLabel: `PCo2${explained[1] || ""}`,
+      });
+    }
+  }
+}
+
+render();
@@ -0,0 +1,249 @@
+use std::fmt::Write;
+
+use serde_json::{json, Value};
+
+use qsalib::prelude::*;
+
+const STYLE: &str = include_str!("report.css");
+const SCRIPT: &str = include_str!("report.js");
+
+/// Mean depth under which a sample is flagged as shallow.
+const LOW_DEPTH: f64 = 100.;
+/// Fraction of hypermutated reads above which a sample is flagged.
+const HYPERMUTATED: f64 = 0.05;
+
+/// Escapes the characters with a meaning in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A finite value rounded to a few decimals, to keep the embedded data small, or null.
+fn rounded(x: f64) -> Value {
+    if x.is_finite() { json!((x * 1e4).round() / 1e4) } else { Value::Null }
+}
+
+/// Problems worth checking before trusting the results of a sample.
+fn sample_warnings(summary: &SampleSummary, threshold: f64) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let name = summary.sample.as_str();
+
+    let reads = summary.read_counts.kept();
+
+    if reads == 0 {
+        warnings.push(format!("{}: no reads fall in the analysed range.", name));
+    } else if summary.mean_depth < LOW_DEPTH {
+        warnings.push(format!("{}: the mean depth ({:.1}) is below {}, so minor variants are unreliable.", name, summary.mean_depth, LOW_DEPTH));
+    }
+    if summary.uncovered > 0 {
+        warnings.push(format!("{}: {} of {} positions are not covered by any read.", name, summary.uncovered, summary.positions));
+    }
+    if threshold > 0. && summary.masked > summary.uncovered {
+        let below = summary.masked - summary.uncovered;
+        warnings.push(format!(
+            "{}: {} positions ({:.1}%) have a coverage below {} of the maximum.",
+            name, below, 100. * below as f64 / summary.positions as f64, threshold,
+        ));
+    }
+    if reads > 0 && summary.hypermutated as f64 / reads as f64 > HYPERMUTATED {
+        warnings.push(format!("{}: {:.1}% of the reads are hypermutated.", name, 100. * summary.hypermutated as f64 / reads as f64));
+    }
+
+    warnings
+}
+
+fn table(html: &mut String, header: &[&str], rows: &[Vec<(String, bool)>]) {
+    html.push_str("<table>\n<tr>");
+    for column in header {
+        write!(html, "<th>{}</th>", escape(column)).unwrap();
+    }
+    html.push_str("</tr>\n");
+
+    for row in rows {
+        html.push_str("<tr>");
+        for (cell, number) in row {
+            let class = if *number { " class=\"number\"" } else { "" };
+            write!(html, "<td{}>{}</td>", class, escape(cell)).unwrap();
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+}
+
+/// Data drawn by the charts of the report.
+fn chart_data(data: &BamData, summaries: &[SampleSummary]) -> Value {
+    let names = data.get_names();
+    let first = data.into_iter().next().map(|bam| bam.get_range().0 as usize + 1).unwrap_or(1);
+    let length = summaries.first().map_or(0, |x| x.positions);
+
+    let samples = data.into_iter()
+        .zip(summaries)
+        .zip(data.get_groups())
+        .map(|((bam, summary), group)| json!({
+            "name": summary.sample,
+            "group": group,
+            "efficiency": bam.matrices.get_efficiency().iter().map(|x| rounded(*x)).collect::<Vec<Value>>(),
+            "depth": bam.matrices.get_depth().to_vec(),
+            "alpha": rounded(summary.alpha),
+            "ci": [rounded(summary.alpha_ci.0), rounded(summary.alpha_ci.1)],
+        }))
+        .collect::<Vec<Value>>();
+
+    let regions = match data.get_annotation() {
+        Some(annotation) => annotation.get_regions()
+            .iter()
+            .filter(|x| !x.is_empty() && x.end() >= first && x.start() < first - 1 + length)
+            .map(|x| json!({ "name": x.name, "start": x.start() + 1, "end": x.end(), "reverse": x.strand == Strand::Reverse }))
+            .collect::<Vec<Value>>(),
+        None => Vec::new(),
+    };
+
+    let beta = data.beta_diversity()
+        .rows()
+        .into_iter()
+        .map(|row| row.iter().map(|x| rounded(*x)).collect::<Vec<Value>>())
+        .collect::<Vec<_>>();
+
+    // samples whose distance is not defined cannot be ordinated
+    let pcoa = data.ordination().ok().map(|ordination| {
+        let coordinates = ordination.get_coordinates();
+        let axis = |i: usize, j: usize| if j < coordinates.ncols() { rounded(coordinates[[i, j]]) } else { json!(0.) };
+
+        json!({
+            "coordinates": (0..coordinates.nrows()).map(|i| vec![axis(i, 0), axis(i, 1)]).collect::<Vec<_>>(),
+            "explained": ordination.proportion_explained().iter().take(2).map(|x| rounded(*x)).collect::<Vec<Value>>(),
+        })
+    });
+
+    json!({
+        "first": first,
+        "samples": samples,
+        "regions": regions,
+        "beta": {
+            "distance": data.get_distance().name(),
+            "labels": names,
+            "matrix": beta,
+        },
+        "pcoa": pcoa,
+    })
+}
+
+/// Writes `report.html`, a single page readable offline with the run parameters, a summary of the
+/// inputs, QC warnings and interactive charts of efficiency, depth, α- and β-diversity.
+///
+/// The styles, the scripts and the data are embedded in the page, so it can be sent on its own.
+/// The confidence intervals of α-diversity in `summaries` come from `bootstrap` resamplings of the positions.
+pub fn report2html(path: String, data: &BamData, summaries: &[SampleSummary], parameters: &[(String, String)], threshold: f64, bootstrap: usize) {
+    let groups = data.get_groups();
+
+    let mut warnings = summaries.iter().flat_map(|x| sample_warnings(x, threshold)).collect::<Vec<String>>();
+    if data.get_metadata().is_some() {
+        for (summary, _) in summaries.iter().zip(&groups).filter(|(_, group)| group.is_none()) {
+            warnings.push(format!("{}: the sample has no group in the metadata.", summary.sample));
+        }
+    }
+    if summaries.len() < 2 {
+        warnings.push("β-diversity needs at least two samples.".to_string());
+    } else if let Err(why) = data.finite_beta_diversity() {
+        warnings.push(format!("{}.", why));
+    }
+
+    let range = data.into_iter().next().map(|bam| bam.get_range()).unwrap_or((0, 0));
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>QSA report</title>\n");
+    write!(html, "<style>\n{}</style>\n</head>\n<body>\n", STYLE).unwrap();
+
+    html.push_str("<h1>QuasiSpecies Analyser report</h1>\n");
+    writeln!(
+        html,
+        "<p class=\"subtitle\">qsa {}, {} samples, positions {}–{}</p>",
+        env!("CARGO_PKG_VERSION"), summaries.len(), range.0 + 1, range.1,
+    ).unwrap();
+    html.push_str(
+        "<nav><a href=\"#warnings\">QC warnings</a><a href=\"#inputs\">Inputs</a><a href=\"#qc\">Quality control</a><a href=\"#parameters\">Parameters</a>\
+         <a href=\"#efficiency-section\">Efficiency</a><a href=\"#coverage-section\">Depth</a>\
+         <a href=\"#alpha-section\">α-diversity</a><a href=\"#beta-section\">β-diversity</a></nav>\n"
+    );
+
+    html.push_str("<h2 id=\"warnings\">QC warnings</h2>\n");
+    if warnings.is_empty() {
+        html.push_str("<p class=\"note\">No warnings.</p>\n");
+    } else {
+        html.push_str("<ul class=\"warnings\">\n");
+        for warning in &warnings {
+            writeln!(html, "<li>{}</li>", escape(warning)).unwrap();
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str("<h2 id=\"inputs\">Inputs</h2>\n");
+    let rows = summaries.iter()
+        .zip(&groups)
+        .zip(data)
+        .map(|((x, group), bam)| vec![
+            (x.sample.clone(), false),
+            (group.clone().unwrap_or_default(), false),
+            (bam.get_path().display().to_string(), false),
+            (x.read_counts.kept().to_string(), true),
+            (x.positions.to_string(), true),
+            (format!("{:.4}", x.alpha), true),
+            (format!("{:.4}–{:.4}", x.alpha_ci.0, x.alpha_ci.1), true),
+        ])
+        .collect::<Vec<_>>();
+    table(
+        &mut html,
+        &["Sample", "Group", "File", "Reads", "Positions", "α-diversity", "95% CI"],
+        &rows,
+    );
+
+    html.push_str("<h2 id=\"qc\">Quality control</h2>\n");
+    let rows = summaries.iter()
+        .map(|x| {
+            let mut row = vec![
+                (x.sample.clone(), false),
+                (x.read_counts.processed.to_string(), true),
+                (x.read_counts.unmapped.to_string(), true),
+                (x.read_counts.outside_range.to_string(), true),
+                (x.hypermutated.to_string(), true),
+                (x.excluded.to_string(), true),
+                (format!("{:.1}", x.mean_depth), true),
+                (format!("{:.1}", x.median_depth), true),
+            ];
+            row.extend(x.breadth.iter().map(|b| (format!("{:.1}", 100. * b), true)));
+            row.push((x.masked.to_string(), true));
+
+            row
+        })
+        .collect::<Vec<_>>();
+    let breadth = BREADTH_DEPTHS.iter().map(|x| format!("Breadth {}x (%)", x)).collect::<Vec<String>>();
+    let mut header = vec![
+        "Sample", "Processed reads", "Unmapped", "Outside range", "Hypermutated", "Excluded", "Mean depth", "Median depth",
+    ];
+    header.extend(breadth.iter().map(|x| x.as_str()));
+    header.push("Masked positions");
+    table(&mut html, &header, &rows);
+
+    html.push_str("<h2 id=\"parameters\">Parameters</h2>\n");
+    let rows = parameters.iter()
+        .map(|(name, value)| vec![(name.clone(), false), (value.clone(), false)])
+        .collect::<Vec<_>>();
+    table(&mut html, &["Parameter", "Value"], &rows);
+
+    html.push_str("<h2 id=\"efficiency-section\">Efficiency</h2>\n");
+    html.push_str("<p class=\"note\">Normalised Shannon entropy of the bases at each position.</p>\n<div id=\"efficiency\"></div>\n");
+    html.push_str("<h2 id=\"coverage-section\">Depth</h2>\n");
+    html.push_str("<p class=\"note\">Number of bases read at each position.</p>\n<div id=\"coverage\"></div>\n");
+    html.push_str("<h2 id=\"alpha-section\">α-diversity</h2>\n");
+    writeln!(html, "<p class=\"note\">Mean efficiency of each sample, with 95% confidence intervals from {} resamplings of the positions.</p>", bootstrap).unwrap();
+    html.push_str("<div id=\"alpha\"></div>\n");
+    html.push_str("<h2 id=\"beta-section\">β-diversity</h2>\n");
+    writeln!(html, "<p class=\"note\">Distance between samples ({}) and its principal coordinates.</p>", data.get_distance().name()).unwrap();
+    html.push_str("<div class=\"figures\"><div id=\"beta\"></div><div id=\"pcoa\"></div></div>\n");
+
+    // `</` would end the script element early
+    let json = chart_data(data, summaries).to_string().replace("</", "<\\/");
+    write!(html, "<script>\nconst DATA = {};\n</script>\n<script>\n{}</script>\n</body>\n</html>\n", json, SCRIPT).unwrap();
+
+    std::fs::write(path + "/report.html", html).expect("could not write report");
+}